@@ -0,0 +1,432 @@
+//! Types and helpers shared across the app's zomes. The cart and
+//! product_catalog coordinators exchange these over bridged calls, so
+//! keeping one definition here is what keeps their serialization
+//! compatible.
+
+use hdi::prelude::*;
+
+/// Reference to a single product inside a catalog group: the
+/// ProductGroup create action plus the product's index within it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub struct ProductReference {
+    #[serde(alias = "groupHash")]
+    pub group_hash: ActionHash,
+    pub index: u32,
+}
+
+/// Typed encode/decode for the byte payloads carried in link tags.
+/// Decoding returns `None` for malformed (or legacy) tags, leaving the
+/// fallback to the caller.
+pub trait LinkTagCodec: Sized {
+    fn encode(&self) -> LinkTag;
+    fn decode(tag: &LinkTag) -> Option<Self>;
+}
+
+/// Index of one chunk within a multi-part blob (product images,
+/// delivery proofs), as 4 little-endian bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkTag(pub u32);
+
+impl LinkTagCodec for ChunkTag {
+    fn encode(&self) -> LinkTag {
+        LinkTag::new(self.0.to_le_bytes().to_vec())
+    }
+
+    fn decode(tag: &LinkTag) -> Option<Self> {
+        <[u8; 4]>::try_from(tag.as_ref().as_slice())
+            .ok()
+            .map(|bytes| ChunkTag(u32::from_le_bytes(bytes)))
+    }
+}
+
+/// Count of the items behind a link, as 4 little-endian bytes; what
+/// the catalog's category and brand links carry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CountTag(pub u32);
+
+impl LinkTagCodec for CountTag {
+    fn encode(&self) -> LinkTag {
+        LinkTag::new(self.0.to_le_bytes().to_vec())
+    }
+
+    fn decode(tag: &LinkTag) -> Option<Self> {
+        <[u8; 4]>::try_from(tag.as_ref().as_slice())
+            .ok()
+            .map(|bytes| CountTag(u32::from_le_bytes(bytes)))
+    }
+}
+
+/// Product count encoded into a link tag so counting doesn't require
+/// fetching every linked entry.
+pub fn link_count_tag(count: usize) -> LinkTag {
+    CountTag(count as u32).encode()
+}
+
+/// Decode a count tag; malformed tags count as 0.
+pub fn count_from_tag(tag: &LinkTag) -> usize {
+    CountTag::decode(tag).map_or(0, |CountTag(count)| count as usize)
+}
+
+/// Small deterministic generator backing the dev-only demo seeders;
+/// not cryptographic, just stable across runs so reseeding with the
+/// same spec reproduces the same data.
+pub struct DemoRng(u64);
+
+impl DemoRng {
+    pub fn new(seed: u64) -> Self {
+        DemoRng(seed.wrapping_mul(6364136223846793005).wrapping_add(1))
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> u64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        self.0 >> 16
+    }
+
+    /// A value in `0..bound`; `bound` must be non-zero.
+    pub fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound as u64) as usize
+    }
+}
+
+/// Deployment-level feature switches, read from the DNA's properties
+/// at install time. Every flag defaults to on, so a DNA with no
+/// properties (or properties from before a flag existed) behaves like
+/// the full app; deployments opt out of features rather than in.
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case", default)]
+pub struct FeatureFlags {
+    pub enable_reviews: bool,
+    pub enable_shopper_dispatch: bool,
+    pub enable_promos: bool,
+    pub enable_gift_cards: bool,
+    /// The one opt-in flag: demo-data seeding is for development
+    /// networks only and stays off unless the properties ask for it.
+    pub enable_dev_seeding: bool,
+}
+
+impl Default for FeatureFlags {
+    fn default() -> Self {
+        FeatureFlags {
+            enable_reviews: true,
+            enable_shopper_dispatch: true,
+            enable_promos: true,
+            enable_gift_cards: true,
+            enable_dev_seeding: false,
+        }
+    }
+}
+
+/// Role string on a shopper invite.
+pub const SHOPPER_INVITE_ROLE: &str = "shopper";
+
+/// Role string on a customer invite, for closed pilot networks.
+pub const CUSTOMER_INVITE_ROLE: &str = "customer";
+
+/// What an admin signs when inviting an agent onto the network. Bound
+/// to the invitee's key, so a leaked code admits nobody else — which is
+/// also what makes a code single-use: it admits exactly the key it
+/// names, and a key only ever joins a network once.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct NetworkInvite {
+    pub invitee: AgentPubKey,
+    /// The role the invite admits, e.g. [`SHOPPER_INVITE_ROLE`] or
+    /// [`CUSTOMER_INVITE_ROLE`].
+    pub role: String,
+    pub issued_at: u64,
+    /// Millisecond timestamp after which the code stops admitting its
+    /// invitee. `None` — including codes signed before this field
+    /// existed — never expires.
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+}
+
+/// A complete invite code: the invite plus the issuing admin's
+/// signature over it. Msgpack-encoded, this is the membrane proof the
+/// invitee joins with.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct SignedNetworkInvite {
+    pub invite: NetworkInvite,
+    pub issuer: AgentPubKey,
+    pub signature: Signature,
+}
+
+/// Membrane check shared by the cart and catalog integrity zomes: on
+/// networks with admins configured, joining requires an invite signed
+/// by one of them, made out to the joining key and not yet expired when
+/// the join happened. Open networks (no admins) admit anyone, proof or
+/// not. `joined_at` is the AgentValidationPkg action timestamp in
+/// milliseconds — the only join time every validator agrees on.
+pub fn validate_invite_membrane(
+    agent: AgentPubKey,
+    membrane_proof: &Option<MembraneProof>,
+    admins: &[AgentPubKey],
+    joined_at: u64,
+) -> ExternResult<ValidateCallbackResult> {
+    if admins.is_empty() {
+        return Ok(ValidateCallbackResult::Valid);
+    }
+    let Some(proof) = membrane_proof else {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Joining this network requires an invite code".to_string(),
+        ));
+    };
+    let Ok(signed) = SignedNetworkInvite::try_from((**proof).clone()) else {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Membrane proof is not an invite code".to_string(),
+        ));
+    };
+    if signed.invite.invitee != agent {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Invite was issued to a different agent".to_string(),
+        ));
+    }
+    if !admins.contains(&signed.issuer) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Invite issuer is not an admin on this network".to_string(),
+        ));
+    }
+    if signed
+        .invite
+        .expires_at
+        .is_some_and(|expires_at| joined_at > expires_at)
+    {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Invite code has expired".to_string(),
+        ));
+    }
+    if !verify_signature(signed.issuer.clone(), signed.signature.clone(), signed.invite)? {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Invite signature does not verify".to_string(),
+        ));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// Run a fallible closure up to `attempts` times, returning the first
+/// success or the last error. The remote-signal senders use it: one
+/// transient host failure shouldn't silently drop a chat message or a
+/// status push.
+pub fn with_retries<T, E>(
+    attempts: usize,
+    mut operation: impl FnMut() -> Result<T, E>,
+) -> Result<T, E> {
+    let mut result = operation();
+    for _ in 1..attempts.max(1) {
+        if result.is_ok() {
+            break;
+        }
+        result = operation();
+    }
+    result
+}
+
+/// One recurring job in a coordinator's scheduler table: a stable name
+/// (the key its last-run bookkeeping is stored under) and how often it
+/// should run. The job body stays with the declaring zome.
+pub struct ScheduledJob {
+    pub name: &'static str,
+    pub period_ms: u64,
+}
+
+impl ScheduledJob {
+    /// Whether the job should run now, given when it last did. A job
+    /// that has never run is always due.
+    pub fn due(&self, now: u64, last_run: Option<u64>) -> bool {
+        last_run.is_none_or(|ran_at| now.saturating_sub(ran_at) >= self.period_ms)
+    }
+}
+
+/// Per-agent source-chain metrics, reported by each zome's
+/// `get_agent_metrics` so chain bloat (the cart-rewrite pattern, heavy
+/// importers) can be quantified instead of guessed at.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct AgentMetrics {
+    /// Every action on the chain, entries or not.
+    pub chain_length: usize,
+    /// App entries by entry-type name; unrecognized types count under
+    /// "other".
+    pub entry_counts: std::collections::BTreeMap<String, usize>,
+    /// Serialized app-entry bytes on the chain. Approximate: actions,
+    /// links and system entries are not counted.
+    pub approx_entry_bytes: usize,
+}
+
+/// Fold a chain's records into [`AgentMetrics`]. Each zome passes its
+/// own entry-type name table; querying the chain stays with the caller
+/// since only coordinator zomes can.
+pub fn agent_metrics_from_records(
+    records: &[Record],
+    names: &[(EntryType, &'static str)],
+) -> AgentMetrics {
+    let mut entry_counts: std::collections::BTreeMap<String, usize> =
+        std::collections::BTreeMap::new();
+    let mut approx_entry_bytes = 0;
+    for record in records {
+        let Some(entry_type) = record.action().entry_type() else {
+            continue;
+        };
+        if let Some(Entry::App(bytes)) = record.entry().as_option() {
+            approx_entry_bytes += bytes.bytes().len();
+        }
+        if let EntryType::App(_) = entry_type {
+            let name = names
+                .iter()
+                .find(|(candidate, _)| candidate == entry_type)
+                .map(|(_, name)| *name)
+                .unwrap_or("other");
+            *entry_counts.entry(name.to_string()).or_insert(0) += 1;
+        }
+    }
+
+    AgentMetrics {
+        chain_length: records.len(),
+        entry_counts,
+        approx_entry_bytes,
+    }
+}
+
+/// Pagination input shared by the listing externs of every zome.
+/// `cursor` is the opaque value the previous page returned — an offset
+/// for anchor-backed listings, a `created_at` watermark for order
+/// history — so callers never interpret it, only echo it back.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct PageRequest {
+    #[serde(default)]
+    pub cursor: Option<u64>,
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+impl PageRequest {
+    /// The cursor read as an offset, for listings that page by index.
+    pub fn offset(&self) -> usize {
+        self.cursor.unwrap_or(0) as usize
+    }
+
+    pub fn limit_or(&self, default: usize) -> usize {
+        self.limit.unwrap_or(default)
+    }
+}
+
+/// One page of a listing: the items, the cursor for the next page
+/// (`None` when this is the last one), and the total match count so
+/// frontends can size scrollbars without fetching everything.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub cursor: Option<u64>,
+    pub total: usize,
+    pub has_more: bool,
+}
+
+impl<T> Page<T> {
+    /// Assemble a page for an offset-style cursor: the next cursor is
+    /// the index just past `items`, or `None` at the end of `total`.
+    pub fn from_offset(items: Vec<T>, offset: usize, total: usize) -> Self {
+        let end = offset.saturating_add(items.len());
+        let has_more = end < total;
+        Page {
+            items,
+            cursor: has_more.then_some(end as u64),
+            total,
+            has_more,
+        }
+    }
+}
+
+/// Machine-branchable error kinds shared by the app's externs. Zome
+/// errors cross the wasm boundary as guest strings, so the kind is
+/// rendered as a stable "kind: detail" prefix frontends and tests can
+/// match on instead of the prose.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum SummonError {
+    NotFound { what: String },
+    NotAuthorized { reason: String },
+    InvalidInput { field: String, reason: String },
+    BridgeFailure { call: String, detail: String },
+    FeatureDisabled { feature: String },
+}
+
+impl SummonError {
+    pub fn not_found(what: impl Into<String>) -> Self {
+        Self::NotFound { what: what.into() }
+    }
+
+    pub fn not_authorized(reason: impl Into<String>) -> Self {
+        Self::NotAuthorized {
+            reason: reason.into(),
+        }
+    }
+
+    pub fn invalid_input(field: impl Into<String>, reason: impl Into<String>) -> Self {
+        Self::InvalidInput {
+            field: field.into(),
+            reason: reason.into(),
+        }
+    }
+
+    pub fn bridge_failure(call: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self::BridgeFailure {
+            call: call.into(),
+            detail: detail.into(),
+        }
+    }
+
+    pub fn feature_disabled(feature: impl Into<String>) -> Self {
+        Self::FeatureDisabled {
+            feature: feature.into(),
+        }
+    }
+}
+
+impl core::fmt::Display for SummonError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::NotFound { what } => write!(f, "not_found: {}", what),
+            Self::NotAuthorized { reason } => write!(f, "not_authorized: {}", reason),
+            Self::InvalidInput { field, reason } => {
+                write!(f, "invalid_input: {}: {}", field, reason)
+            }
+            Self::BridgeFailure { call, detail } => {
+                write!(f, "bridge_failure: {}: {}", call, detail)
+            }
+            Self::FeatureDisabled { feature } => {
+                write!(f, "feature_disabled: {}", feature)
+            }
+        }
+    }
+}
+
+impl From<SummonError> for WasmError {
+    fn from(err: SummonError) -> Self {
+        wasm_error!(WasmErrorInner::Guest(err.to_string()))
+    }
+}
+
+/// Anchor path string for a category / subcategory / product-type
+/// triple, as used by the catalog's category links.
+pub fn category_path_string(
+    category: &str,
+    subcategory: Option<&str>,
+    product_type: Option<&str>,
+) -> String {
+    let mut segments = vec![category.to_string()];
+    if let Some(subcategory) = subcategory {
+        segments.push(subcategory.to_string());
+        if let Some(product_type) = product_type {
+            segments.push(product_type.to_string());
+        }
+    }
+    segments.join(".")
+}
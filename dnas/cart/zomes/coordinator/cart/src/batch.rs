@@ -0,0 +1,239 @@
+use cart_integrity::*;
+use hdk::prelude::*;
+
+use crate::checkout::latest_order_revision;
+use crate::shopper::{
+    generate_pick_list, order_claimer, own_shopper_profile, record_claim, request_order_access,
+    PickListItem,
+};
+
+/// The store an order's items were sold by: the first snapshot with a
+/// store id. `None` for single-store catalogs, which batch with
+/// anything.
+fn order_store(order: &CheckedOutCart) -> Option<String> {
+    order
+        .product_snapshots
+        .iter()
+        .find_map(|snapshot| snapshot.store_id.clone())
+}
+
+/// Check the orders can be shopped as one trip: a shared store and, for
+/// orders with timed delivery slots, the same day with overlapping
+/// windows. Orders without a slot are flexible and fit any trip.
+fn check_batch_compatibility(orders: &[(ActionHash, CheckedOutCart)]) -> Result<(), String> {
+    let mut store: Option<String> = None;
+    for (_, order) in orders {
+        if let Some(order_store) = order_store(order) {
+            match &store {
+                Some(existing) if *existing != order_store => {
+                    return Err(format!(
+                        "Orders span different stores ({} and {})",
+                        existing, order_store
+                    ));
+                }
+                Some(_) => {}
+                None => store = Some(order_store),
+            }
+        }
+    }
+
+    let slots: Vec<&DeliveryTimeSlot> = orders
+        .iter()
+        .filter_map(|(_, order)| order.delivery_time.as_ref())
+        .collect();
+    if let Some(first) = slots.first() {
+        if slots.iter().any(|slot| slot.date != first.date) {
+            return Err("Orders are due on different days".to_string());
+        }
+    }
+    let latest_start = slots
+        .iter()
+        .filter_map(|slot| slot.start_minute)
+        .max();
+    let earliest_end = slots
+        .iter()
+        .filter_map(|slot| slot.end_minute)
+        .min();
+    if let (Some(latest_start), Some(earliest_end)) = (latest_start, earliest_end) {
+        if latest_start >= earliest_end {
+            return Err("Order delivery windows do not overlap".to_string());
+        }
+    }
+    Ok(())
+}
+
+/// Claim several compatible open orders as one store trip. Each order
+/// gets its own claim (so per-order access, fulfillment and delivery
+/// keep working unchanged) and a `ShoppingBatch` ties them together
+/// for the combined pick list.
+#[hdk_extern]
+pub fn claim_batch(order_hashes: Vec<ActionHash>) -> ExternResult<ActionHash> {
+    match own_shopper_profile()? {
+        Some((_, profile)) if profile.active => {}
+        Some(_) => {
+            return Err(wasm_error!(WasmErrorInner::Guest(
+                "Shopper profile is inactive".to_string()
+            )))
+        }
+        None => {
+            return Err(wasm_error!(WasmErrorInner::Guest(
+                "Only registered shoppers may claim orders".to_string()
+            )))
+        }
+    }
+    if order_hashes.len() < 2 {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "A shopping batch combines at least two orders; use claim_order for one".to_string()
+        )));
+    }
+    let cap = crate::checkout::dna_properties()?.limits.max_batch_orders;
+    if cap > 0 && order_hashes.len() > cap as usize {
+        return Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "Batches are limited to {} orders",
+            cap
+        ))));
+    }
+
+    let mut orders = Vec::with_capacity(order_hashes.len());
+    for order_hash in &order_hashes {
+        if orders.iter().any(|(existing, _)| existing == order_hash) {
+            return Err(wasm_error!(WasmErrorInner::Guest(
+                "Batch lists the same order twice".to_string()
+            )));
+        }
+        if order_claimer(order_hash)?.is_some() {
+            return Err(wasm_error!(WasmErrorInner::Guest(format!(
+                "Order {} has already been claimed",
+                order_hash
+            ))));
+        }
+        let (_, order) = latest_order_revision(order_hash.clone())?;
+        if order.status != OrderStatus::Processing {
+            return Err(wasm_error!(WasmErrorInner::Guest(format!(
+                "Order {} is no longer open (status {:?})",
+                order_hash, order.status
+            ))));
+        }
+        if crate::block::is_blocked(&crate::tracking::order_customer(order_hash)?)? {
+            return Err(wasm_error!(WasmErrorInner::Guest(format!(
+                "Order {} is from a declined customer",
+                order_hash
+            ))));
+        }
+        orders.push((order_hash.clone(), order));
+    }
+    check_batch_compatibility(&orders)
+        .map_err(|message| wasm_error!(WasmErrorInner::Guest(message)))?;
+
+    for order_hash in &order_hashes {
+        record_claim(order_hash.clone(), None)?;
+        request_order_access(order_hash.clone())?;
+    }
+
+    let batch_hash = create_entry(&EntryTypes::ShoppingBatch(ShoppingBatch {
+        order_hashes,
+        created_at: sys_time()?.as_millis() as u64,
+    }))?;
+    create_link(
+        agent_info()?.agent_initial_pubkey,
+        batch_hash.clone(),
+        LinkTypes::ShoppingBatch,
+        (),
+    )?;
+    Ok(batch_hash)
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct BatchWithHash {
+    pub batch_hash: ActionHash,
+    pub batch: ShoppingBatch,
+}
+
+/// The caller's own shopping batches, newest first.
+#[hdk_extern]
+pub fn get_my_batches(_: ()) -> ExternResult<Vec<BatchWithHash>> {
+    let agent = agent_info()?.agent_initial_pubkey;
+    let links = get_links(
+        GetLinksInputBuilder::try_new(agent, LinkTypes::ShoppingBatch)?.build(),
+    )?;
+    let mut batches = Vec::new();
+    for link in links {
+        let Some(batch_hash) = link.target.into_action_hash() else {
+            continue;
+        };
+        let Some(record) = get(batch_hash.clone(), GetOptions::default())? else {
+            continue;
+        };
+        if let Some(batch) = record
+            .entry()
+            .to_app_option::<ShoppingBatch>()
+            .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        {
+            batches.push(BatchWithHash { batch_hash, batch });
+        }
+    }
+    batches.sort_by_key(|entry| std::cmp::Reverse(entry.batch.created_at));
+    Ok(batches)
+}
+
+/// One line in a combined pick list, tagged with the order it belongs
+/// to so items land in the right bag at checkout.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct BatchPickItem {
+    pub order_hash: ActionHash,
+    /// Position of the order inside the batch, for short bag labels
+    /// ("bag 1", "bag 2").
+    pub order_number: u32,
+    pub item: PickListItem,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct BatchPickAisle {
+    pub aisle: Option<String>,
+    pub items: Vec<BatchPickItem>,
+}
+
+/// The batch's orders merged into one walk through the store, keeping
+/// every item tagged with its order for per-order separation.
+#[hdk_extern]
+pub fn generate_batch_pick_list(batch_hash: ActionHash) -> ExternResult<Vec<BatchPickAisle>> {
+    let record = get(batch_hash, GetOptions::default())?.ok_or(wasm_error!(
+        WasmErrorInner::Guest("ShoppingBatch not found".to_string())
+    ))?;
+    let batch: ShoppingBatch = record
+        .entry()
+        .to_app_option()
+        .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        .ok_or(wasm_error!(WasmErrorInner::Guest(
+            "Record is not a ShoppingBatch".to_string()
+        )))?;
+
+    let mut aisles: Vec<BatchPickAisle> = Vec::new();
+    for (order_number, order_hash) in batch.order_hashes.into_iter().enumerate() {
+        for group in generate_pick_list(order_hash.clone())? {
+            let target = match aisles.iter_mut().find(|existing| existing.aisle == group.aisle)
+            {
+                Some(target) => target,
+                None => {
+                    aisles.push(BatchPickAisle {
+                        aisle: group.aisle,
+                        items: Vec::new(),
+                    });
+                    aisles.last_mut().expect("just pushed")
+                }
+            };
+            for item in group.items {
+                target.items.push(BatchPickItem {
+                    order_hash: order_hash.clone(),
+                    order_number: order_number as u32 + 1,
+                    item,
+                });
+            }
+        }
+    }
+    aisles.sort_by_key(|group| crate::shopper::aisle_key(&group.aisle));
+    Ok(aisles)
+}
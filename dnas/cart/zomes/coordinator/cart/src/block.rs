@@ -0,0 +1,128 @@
+use cart_integrity::*;
+use hdk::prelude::*;
+use std::collections::HashSet;
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct BlockAgentInput {
+    pub agent: AgentPubKey,
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+/// Every block still in force on the caller's chain: creates minus the
+/// ones a later unblock tombstoned.
+fn live_blocks() -> ExternResult<Vec<(ActionHash, BlockedAgent)>> {
+    let deleted: HashSet<ActionHash> = query(ChainQueryFilter::new().action_type(ActionType::Delete))?
+        .into_iter()
+        .filter_map(|record| match record.action() {
+            Action::Delete(delete) => Some(delete.deletes_address.clone()),
+            _ => None,
+        })
+        .collect();
+
+    let records = query(
+        ChainQueryFilter::new()
+            .entry_type(UnitEntryTypes::BlockedAgent.try_into()?)
+            .include_entries(true),
+    )?;
+    let mut blocks = Vec::new();
+    for record in records {
+        if deleted.contains(record.action_address()) {
+            continue;
+        }
+        if let Some(block) = record
+            .entry()
+            .to_app_option::<BlockedAgent>()
+            .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        {
+            blocks.push((record.action_address().clone(), block));
+        }
+    }
+    Ok(blocks)
+}
+
+/// Whether the caller's chain blocks an agent. Purely local: each side
+/// of a match consults its own list.
+pub(crate) fn is_blocked(agent: &AgentPubKey) -> ExternResult<bool> {
+    Ok(live_blocks()?.into_iter().any(|(_, block)| block.agent == *agent))
+}
+
+/// Refuse to pair this order with a blocked counterparty, unless the
+/// order's claim was placed by an admin — the override path for abuse
+/// review, where dispatch knowingly assigns across a block.
+pub(crate) fn enforce_not_blocked(
+    order_hash: &ActionHash,
+    counterparty: &AgentPubKey,
+) -> ExternResult<()> {
+    if !is_blocked(counterparty)? {
+        return Ok(());
+    }
+    let admins = crate::checkout::dna_properties()?.admins;
+    if !admins.is_empty() {
+        let links = get_links(
+            GetLinksInputBuilder::try_new(order_hash.clone(), LinkTypes::OrderClaim)?.build(),
+        )?;
+        for link in links {
+            let Some(claim_hash) = link.target.into_action_hash() else {
+                continue;
+            };
+            let Some(record) = get(claim_hash, GetOptions::default())? else {
+                continue;
+            };
+            if admins.contains(record.action().author()) {
+                return Ok(());
+            }
+        }
+    }
+    Err(wasm_error!(WasmErrorInner::Guest(
+        "Agent is on the block list for this order's owner".to_string()
+    )))
+}
+
+/// Block an agent: a customer keeping a shopper off their future
+/// orders, or a shopper declining a customer. Stored privately; the
+/// other side never learns about it beyond claims not landing.
+#[hdk_extern]
+pub fn block_agent(input: BlockAgentInput) -> ExternResult<ActionHash> {
+    if input.agent == agent_info()?.agent_initial_pubkey {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Cannot block yourself".to_string()
+        )));
+    }
+    if let Some((block_hash, _)) = live_blocks()?
+        .into_iter()
+        .find(|(_, block)| block.agent == input.agent)
+    {
+        return Ok(block_hash);
+    }
+    create_entry(&EntryTypes::BlockedAgent(BlockedAgent {
+        agent: input.agent,
+        note: input.note,
+        blocked_at: sys_time()?.as_millis() as u64,
+    }))
+}
+
+/// Lift a block. A no-op when the agent isn't blocked.
+#[hdk_extern]
+pub fn unblock_agent(agent: AgentPubKey) -> ExternResult<u32> {
+    let mut removed = 0;
+    for (block_hash, block) in live_blocks()? {
+        if block.agent == agent {
+            delete_entry(block_hash)?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+/// The caller's own block list, newest first.
+#[hdk_extern]
+pub fn get_blocked_agents(_: ()) -> ExternResult<Vec<BlockedAgent>> {
+    let mut blocks: Vec<BlockedAgent> = live_blocks()?
+        .into_iter()
+        .map(|(_, block)| block)
+        .collect();
+    blocks.sort_by_key(|entry| std::cmp::Reverse(entry.blocked_at));
+    Ok(blocks)
+}
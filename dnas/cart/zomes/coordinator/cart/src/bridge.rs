@@ -0,0 +1,71 @@
+//! Bridged calls into the app's other cells. Every cross-DNA call this
+//! zome makes goes through here, so the role names from the happ
+//! manifest, the target zome names and the failure shaping live in one
+//! place instead of being repeated at each call site.
+
+use hdk::prelude::*;
+use summon_types::SummonError;
+
+/// Role names as configured in the happ manifest.
+const PRODUCTS_ROLE: &str = "products_role";
+const PROFILES_ROLE: &str = "profiles_role";
+
+pub use summon_types::ProductReference;
+
+fn bridged_call<I, O>(role: &str, zome: &str, fn_name: &str, payload: I) -> ExternResult<O>
+where
+    I: serde::Serialize + std::fmt::Debug,
+    O: serde::de::DeserializeOwned + std::fmt::Debug,
+{
+    let response = call(
+        CallTargetCell::OtherRole(role.to_string()),
+        ZomeName::from(zome.to_string()),
+        FunctionName::from(fn_name.to_string()),
+        None,
+        payload,
+    )?;
+    match response {
+        ZomeCallResponse::Ok(io) => io.decode().map_err(|e| {
+            SummonError::bridge_failure(
+                format!("{}::{}", zome, fn_name),
+                format!("undecodable response: {}", e),
+            )
+            .into()
+        }),
+        other => Err(SummonError::bridge_failure(
+            format!("{}::{}", zome, fn_name),
+            format!("{:?}", other),
+        )
+        .into()),
+    }
+}
+
+/// Call into the product_catalog zome of the products cell.
+pub(crate) fn call_catalog<I, O>(fn_name: &str, payload: I) -> ExternResult<O>
+where
+    I: serde::Serialize + std::fmt::Debug,
+    O: serde::de::DeserializeOwned + std::fmt::Debug,
+{
+    bridged_call(PRODUCTS_ROLE, "product_catalog", fn_name, payload)
+}
+
+/// Call into the address zome of the profiles cell.
+pub(crate) fn call_address<I, O>(fn_name: &str, payload: I) -> ExternResult<O>
+where
+    I: serde::Serialize + std::fmt::Debug,
+    O: serde::de::DeserializeOwned + std::fmt::Debug,
+{
+    bridged_call(PROFILES_ROLE, "address", fn_name, payload)
+}
+
+/// Convert an order's cart lines into the reference shape the catalog
+/// resolve externs take.
+pub(crate) fn product_references(products: &[cart_integrity::CartProduct]) -> Vec<ProductReference> {
+    products
+        .iter()
+        .map(|item| ProductReference {
+            group_hash: item.group_hash.clone(),
+            index: item.product_index,
+        })
+        .collect()
+}
@@ -0,0 +1,696 @@
+use cart_integrity::*;
+use hdk::prelude::*;
+
+/// Number of deltas allowed to accumulate before they are rolled into a
+/// fresh snapshot.
+pub(crate) const COMPACTION_THRESHOLD: usize = 20;
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct AddToPrivateCartInput {
+    #[serde(alias = "groupHash")]
+    pub group_hash: ActionHash,
+    #[serde(alias = "productIndex")]
+    pub product_index: u32,
+    pub quantity: f64,
+    pub note: Option<String>,
+    #[serde(default, alias = "substitutionPreference")]
+    pub substitution_preference: Option<SubstitutionPreference>,
+    #[serde(default, alias = "soldBy")]
+    pub sold_by: Option<SoldBy>,
+}
+
+/// One item in a full cart replacement, with a native `ActionHash` and
+/// snake_case fields.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct ReplaceCartItem {
+    #[serde(alias = "groupHash")]
+    pub group_hash: ActionHash,
+    #[serde(alias = "productIndex")]
+    pub product_index: u32,
+    pub quantity: f64,
+    pub note: Option<String>,
+    #[serde(default, alias = "substitutionPreference")]
+    pub substitution_preference: Option<SubstitutionPreference>,
+    #[serde(default, alias = "soldBy")]
+    pub sold_by: Option<SoldBy>,
+    pub timestamp: Option<u64>,
+}
+
+/// Conversion shim for clients still sending the old camelCase fields
+/// with base64-encoded hashes. Remove once no deployed frontend sends
+/// this shape.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct LegacyReplaceCartItem {
+    pub group_hash: String,
+    pub product_index: u32,
+    pub quantity: f64,
+    pub note: Option<String>,
+    pub timestamp: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(untagged)]
+pub enum ReplaceCartItemCompat {
+    Native(ReplaceCartItem),
+    Legacy(LegacyReplaceCartItem),
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct ReplacePrivateCartInput {
+    pub items: Vec<ReplaceCartItemCompat>,
+}
+
+/// An item that could not be turned into a cart line. Reported back to
+/// the caller instead of being silently dropped.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct RejectedCartItem {
+    pub group_hash: String,
+    pub product_index: u32,
+    pub reason: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct ReplaceCartReport {
+    pub cart_hash: ActionHash,
+    pub accepted: u32,
+    pub rejected: Vec<RejectedCartItem>,
+}
+
+// --- Legacy API (pre private-cart). Deprecated: these externs now
+// translate into private-cart operations and emit a deprecation warning
+// signal. They will be removed in the next release.
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct AddToCartInput {
+    #[serde(alias = "productHash")]
+    pub product_hash: String,
+    pub quantity: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+struct DeprecationWarning {
+    extern_name: String,
+    message: String,
+}
+
+fn emit_deprecation_warning(extern_name: &str, replacement: &str) -> ExternResult<()> {
+    emit_signal(DeprecationWarning {
+        extern_name: extern_name.to_string(),
+        message: format!(
+            "{} is deprecated and will be removed in the next release; use {} instead",
+            extern_name, replacement
+        ),
+    })
+}
+
+/// Translate the legacy `product_hash` ("groupHashB64" or
+/// "groupHashB64:index") into a group reference.
+fn parse_legacy_product_hash(product_hash: &str) -> ExternResult<(ActionHash, u32)> {
+    let (hash_part, index) = match product_hash.split_once(':') {
+        Some((hash, index)) => (
+            hash,
+            index.parse::<u32>().map_err(|_| {
+                wasm_error!(WasmErrorInner::Guest(format!(
+                    "Invalid product index in legacy product hash: {}",
+                    product_hash
+                )))
+            })?,
+        ),
+        None => (product_hash, 0),
+    };
+    let group_hash = ActionHash::try_from(hash_part.to_string()).map_err(|e| {
+        wasm_error!(WasmErrorInner::Guest(format!(
+            "Invalid legacy product hash: {:?}",
+            e
+        )))
+    })?;
+    Ok((group_hash, index))
+}
+
+/// Previously a silent no-op that lost data for old clients; now applies
+/// the legacy input to the private cart.
+pub fn add_to_cart_impl(input: AddToCartInput) -> ExternResult<ActionHash> {
+    let (group_hash, product_index) = parse_legacy_product_hash(&input.product_hash)?;
+    add_to_private_cart(AddToPrivateCartInput {
+        group_hash,
+        product_index,
+        quantity: input.quantity,
+        note: None,
+        substitution_preference: None,
+        sold_by: None,
+    })
+}
+
+#[hdk_extern]
+pub fn add_to_cart(input: AddToCartInput) -> ExternResult<ActionHash> {
+    emit_deprecation_warning("add_to_cart", "add_to_private_cart")?;
+    add_to_cart_impl(input)
+}
+
+#[hdk_extern]
+pub fn get_cart(_: ()) -> ExternResult<Vec<CartProduct>> {
+    emit_deprecation_warning("get_cart", "get_private_cart")?;
+    Ok(get_private_cart_impl()?.items)
+}
+
+// --- Private cart ---
+
+/// Resolve the caller's current private cart.
+///
+/// The cart is private data that is always present on the caller's own
+/// source chain, so reads go through `query()` instead of link queries
+/// and DHT gets: the newest `PrivateCart` snapshot plus any deltas
+/// recorded after it. This makes cart reads instant and fully
+/// offline-capable. Concurrent-update resolution is handled on the
+/// write path via the entry's update chain.
+pub fn get_private_cart_impl() -> ExternResult<PrivateCart> {
+    let snapshot = latest_snapshot()?.unwrap_or(PrivateCart {
+        items: Vec::new(),
+        last_updated: 0,
+        promo_code_hash: None,
+    });
+    apply_pending_deltas(snapshot)
+}
+
+/// The newest `PrivateCart` snapshot on the caller's chain, if any.
+/// `query` returns records in chain order, so the last match wins.
+fn latest_snapshot() -> ExternResult<Option<PrivateCart>> {
+    let filter = ChainQueryFilter::new()
+        .entry_type(UnitEntryTypes::PrivateCart.try_into()?)
+        .include_entries(true);
+    Ok(query(filter)?.into_iter().rev().find_map(|record| {
+        record
+            .entry()
+            .to_app_option::<PrivateCart>()
+            .ok()
+            .flatten()
+    }))
+}
+
+/// Replay deltas recorded since the snapshot was written.
+fn apply_pending_deltas(mut cart: PrivateCart) -> ExternResult<PrivateCart> {
+    let mut deltas = pending_deltas()?;
+    deltas.retain(|delta| delta.timestamp > cart.last_updated);
+    deltas.sort_by_key(|delta| delta.timestamp);
+    for delta in deltas {
+        cart.last_updated = cart.last_updated.max(delta.timestamp);
+        apply_delta(&mut cart, delta);
+    }
+    Ok(cart)
+}
+
+fn apply_delta(cart: &mut PrivateCart, delta: CartDelta) {
+    match delta.op {
+        CartDeltaOp::Add(item) => {
+            match cart.items.iter_mut().find(|existing| {
+                existing.group_hash == item.group_hash
+                    && existing.product_index == item.product_index
+            }) {
+                Some(existing) => *existing = item,
+                None => cart.items.push(item),
+            }
+        }
+        CartDeltaOp::Update {
+            group_hash,
+            product_index,
+            quantity,
+            note,
+            substitution_preference,
+        } => {
+            if let Some(existing) = cart.items.iter_mut().find(|existing| {
+                existing.group_hash == group_hash && existing.product_index == product_index
+            }) {
+                existing.quantity = quantity;
+                existing.timestamp = delta.timestamp;
+                if note.is_some() {
+                    existing.note = note;
+                }
+                if substitution_preference.is_some() {
+                    existing.substitution_preference = substitution_preference;
+                }
+            }
+        }
+        CartDeltaOp::Remove {
+            group_hash,
+            product_index,
+        } => {
+            cart.items.retain(|existing| {
+                !(existing.group_hash == group_hash
+                    && existing.product_index == product_index)
+            });
+        }
+    }
+}
+
+/// All deltas on the caller's chain, read locally via `query()`.
+/// Deltas older than the latest snapshot are filtered out by the
+/// caller, so no bookkeeping links are needed.
+fn pending_deltas() -> ExternResult<Vec<CartDelta>> {
+    let filter = ChainQueryFilter::new()
+        .entry_type(UnitEntryTypes::CartDelta.try_into()?)
+        .include_entries(true);
+    let records = query(filter)?;
+
+    let mut deltas = Vec::new();
+    for record in records {
+        if let Some(delta) = record
+            .entry()
+            .to_app_option::<CartDelta>()
+            .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        {
+            deltas.push(delta);
+        }
+    }
+    Ok(deltas)
+}
+
+/// Record one delta. Once enough deltas pile up they are compacted into
+/// a full snapshot so rebuilds stay cheap.
+fn record_delta(op: CartDeltaOp) -> ExternResult<ActionHash> {
+    let timestamp = sys_time()?.as_millis() as u64;
+    let hash = create_entry(&EntryTypes::CartDelta(CartDelta { op, timestamp }))?;
+
+    let snapshot_ts = latest_snapshot()?
+        .map(|cart| cart.last_updated)
+        .unwrap_or(0);
+    let pending = pending_deltas()?
+        .into_iter()
+        .filter(|delta| delta.timestamp > snapshot_ts)
+        .count();
+    if pending >= COMPACTION_THRESHOLD {
+        // The rebuilt cart becomes the new snapshot; its last_updated
+        // supersedes the replayed deltas.
+        write_private_cart(get_private_cart_impl()?)?;
+    }
+
+    Ok(hash)
+}
+
+/// Follow the update chain from a cart action to its newest revision.
+/// Resolution goes through `get_details` so concurrent updates converge
+/// on the latest write instead of whichever branch was fetched first.
+fn latest_cart_revision(
+    action_hash: ActionHash,
+) -> ExternResult<Option<(ActionHash, PrivateCart)>> {
+    let mut current = action_hash;
+    loop {
+        let Some(Details::Record(details)) =
+            get_details(current.clone(), GetOptions::default())?
+        else {
+            return Ok(None);
+        };
+        let newest_update = details
+            .updates
+            .into_iter()
+            .max_by_key(|update| update.action().timestamp());
+        match newest_update {
+            Some(update) => current = update.action_address().clone(),
+            None => {
+                let cart = details
+                    .record
+                    .entry()
+                    .to_app_option::<PrivateCart>()
+                    .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?;
+                return Ok(cart.map(|cart| (current, cart)));
+            }
+        }
+    }
+}
+
+/// Write a new cart revision. The first write creates the entry and the
+/// one agent-key link; every later write is an `update_entry` on the
+/// newest revision, so the link never has to be deleted and re-created
+/// and there is no window where no cart link exists.
+pub(crate) fn write_private_cart(cart: PrivateCart) -> ExternResult<ActionHash> {
+    let agent = agent_info()?.agent_initial_pubkey;
+    let links = get_links(
+        GetLinksInputBuilder::try_new(agent.clone(), LinkTypes::PrivateCart)?.build(),
+    )?;
+
+    let original = links
+        .iter()
+        .find_map(|link| link.target.clone().into_action_hash());
+
+    let cart_hash = match original {
+        Some(original) => match latest_cart_revision(original.clone())? {
+            Some((latest, _)) => update_entry(latest, &EntryTypes::PrivateCart(cart))?,
+            None => update_entry(original, &EntryTypes::PrivateCart(cart))?,
+        },
+        None => {
+            let cart_hash = create_entry(&EntryTypes::PrivateCart(cart))?;
+            create_link(
+                agent.clone(),
+                cart_hash.clone(),
+                LinkTypes::PrivateCart,
+                (),
+            )?;
+            cart_hash
+        }
+    };
+
+    // Surplus links from the pre-update_entry era (or device races) are
+    // collapsed down to one.
+    for link in links.iter().skip(1) {
+        delete_link(link.create_link_hash.clone())?;
+    }
+
+    emit_signal(crate::AppSignal::CartUpdated)?;
+    Ok(cart_hash)
+}
+
+#[hdk_extern]
+pub fn get_private_cart(_: ()) -> ExternResult<PrivateCart> {
+    get_private_cart_impl()
+}
+
+#[hdk_extern]
+pub fn add_to_private_cart(input: AddToPrivateCartInput) -> ExternResult<ActionHash> {
+    let cart = get_private_cart_impl()?;
+    let now = sys_time()?.as_millis() as u64;
+    let existing_sold_by = cart
+        .items
+        .iter()
+        .find(|item| {
+            item.group_hash == input.group_hash && item.product_index == input.product_index
+        })
+        .map(|item| item.sold_by);
+
+    let op = if input.quantity <= 0.0 {
+        CartDeltaOp::Remove {
+            group_hash: input.group_hash,
+            product_index: input.product_index,
+        }
+    } else {
+        // A line already in the cart keeps its recorded unit of sale.
+        let sold_by = existing_sold_by.flatten().or(input.sold_by);
+        validate_quantity(input.quantity, sold_by)
+            .map_err(|reason| wasm_error!(WasmErrorInner::Guest(reason)))?;
+        if existing_sold_by.is_some() {
+            CartDeltaOp::Update {
+                group_hash: input.group_hash,
+                product_index: input.product_index,
+                quantity: input.quantity,
+                note: input.note,
+                substitution_preference: input.substitution_preference,
+            }
+        } else {
+            CartDeltaOp::Add(CartProduct {
+                group_hash: input.group_hash,
+                product_index: input.product_index,
+                quantity: input.quantity,
+                timestamp: now,
+                note: input.note,
+                substitution_preference: input.substitution_preference,
+                sold_by,
+            })
+        }
+    };
+
+    record_delta(op)
+}
+
+/// One change in a batched cart update. A non-positive quantity removes
+/// the line, matching `add_to_private_cart` semantics.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct CartItemChange {
+    #[serde(alias = "groupHash")]
+    pub group_hash: ActionHash,
+    #[serde(alias = "productIndex")]
+    pub product_index: u32,
+    pub quantity: f64,
+    pub note: Option<String>,
+    #[serde(default, alias = "substitutionPreference")]
+    pub substitution_preference: Option<SubstitutionPreference>,
+    #[serde(default, alias = "soldBy")]
+    pub sold_by: Option<SoldBy>,
+}
+
+/// Apply several changes (e.g. an offline queue or "add all to cart")
+/// in one zome call, producing a single new cart revision instead of N
+/// sequential writes.
+#[hdk_extern]
+pub fn update_cart_items(changes: Vec<CartItemChange>) -> ExternResult<ActionHash> {
+    let now = sys_time()?.as_millis() as u64;
+    let mut cart = get_private_cart_impl()?;
+
+    for change in changes {
+        if change.quantity <= 0.0 {
+            cart.items.retain(|item| {
+                !(item.group_hash == change.group_hash
+                    && item.product_index == change.product_index)
+            });
+            continue;
+        }
+        match cart.items.iter_mut().find(|item| {
+            item.group_hash == change.group_hash
+                && item.product_index == change.product_index
+        }) {
+            Some(item) => {
+                validate_quantity(change.quantity, item.sold_by.or(change.sold_by))
+                    .map_err(|reason| wasm_error!(WasmErrorInner::Guest(reason)))?;
+                item.quantity = change.quantity;
+                item.timestamp = now;
+                if change.note.is_some() {
+                    item.note = change.note;
+                }
+                if change.substitution_preference.is_some() {
+                    item.substitution_preference = change.substitution_preference;
+                }
+                if change.sold_by.is_some() {
+                    item.sold_by = change.sold_by;
+                }
+            }
+            None => {
+                validate_quantity(change.quantity, change.sold_by)
+                    .map_err(|reason| wasm_error!(WasmErrorInner::Guest(reason)))?;
+                cart.items.push(CartProduct {
+                    group_hash: change.group_hash,
+                    product_index: change.product_index,
+                    quantity: change.quantity,
+                    timestamp: now,
+                    note: change.note,
+                    substitution_preference: change.substitution_preference,
+                    sold_by: change.sold_by,
+                });
+            }
+        }
+    }
+
+    cart.last_updated = now;
+    write_private_cart(cart)
+}
+
+#[hdk_extern]
+pub fn replace_private_cart(input: ReplacePrivateCartInput) -> ExternResult<ReplaceCartReport> {
+    let now = sys_time()?.as_millis() as u64;
+    let mut items = Vec::new();
+    let mut rejected = Vec::new();
+    for item in input.items {
+        let item = match item {
+            ReplaceCartItemCompat::Native(item) => item,
+            ReplaceCartItemCompat::Legacy(legacy) => {
+                match ActionHash::try_from(legacy.group_hash.clone()) {
+                    Ok(group_hash) => ReplaceCartItem {
+                        group_hash,
+                        product_index: legacy.product_index,
+                        quantity: legacy.quantity,
+                        note: legacy.note,
+                        substitution_preference: None,
+                        sold_by: None,
+                        timestamp: legacy.timestamp,
+                    },
+                    Err(e) => {
+                        rejected.push(RejectedCartItem {
+                            group_hash: legacy.group_hash,
+                            product_index: legacy.product_index,
+                            reason: format!("invalid group hash: {:?}", e),
+                        });
+                        continue;
+                    }
+                }
+            }
+        };
+        if let Err(reason) = validate_quantity(item.quantity, item.sold_by) {
+            rejected.push(RejectedCartItem {
+                group_hash: item.group_hash.to_string(),
+                product_index: item.product_index,
+                reason,
+            });
+            continue;
+        }
+        items.push(CartProduct {
+            group_hash: item.group_hash,
+            product_index: item.product_index,
+            quantity: item.quantity,
+            timestamp: item.timestamp.unwrap_or(now),
+            note: item.note,
+            substitution_preference: item.substitution_preference,
+            sold_by: item.sold_by,
+        });
+    }
+
+    let accepted = items.len() as u32;
+    // Replacing the items leaves any applied promo code in place.
+    let promo_code_hash = get_private_cart_impl()?.promo_code_hash;
+    let cart_hash = write_private_cart(PrivateCart {
+        items,
+        last_updated: now,
+        promo_code_hash,
+    })?;
+
+    Ok(ReplaceCartReport {
+        cart_hash,
+        accepted,
+        rejected,
+    })
+}
+
+// --- Abandoned-cart expiry ---
+
+/// Emitted when the expiry job sets an idle cart aside, so an open UI
+/// can refresh and point the customer at their saved list.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct CartExpiredSignal {
+    pub saved_cart_hash: ActionHash,
+    pub item_count: u32,
+    pub last_updated: u64,
+}
+
+/// Scheduled daily through [`crate::scheduler`]: if the private cart
+/// has sat untouched beyond the configured period, snapshot it into a
+/// [`SavedCart`] and clear the active cart, so months-old prices and
+/// quantities are never silently checked out.
+pub(crate) fn expire_abandoned_cart() -> ExternResult<()> {
+    let expiry = crate::checkout::dna_properties()?.expiry;
+    if expiry.abandoned_after_ms == 0 {
+        return Ok(());
+    }
+
+    let cart = get_private_cart_impl()?;
+    let now = sys_time()?.as_millis() as u64;
+    if cart.items.is_empty()
+        || now.saturating_sub(cart.last_updated) <= expiry.abandoned_after_ms
+    {
+        return Ok(());
+    }
+
+    let item_count = cart.items.len() as u32;
+    let last_updated = cart.last_updated;
+    let saved_cart_hash = create_entry(&EntryTypes::SavedCart(SavedCart {
+        items: cart.items,
+        saved_at: now,
+    }))?;
+    // The promo is dropped along with the items; its window may well
+    // have closed by the time the customer comes back.
+    write_private_cart(PrivateCart {
+        items: Vec::new(),
+        last_updated: now,
+        promo_code_hash: None,
+    })?;
+
+    emit_signal(CartExpiredSignal {
+        saved_cart_hash,
+        item_count,
+        last_updated,
+    })?;
+    Ok(())
+}
+
+/// Scheduled daily through [`crate::scheduler`]: roll any deltas
+/// recorded since the last snapshot into a fresh one. The count-based
+/// compaction in [`record_delta`] only fires on busy carts; this
+/// bounds the replay window for carts that trickle a few deltas and
+/// then sit, so `get_private_cart_impl` never has more than a day of
+/// history to apply.
+pub(crate) fn compact_private_cart() -> ExternResult<()> {
+    let snapshot_ts = latest_snapshot()?
+        .map(|cart| cart.last_updated)
+        .unwrap_or(0);
+    let pending = pending_deltas()?
+        .into_iter()
+        .filter(|delta| delta.timestamp > snapshot_ts)
+        .count();
+    // One snapshot per period, and only when there is history to roll
+    // up — an idle cart adds nothing to its chain.
+    if pending == 0 {
+        return Ok(());
+    }
+    write_private_cart(get_private_cart_impl()?)?;
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct SavedCartWithHash {
+    pub saved_cart_hash: ActionHash,
+    pub saved_cart: SavedCart,
+}
+
+/// The caller's saved-for-later carts, newest first, read locally from
+/// the source chain.
+#[hdk_extern]
+pub fn get_saved_carts(_: ()) -> ExternResult<Vec<SavedCartWithHash>> {
+    let filter = ChainQueryFilter::new()
+        .entry_type(UnitEntryTypes::SavedCart.try_into()?)
+        .action_type(ActionType::Create)
+        .include_entries(true);
+
+    let mut saved = Vec::new();
+    for record in query(filter)? {
+        if let Some(saved_cart) = record
+            .entry()
+            .to_app_option::<SavedCart>()
+            .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        {
+            saved.push(SavedCartWithHash {
+                saved_cart_hash: record.action_address().clone(),
+                saved_cart,
+            });
+        }
+    }
+    saved.sort_by_key(|entry| std::cmp::Reverse(entry.saved_cart.saved_at));
+    Ok(saved)
+}
+
+/// Merge a saved cart back into the active private cart. Lines already
+/// in the cart get the quantities added together.
+#[hdk_extern]
+pub fn restore_saved_cart(saved_cart_hash: ActionHash) -> ExternResult<ActionHash> {
+    let record = get(saved_cart_hash, GetOptions::default())?.ok_or(wasm_error!(
+        WasmErrorInner::Guest("SavedCart not found".to_string())
+    ))?;
+    let saved: SavedCart = record
+        .entry()
+        .to_app_option()
+        .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        .ok_or(wasm_error!(WasmErrorInner::Guest(
+            "Record is not a SavedCart".to_string()
+        )))?;
+
+    let now = sys_time()?.as_millis() as u64;
+    let mut cart = get_private_cart_impl()?;
+    for item in saved.items {
+        match cart.items.iter_mut().find(|existing| {
+            existing.group_hash == item.group_hash
+                && existing.product_index == item.product_index
+        }) {
+            Some(existing) => {
+                existing.quantity += item.quantity;
+                existing.timestamp = now;
+            }
+            None => cart.items.push(CartProduct { timestamp: now, ..item }),
+        }
+    }
+    cart.last_updated = now;
+    write_private_cart(cart)
+}
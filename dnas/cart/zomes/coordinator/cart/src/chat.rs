@@ -0,0 +1,127 @@
+use cart_integrity::*;
+use hdk::prelude::*;
+
+use crate::shopper::order_claimer;
+use crate::tracking::order_customer;
+
+/// A chat message being pushed to the other participant the moment it
+/// is written, so neither side has to poll the DHT.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum ChatSignal {
+    Message {
+        order_hash: ActionHash,
+        message_hash: ActionHash,
+        author: AgentPubKey,
+        text: String,
+        sent_at: u64,
+    },
+}
+
+/// The other party in an order's chat, from this agent's point of view:
+/// the assigned shopper for the customer, the customer for the shopper.
+/// Errors when the caller is neither, or no shopper is assigned yet.
+fn chat_counterparty(order_hash: &ActionHash, agent: &AgentPubKey) -> ExternResult<AgentPubKey> {
+    let customer = order_customer(order_hash)?;
+    let shopper = order_claimer(order_hash)?.ok_or(wasm_error!(WasmErrorInner::Guest(
+        "Order has no assigned shopper to chat with".to_string()
+    )))?;
+    if *agent == customer {
+        Ok(shopper)
+    } else if *agent == shopper {
+        Ok(customer)
+    } else {
+        Err(wasm_error!(WasmErrorInner::Guest(
+            "Only the customer and the assigned shopper may use an order's chat".to_string()
+        )))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct SendChatMessageInput {
+    #[serde(alias = "orderHash")]
+    pub order_hash: ActionHash,
+    pub text: String,
+}
+
+/// Write a message into an order's chat and push it to the other
+/// participant. Restricted to the order's customer and assigned
+/// shopper.
+#[hdk_extern]
+pub fn send_chat_message(input: SendChatMessageInput) -> ExternResult<ActionHash> {
+    let agent = agent_info()?.agent_initial_pubkey;
+    let counterparty = chat_counterparty(&input.order_hash, &agent)?;
+
+    let message = ChatMessage {
+        order_hash: input.order_hash.clone(),
+        text: input.text,
+        sent_at: sys_time()?.as_millis() as u64,
+    };
+    let message_hash = create_entry(&EntryTypes::ChatMessage(message.clone()))?;
+    create_link(
+        input.order_hash.clone(),
+        message_hash.clone(),
+        LinkTypes::ChatMessage,
+        (),
+    )?;
+
+    crate::signal::push_remote_signal(
+        ChatSignal::Message {
+            order_hash: input.order_hash,
+            message_hash: message_hash.clone(),
+            author: agent,
+            text: message.text,
+            sent_at: message.sent_at,
+        },
+        vec![counterparty],
+    )?;
+    Ok(message_hash)
+}
+
+/// One message as returned to the UI, with the author resolved from the
+/// create action.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct ChatMessageWithAuthor {
+    pub message_hash: ActionHash,
+    pub author: AgentPubKey,
+    pub text: String,
+    pub sent_at: u64,
+}
+
+/// All messages in an order's chat, oldest first. Restricted to the
+/// order's customer and assigned shopper.
+#[hdk_extern]
+pub fn get_chat_messages(order_hash: ActionHash) -> ExternResult<Vec<ChatMessageWithAuthor>> {
+    let agent = agent_info()?.agent_initial_pubkey;
+    chat_counterparty(&order_hash, &agent)?;
+
+    let links = get_links(
+        GetLinksInputBuilder::try_new(order_hash, LinkTypes::ChatMessage)?.build(),
+    )?;
+    let mut messages = Vec::new();
+    for link in links {
+        let Some(message_hash) = link.target.into_action_hash() else {
+            continue;
+        };
+        let Some(record) = get(message_hash.clone(), GetOptions::default())? else {
+            continue;
+        };
+        let Some(message) = record
+            .entry()
+            .to_app_option::<ChatMessage>()
+            .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        else {
+            continue;
+        };
+        messages.push(ChatMessageWithAuthor {
+            message_hash,
+            author: record.action().author().clone(),
+            text: message.text,
+            sent_at: message.sent_at,
+        });
+    }
+    messages.sort_by_key(|message| message.sent_at);
+    Ok(messages)
+}
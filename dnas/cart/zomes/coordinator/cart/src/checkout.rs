@@ -0,0 +1,1771 @@
+use cart_integrity::*;
+use hdk::prelude::*;
+use summon_types::{LinkTagCodec, Page, PageRequest, SummonError};
+
+use crate::preference::{save_product_preference, PreferenceKey, SavePreferenceInput};
+
+/// Round a dollar amount to cents.
+pub(crate) fn round_cents(amount: f64) -> f64 {
+    (amount * 100.0).round() / 100.0
+}
+
+/// The properties this cell was installed with; unparseable or absent
+/// properties mean the defaults.
+pub(crate) fn dna_properties() -> ExternResult<DnaProperties> {
+    Ok(DnaProperties::try_from(dna_info()?.modifiers.properties).unwrap_or_default())
+}
+
+/// One tax line per category purchased, using the configured rates.
+fn compute_tax_lines(
+    tax: &TaxConfig,
+    products: &[ProductSnapshot],
+    line_totals: &[f64],
+) -> Vec<TaxLine> {
+    let mut lines: Vec<TaxLine> = Vec::new();
+    for (snapshot, line_total) in products.iter().zip(line_totals) {
+        let rate = tax.rate_for_category(&snapshot.category);
+        if rate <= 0.0 {
+            continue;
+        }
+        match lines.iter_mut().find(|line| line.category == snapshot.category) {
+            Some(line) => line.amount += line_total * rate,
+            None => lines.push(TaxLine {
+                category: snapshot.category.clone(),
+                rate,
+                amount: line_total * rate,
+            }),
+        }
+    }
+    for line in &mut lines {
+        line.amount = round_cents(line.amount);
+    }
+    lines
+}
+
+/// Resolve every cart line against the catalog over the bridge. This
+/// both validates the references (groups exist, indexes in range) and
+/// returns the snapshots to embed in the order.
+fn fetch_product_snapshots(products: &[CartProduct]) -> ExternResult<Vec<ProductSnapshot>> {
+    let references = crate::bridge::product_references(products);
+    let resolved: Vec<Option<ProductSnapshot>> =
+        crate::bridge::call_catalog("resolve_product_references", references)?;
+
+    let unknown: Vec<String> = products
+        .iter()
+        .zip(&resolved)
+        .filter(|(_, resolved)| resolved.is_none())
+        .map(|(item, _)| format!("{}[{}]", item.group_hash, item.product_index))
+        .collect();
+    if !unknown.is_empty() {
+        return Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "Cart references unknown catalog products: {}",
+            unknown.join(", ")
+        ))));
+    }
+    Ok(resolved.into_iter().flatten().collect())
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct CheckoutCartInput {
+    #[serde(alias = "addressHash")]
+    pub address_hash: Option<ActionHash>,
+    #[serde(alias = "deliveryInstructions")]
+    pub delivery_instructions: Option<String>,
+    #[serde(alias = "deliveryTime")]
+    pub delivery_time: Option<DeliveryTimeSlot>,
+    /// The frontend sends the cart lines it is checking out so the
+    /// order matches exactly what the customer saw, including any notes
+    /// edited on the checkout screen.
+    #[serde(alias = "cartProducts")]
+    pub cart_products: Vec<CartProduct>,
+    /// Items whose note the customer checked "remember this note" for;
+    /// their notes are persisted back to ProductPreference entries.
+    #[serde(default, alias = "rememberNotes")]
+    pub remember_notes: Vec<PreferenceKey>,
+    /// Claimed gift card to draw the payment from, if the customer
+    /// chose one at checkout.
+    #[serde(default, alias = "giftCardHash")]
+    pub gift_card_hash: Option<ActionHash>,
+    /// Omitted by older clients; an address then implies delivery.
+    #[serde(default, alias = "fulfillmentMethod")]
+    pub fulfillment_method: Option<FulfillmentMethod>,
+    /// Structured handoff choice for delivery orders.
+    #[serde(default, alias = "deliveryHandoff")]
+    pub delivery_handoff: Option<DeliveryHandoff>,
+    /// Inline delivery address for pseudonymous orders placed from a
+    /// one-time identity with no saved address book. Mutually exclusive
+    /// with `address_hash`; held as a private entry, never published.
+    #[serde(default, alias = "pseudonymousAddress")]
+    pub pseudonymous_address: Option<crate::export::ExportedAddress>,
+}
+
+/// Mirror of the service check returned by the address zome.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+struct AddressServiceCheck {
+    served: bool,
+    message: Option<String>,
+    #[serde(default)]
+    zone: Option<String>,
+}
+
+/// Reject checkout to an address outside the configured service zones,
+/// returning the matching zone's name for dispatch. Goes over the
+/// bridge to the profiles DNA, since the address may have been saved
+/// before the zones changed.
+fn check_address_in_service_zone(address_hash: &ActionHash) -> ExternResult<Option<String>> {
+    let check: AddressServiceCheck =
+        crate::bridge::call_address("check_address_served", address_hash.clone())?;
+    if !check.served {
+        return Err(wasm_error!(WasmErrorInner::Guest(check.message.unwrap_or(
+            "Delivery address is outside the served areas".to_string()
+        ))));
+    }
+    Ok(check.zone)
+}
+
+/// Sent by the assigned fulfiller to the customer's cell, which answers
+/// by granting them read access to the order's delivery address.
+/// Addresses are private entries, so this grant is the only way a
+/// fulfiller ever sees one.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct AddressRequestSignal {
+    #[serde(alias = "orderHash")]
+    pub order_hash: ActionHash,
+}
+
+/// Mirror of the share input expected by the address zome.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+struct ShareAddressBridge {
+    address_hash: ActionHash,
+    with_agent: AgentPubKey,
+}
+
+/// Customer-side handler for [`AddressRequestSignal`]: verify the
+/// requester really is the agent shopping our order, then share the
+/// delivery address with them over the bridge to the profiles DNA.
+pub(crate) fn grant_delivery_address(
+    order_hash: &ActionHash,
+    fulfiller: AgentPubKey,
+) -> ExternResult<()> {
+    let record = get(order_hash.clone(), GetOptions::default())?.ok_or(SummonError::not_found("CheckedOutCart"))?;
+    if record.action().author() != &agent_info()?.agent_initial_pubkey {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Address requests only apply to the caller's own orders".to_string()
+        )));
+    }
+    let (_, cart) = latest_order_revision(order_hash.clone())?;
+    // Assignment is the claim since claims exist; older orders fall
+    // back to whoever moved them into Shopping.
+    let mut assigned = crate::shopper::order_claimer(order_hash)?;
+    if assigned.is_none() {
+        assigned = cart
+            .status_history
+            .iter()
+            .rev()
+            .find(|change| change.status == OrderStatus::Shopping)
+            .map(|change| change.actor.clone());
+    }
+    if assigned.as_ref() != Some(&fulfiller) {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Requester is not the agent assigned to this order".to_string()
+        )));
+    }
+    crate::block::enforce_not_blocked(order_hash, &fulfiller)?;
+    let Some(address_hash) = cart.address_hash else {
+        return Ok(());
+    };
+
+    crate::bridge::call_address(
+        "share_address",
+        ShareAddressBridge {
+            address_hash,
+            with_agent: fulfiller,
+        },
+    )
+}
+
+/// Mirror of the shared-address record returned by the address zome.
+/// Extra fields (coordinates and the like) are ignored on decode.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct SharedDeliveryAddress {
+    pub owner: AgentPubKey,
+    pub address_hash: ActionHash,
+    pub address: crate::export::ExportedAddress,
+}
+
+/// Fulfiller-side lookup of an order's delivery address, through the
+/// capability the customer granted at assignment time. `None` until the
+/// customer's cell has answered the address request.
+#[hdk_extern]
+pub fn fetch_delivery_address(
+    order_hash: ActionHash,
+) -> ExternResult<Option<SharedDeliveryAddress>> {
+    let (_, cart) = latest_order_revision(order_hash)?;
+    let Some(address_hash) = cart.address_hash else {
+        return Ok(None);
+    };
+
+    let shared: Vec<SharedDeliveryAddress> =
+        crate::bridge::call_address("get_shared_addresses", ())?;
+    Ok(shared
+        .into_iter()
+        .find(|entry| entry.address_hash == address_hash))
+}
+
+/// Anchor the delivery orders booked into one slot hang off, so the
+/// ETA estimate can see how loaded the slot already is.
+fn slot_load_anchor(slot: &DeliveryTimeSlot) -> ExternResult<TypedPath> {
+    Path::from(format!("slot_load.{}.{}", slot.date, slot.time_slot)).typed(LinkTypes::SlotLoad)
+}
+
+/// Anchor every published order is logged under, so admin metrics can
+/// walk the whole network's orders.
+pub(crate) fn order_log_anchor() -> ExternResult<TypedPath> {
+    Path::from("order_log").typed(LinkTypes::OrderLog)
+}
+
+/// Open delivery orders already booked into a slot.
+fn open_orders_in_slot(slot: &DeliveryTimeSlot) -> ExternResult<u32> {
+    let anchor = slot_load_anchor(slot)?;
+    let links = get_links(
+        GetLinksInputBuilder::try_new(anchor.path_entry_hash()?, LinkTypes::SlotLoad)?.build(),
+    )?;
+    Ok(links.len() as u32)
+}
+
+fn compute_delivery_estimate(
+    slot: &DeliveryTimeSlot,
+    load: u32,
+    now: u64,
+) -> ExternResult<DeliveryEstimate> {
+    let eta = dna_properties()?.eta;
+    let start = slot.date.max(now);
+    let earliest = start
+        + (eta.prep_minutes + eta.travel_minutes + eta.minutes_per_open_order * load as u64)
+            * 60_000;
+    Ok(DeliveryEstimate {
+        earliest,
+        latest: earliest + eta.window_minutes * 60_000,
+    })
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct EstimateDeliveryInput {
+    /// Checked against the service zones when given.
+    #[serde(default, alias = "addressHash")]
+    pub address_hash: Option<ActionHash>,
+    pub slot: DeliveryTimeSlot,
+}
+
+/// A realistic arrival window for a delivery slot, from the configured
+/// prep and travel times plus how many open orders the slot already
+/// carries. The same estimate is stored on the order at checkout.
+#[hdk_extern]
+pub fn estimate_delivery(input: EstimateDeliveryInput) -> ExternResult<DeliveryEstimate> {
+    if let Some(address_hash) = &input.address_hash {
+        check_address_in_service_zone(address_hash)?;
+    }
+    let load = open_orders_in_slot(&input.slot)?;
+    compute_delivery_estimate(&input.slot, load, sys_time()?.as_millis() as u64)
+}
+
+pub fn checkout_cart_impl(mut input: CheckoutCartInput) -> ExternResult<ActionHash> {
+    if input.cart_products.is_empty() {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Cannot check out an empty cart".to_string()
+        )));
+    }
+
+    // Pseudonymous orders keep their address off the DHT entirely: no
+    // saved-address reference, just the private inline copy written
+    // after the order publishes.
+    let pseudonymous_address = input.pseudonymous_address.take();
+    if pseudonymous_address.is_some()
+        && (input.address_hash.is_some()
+            || matches!(
+                input.fulfillment_method,
+                Some(FulfillmentMethod::Delivery { .. })
+            ))
+    {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Pseudonymous orders carry their address inline, not by reference".to_string()
+        )));
+    }
+
+    // Normalize the fulfillment method against the legacy address
+    // field, so delivery orders always carry both.
+    let mut pickup_slot_hash = None;
+    let mut delivery_zone = None;
+    let fulfillment_method = match input.fulfillment_method.take() {
+        Some(FulfillmentMethod::Delivery { address_hash }) => {
+            delivery_zone = check_address_in_service_zone(&address_hash)?;
+            input.address_hash = Some(address_hash.clone());
+            Some(FulfillmentMethod::Delivery { address_hash })
+        }
+        None if input.address_hash.is_some() => {
+            let address_hash = input.address_hash.clone().unwrap();
+            delivery_zone = check_address_in_service_zone(&address_hash)?;
+            Some(FulfillmentMethod::Delivery { address_hash })
+        }
+        Some(FulfillmentMethod::Pickup { store_id, slot }) => {
+            if store_id.trim().is_empty() {
+                return Err(wasm_error!(WasmErrorInner::Guest(
+                    "Pickup order must name a store".to_string()
+                )));
+            }
+            if slot.time_slot.trim().is_empty() {
+                return Err(wasm_error!(WasmErrorInner::Guest(
+                    "Pickup order must carry a pickup slot".to_string()
+                )));
+            }
+            if input.delivery_handoff.is_some() {
+                return Err(wasm_error!(WasmErrorInner::Guest(
+                    "Pickup orders cannot carry a delivery handoff".to_string()
+                )));
+            }
+            input.address_hash = None;
+            // Reserve a unit of the store's published capacity, if the
+            // store schedules its pickup windows.
+            pickup_slot_hash = crate::pickup::find_bookable_slot(&store_id, &slot)?;
+            Some(FulfillmentMethod::Pickup { store_id, slot })
+        }
+        None => None,
+    };
+
+    let product_snapshots = fetch_product_snapshots(&input.cart_products)?;
+
+    // Totals come from catalog prices, not the client, so the permanent
+    // order record carries a trustworthy amount.
+    let line_totals: Vec<f64> = input
+        .cart_products
+        .iter()
+        .zip(&product_snapshots)
+        .map(|(item, snapshot)| {
+            let unit_price = snapshot.promo_price.unwrap_or(snapshot.price);
+            round_cents(unit_price * item.quantity)
+        })
+        .collect();
+    let subtotal = round_cents(line_totals.iter().sum());
+
+    // Redeem any promo code attached to the private cart, re-checking
+    // limits at the moment of checkout.
+    let promo = match crate::cart::get_private_cart_impl()?.promo_code_hash {
+        Some(promo_hash) => {
+            let record = get(promo_hash.clone(), GetOptions::default())?.ok_or(SummonError::not_found("Applied promo code"))?;
+            let promo: PromoCode = record
+                .entry()
+                .to_app_option()
+                .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+                .ok_or(wasm_error!(WasmErrorInner::Guest(
+                    "Record is not a PromoCode".to_string()
+                )))?;
+            crate::promo::check_promo_redeemable(&promo_hash, &promo)?;
+            Some((promo_hash, promo))
+        }
+        None => None,
+    };
+    let discount = match &promo {
+        Some((_, promo)) => round_cents(promo.discount.amount_for_subtotal(subtotal)),
+        None => 0.0,
+    };
+
+    let properties = dna_properties()?;
+    let tax_lines = compute_tax_lines(&properties.tax, &product_snapshots, &line_totals);
+    let tax = round_cents(tax_lines.iter().map(|line| line.amount).sum());
+    // Pickup orders are never charged a delivery fee.
+    let delivery_fee = match &fulfillment_method {
+        Some(FulfillmentMethod::Pickup { .. }) => 0.0,
+        _ => round_cents(properties.delivery.fee_for_subtotal(subtotal)),
+    };
+    let total = round_cents(subtotal - discount + tax + delivery_fee);
+
+    let agent = agent_info()?.agent_initial_pubkey;
+    let now = sys_time()?.as_millis() as u64;
+
+    // Fail with a readable error before publishing; integrity enforces
+    // the same slot rules against hostile clients.
+    if let Some(slot) = &input.delivery_time {
+        validate_delivery_slot(slot, now, &properties.hours)
+            .map_err(|reason| wasm_error!(WasmErrorInner::Guest(reason)))?;
+    }
+
+    for key in &input.remember_notes {
+        let note = input
+            .cart_products
+            .iter()
+            .find(|item| {
+                item.group_hash == key.group_hash && item.product_index == key.product_index
+            })
+            .and_then(|item| item.note.clone());
+        if let Some(note) = note {
+            save_product_preference(SavePreferenceInput {
+                group_hash: key.group_hash.clone(),
+                product_index: key.product_index,
+                note,
+            })?;
+        }
+    }
+
+    let order_id = format!("order-{}", now);
+    let gift_card_payment = match input.gift_card_hash {
+        Some(gift_card_hash) => {
+            crate::giftcard::spend_from_gift_card(gift_card_hash, total, &order_id, now)?
+        }
+        None => 0.0,
+    };
+
+    let checked_out_delivery_time = input.delivery_time.clone();
+
+    // Promise an arrival window on delivery orders with a chosen slot.
+    let is_delivery = !matches!(
+        fulfillment_method,
+        Some(FulfillmentMethod::Pickup { .. })
+    );
+    let delivery_estimate = match (&input.delivery_time, is_delivery) {
+        (Some(slot), true) => {
+            let load = open_orders_in_slot(slot)?;
+            Some(compute_delivery_estimate(slot, load, now)?)
+        }
+        _ => None,
+    };
+
+    let checked_out = CheckedOutCart {
+        id: order_id,
+        products: input.cart_products,
+        product_snapshots,
+        line_totals,
+        subtotal,
+        tax_lines,
+        tax,
+        delivery_fee,
+        promo_code_hash: promo.as_ref().map(|(hash, _)| hash.clone()),
+        discount,
+        gift_card_payment,
+        total,
+        created_at: now,
+        status: OrderStatus::Processing,
+        status_history: vec![StatusChange {
+            status: OrderStatus::Processing,
+            timestamp: now,
+            actor: agent.clone(),
+        }],
+        cancellation: None,
+        item_fulfillments: Vec::new(),
+        address_hash: input.address_hash,
+        delivery_instructions: input.delivery_instructions,
+        delivery_time: input.delivery_time,
+        delivery_details_history: Vec::new(),
+        delivery_estimate,
+        delivery_handoff: input.delivery_handoff,
+        delivery_zone,
+        fulfillment_method,
+        id_check: None,
+        handoff_verification: None,
+        pseudonymous: pseudonymous_address.is_some(),
+    };
+
+    // Fail with a readable error before publishing; integrity enforces
+    // the same caps against hostile clients.
+    properties
+        .limits
+        .check(&checked_out)
+        .map_err(|reason| wasm_error!(WasmErrorInner::Guest(reason)))?;
+
+    let order_tag = customer_order_tag(OrderStatus::Processing, &checked_out);
+    let cart_hash = create_entry(&EntryTypes::CheckedOutCart(checked_out))?;
+    if let Some(address) = pseudonymous_address {
+        create_entry(&EntryTypes::PrivateDeliveryAddress(PrivateDeliveryAddress {
+            order_hash: cart_hash.clone(),
+            street: address.street,
+            unit: address.unit,
+            city: address.city,
+            state: address.state,
+            zip: address.zip,
+            label: address.label,
+        }))?;
+    }
+    if let Some(slot_hash) = pickup_slot_hash {
+        crate::pickup::book_slot(slot_hash, cart_hash.clone())?;
+    }
+    // Offer the order to shoppers until one claims it.
+    let available = crate::shopper::available_orders_anchor()?;
+    available.ensure()?;
+    create_link(
+        available.path_entry_hash()?,
+        cart_hash.clone(),
+        LinkTypes::AvailableOrder,
+        (),
+    )?;
+    // Log the order under the network-wide anchor for SLA metrics.
+    let order_log = order_log_anchor()?;
+    order_log.ensure()?;
+    create_link(
+        order_log.path_entry_hash()?,
+        cart_hash.clone(),
+        LinkTypes::OrderLog,
+        (),
+    )?;
+    // Count this order toward its slot's load for later estimates.
+    if let (Some(slot), true) = (&checked_out_delivery_time, is_delivery) {
+        let anchor = slot_load_anchor(slot)?;
+        anchor.ensure()?;
+        create_link(
+            anchor.path_entry_hash()?,
+            cart_hash.clone(),
+            LinkTypes::SlotLoad,
+            (),
+        )?;
+    }
+    create_link(
+        agent,
+        cart_hash.clone(),
+        LinkTypes::CheckedOutCart,
+        order_tag,
+    )?;
+    if let Some((promo_hash, _)) = promo {
+        create_link(
+            promo_hash,
+            cart_hash.clone(),
+            LinkTypes::PromoCodeUse,
+            (),
+        )?;
+    }
+
+    // Clear the private cart now that the order is published.
+    crate::cart::write_private_cart(PrivateCart {
+        items: Vec::new(),
+        last_updated: now,
+        promo_code_hash: None,
+    })?;
+
+    emit_signal(crate::AppSignal::OrderCreated {
+        order_hash: cart_hash.clone(),
+    })?;
+    Ok(cart_hash)
+}
+
+#[hdk_extern]
+pub fn checkout_cart(input: CheckoutCartInput) -> ExternResult<ActionHash> {
+    checkout_cart_impl(input)
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct DeliveryFeePreviewInput {
+    #[serde(alias = "cartTotal")]
+    pub cart_total: f64,
+    /// Reserved for zone-based pricing; not used by the current rules.
+    #[serde(default, alias = "addressHash")]
+    pub address_hash: Option<ActionHash>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct DeliveryFeePreview {
+    pub delivery_fee: f64,
+    /// How much more the customer would need to spend for free
+    /// delivery, when a threshold is configured and not yet met.
+    pub amount_to_free_delivery: Option<f64>,
+}
+
+/// The delivery fee the current rules would charge for a given cart
+/// subtotal, so the cart UI can show it before the customer commits.
+#[hdk_extern]
+pub fn get_delivery_fee_preview(input: DeliveryFeePreviewInput) -> ExternResult<DeliveryFeePreview> {
+    let config = dna_properties()?.delivery;
+    let delivery_fee = round_cents(config.fee_for_subtotal(input.cart_total));
+    let amount_to_free_delivery = if config.free_over > 0.0 && input.cart_total < config.free_over
+    {
+        Some(round_cents(config.free_over - input.cart_total))
+    } else {
+        None
+    };
+    Ok(DeliveryFeePreview {
+        delivery_fee,
+        amount_to_free_delivery,
+    })
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct CheckedOutCartWithHash {
+    pub cart_hash: ActionHash,
+    pub cart: CheckedOutCart,
+}
+
+/// Orders a customer is done with move from the "customer" tag to
+/// "archived" so the default listing stays small.
+const ARCHIVED_TAG: &[u8] = b"archived";
+/// Completed and cancelled orders are archived automatically after
+/// this long.
+const AUTO_ARCHIVE_AFTER_MS: u64 = 30 * 24 * 60 * 60 * 1000;
+
+fn is_archived(link: &Link) -> bool {
+    link.tag.as_ref().as_slice() == ARCHIVED_TAG
+}
+
+fn status_tag_byte(status: OrderStatus) -> u8 {
+    match status {
+        OrderStatus::Processing => 0,
+        OrderStatus::Confirmed => 1,
+        OrderStatus::Shopping => 2,
+        OrderStatus::Delivering => 3,
+        OrderStatus::Completed => 4,
+        OrderStatus::Returned => 5,
+        OrderStatus::Cancelled => 6,
+    }
+}
+
+fn status_from_tag_byte(byte: u8) -> Option<OrderStatus> {
+    Some(match byte {
+        0 => OrderStatus::Processing,
+        1 => OrderStatus::Confirmed,
+        2 => OrderStatus::Shopping,
+        3 => OrderStatus::Delivering,
+        4 => OrderStatus::Completed,
+        5 => OrderStatus::Returned,
+        6 => OrderStatus::Cancelled,
+        _ => return None,
+    })
+}
+
+/// The order facts encoded into a customer order link tag, so listing
+/// screens can work without fetching the entries.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct OrderTagData {
+    pub status: Option<OrderStatus>,
+    pub created_at: u64,
+    pub item_count: Option<u32>,
+    /// Total in cents.
+    pub total_cents: Option<u64>,
+}
+
+/// Customer order links carry status, `created_at`, item count and
+/// total in the tag ("customer:" + status byte + 8 + 4 + 8 big-endian
+/// bytes), so history can be filtered, paged and summarized without
+/// fetching order entries. The link is re-tagged on every status
+/// transition.
+impl LinkTagCodec for OrderTagData {
+    fn encode(&self) -> LinkTag {
+        let mut bytes = b"customer:".to_vec();
+        bytes.push(self.status.map_or(u8::MAX, status_tag_byte));
+        bytes.extend_from_slice(&self.created_at.to_be_bytes());
+        bytes.extend_from_slice(&self.item_count.unwrap_or(0).to_be_bytes());
+        bytes.extend_from_slice(&self.total_cents.unwrap_or(0).to_be_bytes());
+        LinkTag::new(bytes)
+    }
+
+    /// Tolerates the older layouts (created_at only, no summary
+    /// fields); plain "customer" markers decode to `None`.
+    fn decode(tag: &LinkTag) -> Option<Self> {
+        let bytes = tag.as_ref().as_slice().strip_prefix(b"customer:")?;
+        if bytes.len() >= 9 {
+            if let Ok(created_at) = <[u8; 8]>::try_from(&bytes[1..9]) {
+                let item_count = <[u8; 4]>::try_from(&bytes[9..13.min(bytes.len())])
+                    .ok()
+                    .map(u32::from_be_bytes);
+                let total_cents = if bytes.len() >= 21 {
+                    <[u8; 8]>::try_from(&bytes[13..21]).ok().map(u64::from_be_bytes)
+                } else {
+                    None
+                };
+                return Some(OrderTagData {
+                    status: status_from_tag_byte(bytes[0]),
+                    created_at: u64::from_be_bytes(created_at),
+                    item_count,
+                    total_cents,
+                });
+            }
+        }
+        let created_at = <[u8; 8]>::try_from(bytes).ok()?;
+        Some(OrderTagData {
+            status: None,
+            created_at: u64::from_be_bytes(created_at),
+            item_count: None,
+            total_cents: None,
+        })
+    }
+}
+
+pub(crate) fn customer_order_tag(status: OrderStatus, cart: &CheckedOutCart) -> LinkTag {
+    OrderTagData {
+        status: Some(status),
+        created_at: cart.created_at,
+        item_count: Some(cart.products.len() as u32),
+        total_cents: Some((cart.total * 100.0).round() as u64),
+    }
+    .encode()
+}
+
+/// Decode an order link tag, falling back to the link's own timestamp
+/// for tags from before the encoding existed.
+pub(crate) fn decode_order_tag(link: &Link) -> OrderTagData {
+    OrderTagData::decode(&link.tag).unwrap_or(OrderTagData {
+        status: None,
+        created_at: link.timestamp.as_millis() as u64,
+        item_count: None,
+        total_cents: None,
+    })
+}
+
+fn created_at_from_link(link: &Link) -> u64 {
+    decode_order_tag(link).created_at
+}
+
+/// Re-point the customer's order link at the current status after a
+/// transition, so link-level filtering stays accurate.
+pub(crate) fn retag_customer_link(
+    cart_hash: &ActionHash,
+    cart: &CheckedOutCart,
+) -> ExternResult<()> {
+    let customer = get(cart_hash.clone(), GetOptions::default())?
+        .ok_or(SummonError::not_found("CheckedOutCart"))?
+        .action()
+        .author()
+        .clone();
+    let links = get_links(
+        GetLinksInputBuilder::try_new(customer.clone(), LinkTypes::CheckedOutCart)?.build(),
+    )?;
+    for link in links {
+        if link.target.clone().into_action_hash() == Some(cart_hash.clone()) && !is_archived(&link)
+        {
+            delete_link(link.create_link_hash)?;
+            create_link(
+                customer.clone(),
+                cart_hash.clone(),
+                LinkTypes::CheckedOutCart,
+                customer_order_tag(cart.status, cart),
+            )?;
+        }
+    }
+    Ok(())
+}
+
+pub fn get_checked_out_carts_impl() -> ExternResult<Vec<CheckedOutCartWithHash>> {
+    let agent = agent_info()?.agent_initial_pubkey;
+    let mut links = get_links(
+        GetLinksInputBuilder::try_new(agent, LinkTypes::CheckedOutCart)?.build(),
+    )?;
+    links.retain(|link| !is_archived(link));
+
+    let mut carts = Vec::new();
+    for link in links {
+        let Some(hash) = link.target.into_action_hash() else {
+            continue;
+        };
+        let Some(record) = get(hash.clone(), GetOptions::default())? else {
+            continue;
+        };
+        if let Some(cart) = record
+            .entry()
+            .to_app_option::<CheckedOutCart>()
+            .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        {
+            // Orders returned to shopping are filtered out of the list.
+            if cart.status != OrderStatus::Returned {
+                carts.push(CheckedOutCartWithHash { cart_hash: hash, cart });
+            }
+        }
+    }
+
+    carts.sort_by_key(|entry| std::cmp::Reverse(entry.cart.created_at));
+    Ok(carts)
+}
+
+#[hdk_extern]
+pub fn get_checked_out_carts(_: ()) -> ExternResult<Vec<CheckedOutCartWithHash>> {
+    get_checked_out_carts_impl()
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct GetOrdersPageInput {
+    /// `cursor` is the `created_at` of the last order of the previous
+    /// page; omit for the first page.
+    #[serde(flatten)]
+    pub page: PageRequest,
+    #[serde(alias = "statusFilter")]
+    pub status_filter: Option<OrderStatus>,
+    /// Keep only orders in any of these statuses; empty means all.
+    #[serde(default)]
+    pub statuses: Vec<OrderStatus>,
+    /// Keep only orders created at or after this time (unix ms).
+    #[serde(default, alias = "fromTs")]
+    pub from_ts: Option<u64>,
+    /// Keep only orders created at or before this time (unix ms).
+    #[serde(default, alias = "toTs")]
+    pub to_ts: Option<u64>,
+    /// Keep only delivery or only pickup orders; omit for both.
+    #[serde(default, alias = "fulfillmentFilter")]
+    pub fulfillment_filter: Option<FulfillmentKind>,
+}
+
+/// Discriminates orders by how they are fulfilled, for filtering.
+/// Orders from before pickup existed count as delivery.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FulfillmentKind {
+    Delivery,
+    Pickup,
+}
+
+pub(crate) fn fulfillment_kind(cart: &CheckedOutCart) -> FulfillmentKind {
+    match cart.fulfillment_method {
+        Some(FulfillmentMethod::Pickup { .. }) => FulfillmentKind::Pickup,
+        _ => FulfillmentKind::Delivery,
+    }
+}
+
+/// Page through the caller's active order history, newest first. Link
+/// tags carry `created_at`, so cursoring never fetches order entries
+/// outside the requested page.
+#[hdk_extern]
+pub fn get_orders_page(
+    input: GetOrdersPageInput,
+) -> ExternResult<Page<CheckedOutCartWithHash>> {
+    let agent = agent_info()?.agent_initial_pubkey;
+    let mut links = get_links(
+        GetLinksInputBuilder::try_new(agent, LinkTypes::CheckedOutCart)?.build(),
+    )?;
+    links.retain(|link| !is_archived(link));
+    if let Some(cursor) = input.page.cursor {
+        links.retain(|link| created_at_from_link(link) < cursor);
+    }
+
+    // Status and date filters run on the decoded tags, so no order
+    // entry outside the final page gets fetched.
+    let mut statuses = input.statuses;
+    if let Some(status) = input.status_filter {
+        if !statuses.contains(&status) {
+            statuses.push(status);
+        }
+    }
+    if let Some(from_ts) = input.from_ts {
+        links.retain(|link| created_at_from_link(link) >= from_ts);
+    }
+    if let Some(to_ts) = input.to_ts {
+        links.retain(|link| created_at_from_link(link) <= to_ts);
+    }
+    if !statuses.is_empty() {
+        links.retain(|link| match decode_order_tag(link).status {
+            Some(status) => statuses.contains(&status),
+            // Legacy tags don't carry a status; resolve after fetch.
+            None => true,
+        });
+    }
+    links.sort_by_key(|entry| std::cmp::Reverse(created_at_from_link(entry)));
+
+    let limit = input.page.limit_or(20);
+    let total = links.len();
+    let mut orders = Vec::new();
+    let mut next_cursor = None;
+    for link in &links {
+        if orders.len() >= limit {
+            break;
+        }
+        let Some(hash) = link.target.clone().into_action_hash() else {
+            continue;
+        };
+        let Ok((_, cart)) = latest_order_revision(hash.clone()) else {
+            continue;
+        };
+        if !statuses.is_empty() && !statuses.contains(&cart.status) {
+            continue;
+        }
+        if input
+            .fulfillment_filter
+            .is_some_and(|kind| fulfillment_kind(&cart) != kind)
+        {
+            continue;
+        }
+        next_cursor = Some(cart.created_at);
+        orders.push(CheckedOutCartWithHash {
+            cart_hash: hash,
+            cart,
+        });
+    }
+    // Nothing older than the last returned order means no next page.
+    if let Some(cursor) = next_cursor {
+        if !links
+            .iter()
+            .any(|link| created_at_from_link(link) < cursor)
+        {
+            next_cursor = None;
+        }
+    }
+    Ok(Page {
+        items: orders,
+        has_more: next_cursor.is_some(),
+        cursor: next_cursor,
+        total,
+    })
+}
+
+/// Move the caller's link to an order under the "archived" tag, taking
+/// it out of the default order listing.
+#[hdk_extern]
+pub fn archive_order(cart_hash: ActionHash) -> ExternResult<()> {
+    let agent = agent_info()?.agent_initial_pubkey;
+    let links = get_links(
+        GetLinksInputBuilder::try_new(agent.clone(), LinkTypes::CheckedOutCart)?.build(),
+    )?;
+    for link in links {
+        if link.target.clone().into_action_hash() == Some(cart_hash.clone()) && !is_archived(&link)
+        {
+            delete_link(link.create_link_hash)?;
+            create_link(
+                agent.clone(),
+                cart_hash.clone(),
+                LinkTypes::CheckedOutCart,
+                LinkTag::new(ARCHIVED_TAG.to_vec()),
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Page through archived orders, newest first. The cursor is an offset
+/// into the archived listing.
+#[hdk_extern]
+pub fn get_archived_orders(
+    query: PageRequest,
+) -> ExternResult<Page<CheckedOutCartWithHash>> {
+    let agent = agent_info()?.agent_initial_pubkey;
+    let mut links = get_links(
+        GetLinksInputBuilder::try_new(agent, LinkTypes::CheckedOutCart)?.build(),
+    )?;
+    links.retain(is_archived);
+    links.sort_by_key(|entry| std::cmp::Reverse(entry.timestamp));
+
+    let offset = query.offset();
+    let limit = query.limit_or(20);
+    let total = links.len();
+
+    let mut carts = Vec::new();
+    for link in links.into_iter().skip(offset).take(limit) {
+        let Some(hash) = link.target.into_action_hash() else {
+            continue;
+        };
+        let Ok((_, cart)) = latest_order_revision(hash.clone()) else {
+            continue;
+        };
+        carts.push(CheckedOutCartWithHash {
+            cart_hash: hash,
+            cart,
+        });
+    }
+    Ok(Page::from_offset(carts, offset, total))
+}
+
+/// Scheduled daily through [`crate::scheduler`]: archive finished
+/// orders older than the retention window so the active listing
+/// doesn't grow without bound.
+pub(crate) fn auto_archive_orders() -> ExternResult<()> {
+    let now = sys_time()?.as_millis() as u64;
+    for entry in get_checked_out_carts_impl()? {
+        let finished = matches!(
+            entry.cart.status,
+            OrderStatus::Completed | OrderStatus::Cancelled
+        );
+        if finished && now.saturating_sub(entry.cart.created_at) > AUTO_ARCHIVE_AFTER_MS {
+            archive_order(entry.cart_hash)?;
+        }
+    }
+    Ok(())
+}
+
+/// The newest revision of an order, following the entry's update chain
+/// from the create the customer links to.
+pub(crate) fn latest_order_revision(
+    cart_hash: ActionHash,
+) -> ExternResult<(ActionHash, CheckedOutCart)> {
+    let details = get_details(cart_hash.clone(), GetOptions::default())?.ok_or(SummonError::not_found("CheckedOutCart"))?;
+    let Details::Record(details) = details else {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Expected record details".to_string()
+        )));
+    };
+
+    let mut newest = (cart_hash, details.record);
+    let mut updates = details.updates;
+    while let Some(update) = updates
+        .iter()
+        .max_by_key(|update| update.action().timestamp())
+        .cloned()
+    {
+        let hash = update.action_address().clone();
+        let Some(update_details) = get_details(hash.clone(), GetOptions::default())? else {
+            break;
+        };
+        let Details::Record(update_details) = update_details else {
+            break;
+        };
+        newest = (hash, update_details.record);
+        updates = update_details.updates;
+    }
+
+    let cart: CheckedOutCart = newest
+        .1
+        .entry()
+        .to_app_option()
+        .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        .ok_or(wasm_error!(WasmErrorInner::Guest(
+            "Record is not a CheckedOutCart".to_string()
+        )))?;
+    Ok((newest.0, cart))
+}
+
+/// An order moved through the fulfillment state machine, pushed to the
+/// customer so their app can stop polling order history.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum OrderStatusSignal {
+    StatusChanged {
+        order_hash: ActionHash,
+        status: OrderStatus,
+        actor: AgentPubKey,
+        timestamp: u64,
+    },
+}
+
+/// Move an order along the fulfillment state machine, stamping the
+/// transition into its status history and pushing the change to the
+/// customer. Transition legality is enforced again in validation.
+pub(crate) fn transition_order_status(
+    cart_hash: ActionHash,
+    status: OrderStatus,
+) -> ExternResult<(ActionHash, CheckedOutCart)> {
+    let (newest_hash, mut cart) = latest_order_revision(cart_hash.clone())?;
+    if !cart.status.can_transition_to(status) {
+        return Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "Cannot move order from {:?} to {:?}",
+            cart.status, status
+        ))));
+    }
+    let actor = agent_info()?.agent_initial_pubkey;
+    let timestamp = sys_time()?.as_millis() as u64;
+    cart.status = status;
+    cart.status_history.push(StatusChange {
+        status,
+        timestamp,
+        actor: actor.clone(),
+    });
+    let update_hash = update_entry(newest_hash, &EntryTypes::CheckedOutCart(cart.clone()))?;
+    retag_customer_link(&cart_hash, &cart)?;
+    emit_signal(crate::AppSignal::OrderStatusChanged {
+        order_hash: cart_hash.clone(),
+        status,
+    })?;
+
+    let customer = crate::tracking::order_customer(&cart_hash)?;
+    if customer != actor {
+        crate::signal::push_remote_signal(
+            OrderStatusSignal::StatusChanged {
+                order_hash: cart_hash,
+                status,
+                actor,
+                timestamp,
+            },
+            vec![customer],
+        )?;
+    }
+    Ok((update_hash, cart))
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct AdvanceOrderStatusInput {
+    #[serde(alias = "cartHash")]
+    pub cart_hash: ActionHash,
+    pub status: OrderStatus,
+}
+
+/// Fulfiller-side extern to advance an order (Confirmed, Shopping,
+/// Delivering, Completed), so customers can see where their order
+/// actually is.
+#[hdk_extern]
+pub fn advance_order_status(input: AdvanceOrderStatusInput) -> ExternResult<ActionHash> {
+    if OrderStatus::customer_only(input.status) {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Use return_to_shopping or cancel_order for customer transitions".to_string()
+        )));
+    }
+    let (update_hash, cart) = transition_order_status(input.cart_hash.clone(), input.status)?;
+    // Taking on the shopping is the assignment; ask the customer's cell
+    // to grant us their (private) delivery address.
+    if input.status == OrderStatus::Shopping && cart.address_hash.is_some() {
+        let customer = crate::tracking::order_customer(&input.cart_hash)?;
+        crate::signal::push_remote_signal(AddressRequestSignal { order_hash: input.cart_hash }, vec![customer])?;
+    }
+    Ok(update_hash)
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct SetItemFulfillmentInput {
+    #[serde(alias = "cartHash")]
+    pub cart_hash: ActionHash,
+    #[serde(alias = "groupHash")]
+    pub group_hash: ActionHash,
+    #[serde(alias = "productIndex")]
+    pub product_index: u32,
+    pub fulfillment: ItemFulfillment,
+}
+
+/// Record what happened to one line while shopping the order, so the
+/// final record and receipt reflect what was actually delivered rather
+/// than what was requested.
+#[hdk_extern]
+pub fn set_item_fulfillment(input: SetItemFulfillmentInput) -> ExternResult<ActionHash> {
+    let (newest_hash, mut cart) = latest_order_revision(input.cart_hash.clone())?;
+    if cart.status != OrderStatus::Shopping {
+        return Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "Item fulfillment can only be recorded while shopping (status {:?})",
+            cart.status
+        ))));
+    }
+    let agent = agent_info()?.agent_initial_pubkey;
+    if crate::shopper::order_claimer(&input.cart_hash)?.as_ref() != Some(&agent) {
+        return Err(SummonError::not_authorized(
+            "Only the assigned shopper may record item fulfillments",
+        )
+        .into());
+    }
+
+    let position = cart
+        .products
+        .iter()
+        .position(|item| {
+            item.group_hash == input.group_hash && item.product_index == input.product_index
+        })
+        .ok_or(wasm_error!(WasmErrorInner::Guest(
+            "Order has no such line".to_string()
+        )))?;
+
+    if cart.item_fulfillments.is_empty() {
+        cart.item_fulfillments = vec![None; cart.products.len()];
+    }
+    cart.item_fulfillments[position] = Some(input.fulfillment);
+    update_entry(newest_hash, &EntryTypes::CheckedOutCart(cart))
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct MarkItemPickedInput {
+    #[serde(alias = "orderHash")]
+    pub order_hash: ActionHash,
+    /// Position of the line in the order, as in the pick list.
+    #[serde(alias = "itemIndex")]
+    pub item_index: u32,
+    /// What was actually bagged; differs from the requested quantity
+    /// for weight-based items.
+    #[serde(alias = "actualQuantity")]
+    pub actual_quantity: f64,
+}
+
+/// Check one line off while shopping, recording the actual quantity
+/// picked and pushing a progress signal to the customer. Restricted to
+/// the shopper assigned to the order.
+#[hdk_extern]
+pub fn mark_item_picked(input: MarkItemPickedInput) -> ExternResult<ActionHash> {
+    let (newest_hash, cart) = latest_order_revision(input.order_hash.clone())?;
+    if cart.status != OrderStatus::Shopping {
+        return Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "Items can only be picked while shopping (status {:?})",
+            cart.status
+        ))));
+    }
+    let agent = agent_info()?.agent_initial_pubkey;
+    if crate::shopper::order_claimer(&input.order_hash)?.as_ref() != Some(&agent) {
+        return Err(SummonError::not_authorized(
+            "Only the assigned shopper may pick items",
+        )
+        .into());
+    }
+    if input.actual_quantity <= 0.0 {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Picked quantity must be positive; use set_item_fulfillment for out-of-stock lines"
+                .to_string()
+        )));
+    }
+    apply_picked_quantity(
+        newest_hash,
+        cart,
+        input.order_hash,
+        input.item_index,
+        input.actual_quantity,
+    )
+}
+
+/// Shared tail of the pick and weight confirmations: write the
+/// `Picked` fulfillment and push the progress signal to the customer
+/// (skipped when they are the caller).
+fn apply_picked_quantity(
+    newest_hash: ActionHash,
+    mut cart: CheckedOutCart,
+    order_hash: ActionHash,
+    item_index: u32,
+    actual_quantity: f64,
+) -> ExternResult<ActionHash> {
+    let position = item_index as usize;
+    if position >= cart.products.len() {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Order has no such line".to_string()
+        )));
+    }
+
+    if cart.item_fulfillments.is_empty() {
+        cart.item_fulfillments = vec![None; cart.products.len()];
+    }
+    cart.item_fulfillments[position] = Some(ItemFulfillment::Picked { actual_quantity });
+    let picked_count = cart
+        .item_fulfillments
+        .iter()
+        .filter(|entry| entry.is_some())
+        .count() as u32;
+    let item_count = cart.products.len() as u32;
+    let update_hash = update_entry(newest_hash, &EntryTypes::CheckedOutCart(cart))?;
+
+    let customer = crate::tracking::order_customer(&order_hash)?;
+    if customer != agent_info()?.agent_initial_pubkey {
+        crate::signal::push_remote_signal(
+            crate::tracking::TrackingSignal::ItemPicked {
+                order_hash,
+                item_index,
+                picked_count,
+                item_count,
+            },
+            vec![customer],
+        )?;
+    }
+    Ok(update_hash)
+}
+
+/// The weight-approval round trip for weighed items that came in far
+/// off the requested quantity.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum WeightSignal {
+    /// Pushed to the customer when a confirmed weight lands outside
+    /// the configured tolerance.
+    ApprovalRequested {
+        order_hash: ActionHash,
+        item_index: u32,
+        requested_quantity: f64,
+        actual_weight: f64,
+    },
+    /// Pushed back to the shopper when the customer accepts it.
+    Approved {
+        order_hash: ActionHash,
+        item_index: u32,
+        actual_weight: f64,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct ConfirmItemWeightInput {
+    #[serde(alias = "orderHash")]
+    pub order_hash: ActionHash,
+    #[serde(alias = "itemIndex")]
+    pub item_index: u32,
+    /// What the scale said, in the same unit the quantity was ordered
+    /// in (pounds).
+    #[serde(alias = "actualWeight")]
+    pub actual_weight: f64,
+}
+
+/// Outcome of a weight confirmation: applied directly, or parked
+/// pending the customer's approval because it was out of tolerance.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct WeightConfirmation {
+    pub applied: bool,
+    pub update_hash: Option<ActionHash>,
+}
+
+/// Record the actual weight of a weighed line. Within the configured
+/// tolerance of the requested quantity it applies straight to the
+/// fulfillment (and so to the receipt); outside it, the customer is
+/// asked to approve via [`approve_item_weight`].
+#[hdk_extern]
+pub fn confirm_item_weight(input: ConfirmItemWeightInput) -> ExternResult<WeightConfirmation> {
+    let (newest_hash, cart) = latest_order_revision(input.order_hash.clone())?;
+    if cart.status != OrderStatus::Shopping {
+        return Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "Weights can only be confirmed while shopping (status {:?})",
+            cart.status
+        ))));
+    }
+    let agent = agent_info()?.agent_initial_pubkey;
+    if crate::shopper::order_claimer(&input.order_hash)?.as_ref() != Some(&agent) {
+        return Err(SummonError::not_authorized(
+            "Only the assigned shopper may confirm weights",
+        )
+        .into());
+    }
+    if input.actual_weight <= 0.0 {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Confirmed weight must be positive".to_string()
+        )));
+    }
+    let item = cart
+        .products
+        .get(input.item_index as usize)
+        .ok_or(wasm_error!(WasmErrorInner::Guest(
+            "Order has no such line".to_string()
+        )))?;
+    if item.sold_by != Some(SoldBy::Weight) {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Line is not sold by weight; use mark_item_picked".to_string()
+        )));
+    }
+    let requested = item.quantity;
+
+    if !crate::checkout::dna_properties()?
+        .weights
+        .within(requested, input.actual_weight)
+    {
+        let customer = crate::tracking::order_customer(&input.order_hash)?;
+        crate::signal::push_remote_signal(
+            WeightSignal::ApprovalRequested {
+                order_hash: input.order_hash,
+                item_index: input.item_index,
+                requested_quantity: requested,
+                actual_weight: input.actual_weight,
+            },
+            vec![customer],
+        )?;
+        return Ok(WeightConfirmation {
+            applied: false,
+            update_hash: None,
+        });
+    }
+
+    let update_hash = apply_picked_quantity(
+        newest_hash,
+        cart,
+        input.order_hash,
+        input.item_index,
+        input.actual_weight,
+    )?;
+    Ok(WeightConfirmation {
+        applied: true,
+        update_hash: Some(update_hash),
+    })
+}
+
+/// Customer-side acceptance of an out-of-tolerance weight: applies the
+/// fulfillment and tells the shopper to bag it.
+#[hdk_extern]
+pub fn approve_item_weight(input: ConfirmItemWeightInput) -> ExternResult<ActionHash> {
+    if crate::tracking::order_customer(&input.order_hash)? != agent_info()?.agent_initial_pubkey
+    {
+        return Err(SummonError::not_authorized(
+            "Only the customer may approve an out-of-tolerance weight",
+        )
+        .into());
+    }
+    let (newest_hash, cart) = latest_order_revision(input.order_hash.clone())?;
+    if cart.status != OrderStatus::Shopping {
+        return Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "Weights can only be approved while shopping (status {:?})",
+            cart.status
+        ))));
+    }
+    let update_hash = apply_picked_quantity(
+        newest_hash,
+        cart,
+        input.order_hash.clone(),
+        input.item_index,
+        input.actual_weight,
+    )?;
+    if let Some(shopper) = crate::shopper::order_claimer(&input.order_hash)? {
+        crate::signal::push_remote_signal(
+            WeightSignal::Approved {
+                order_hash: input.order_hash,
+                item_index: input.item_index,
+                actual_weight: input.actual_weight,
+            },
+            vec![shopper],
+        )?;
+    }
+    Ok(update_hash)
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct SplitCheckoutResult {
+    /// Present when the cart spanned more than one store.
+    pub bundle_hash: Option<ActionHash>,
+    /// One order per store, in checkout order.
+    pub order_hashes: Vec<ActionHash>,
+}
+
+/// Check out a cart that may span several stores: lines are partitioned
+/// by the store id on their catalog listing and one order is published
+/// per store, grouped under an [`OrderBundle`]. A promo code or gift
+/// card attached to the cart is applied to the first store's order
+/// only. Single-store carts behave exactly like `checkout_cart`.
+#[hdk_extern]
+pub fn checkout_cart_by_store(mut input: CheckoutCartInput) -> ExternResult<SplitCheckoutResult> {
+    if input.cart_products.is_empty() {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Cannot check out an empty cart".to_string()
+        )));
+    }
+
+    let snapshots = fetch_product_snapshots(&input.cart_products)?;
+    let mut stores: Vec<(Option<String>, Vec<CartProduct>)> = Vec::new();
+    for (item, snapshot) in input.cart_products.iter().zip(&snapshots) {
+        match stores.iter_mut().find(|(store, _)| *store == snapshot.store_id) {
+            Some((_, products)) => products.push(item.clone()),
+            None => stores.push((snapshot.store_id.clone(), vec![item.clone()])),
+        }
+    }
+
+    if stores.len() == 1 {
+        return Ok(SplitCheckoutResult {
+            bundle_hash: None,
+            order_hashes: vec![checkout_cart_impl(input)?],
+        });
+    }
+
+    let mut order_hashes = Vec::new();
+    for (position, (_, cart_products)) in stores.into_iter().enumerate() {
+        let part = CheckoutCartInput {
+            address_hash: input.address_hash.clone(),
+            delivery_instructions: input.delivery_instructions.clone(),
+            delivery_time: input.delivery_time.clone(),
+            cart_products,
+            remember_notes: if position == 0 {
+                std::mem::take(&mut input.remember_notes)
+            } else {
+                Vec::new()
+            },
+            gift_card_hash: if position == 0 {
+                input.gift_card_hash.take()
+            } else {
+                None
+            },
+            fulfillment_method: input.fulfillment_method.clone(),
+            delivery_handoff: input.delivery_handoff.clone(),
+            pseudonymous_address: input.pseudonymous_address.clone(),
+        };
+        order_hashes.push(checkout_cart_impl(part)?);
+    }
+
+    let bundle = OrderBundle {
+        order_hashes: order_hashes.clone(),
+        created_at: sys_time()?.as_millis() as u64,
+    };
+    let bundle_hash = create_entry(&EntryTypes::OrderBundle(bundle))?;
+    create_link(
+        agent_info()?.agent_initial_pubkey,
+        bundle_hash.clone(),
+        LinkTypes::OrderBundle,
+        (),
+    )?;
+
+    Ok(SplitCheckoutResult {
+        bundle_hash: Some(bundle_hash),
+        order_hashes,
+    })
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct OrderBundleWithHash {
+    pub bundle_hash: ActionHash,
+    pub bundle: OrderBundle,
+}
+
+/// The caller's split-checkout bundles, newest first.
+#[hdk_extern]
+pub fn get_order_bundles(_: ()) -> ExternResult<Vec<OrderBundleWithHash>> {
+    let agent = agent_info()?.agent_initial_pubkey;
+    let links = get_links(
+        GetLinksInputBuilder::try_new(agent, LinkTypes::OrderBundle)?.build(),
+    )?;
+
+    let mut bundles = Vec::new();
+    for link in links {
+        let Some(hash) = link.target.into_action_hash() else {
+            continue;
+        };
+        let Some(record) = get(hash.clone(), GetOptions::default())? else {
+            continue;
+        };
+        if let Some(bundle) = record
+            .entry()
+            .to_app_option::<OrderBundle>()
+            .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        {
+            bundles.push(OrderBundleWithHash {
+                bundle_hash: hash,
+                bundle,
+            });
+        }
+    }
+    bundles.sort_by_key(|entry| std::cmp::Reverse(entry.bundle.created_at));
+    Ok(bundles)
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct UpdateDeliveryDetailsInput {
+    #[serde(alias = "cartHash")]
+    pub cart_hash: ActionHash,
+    #[serde(alias = "deliveryInstructions")]
+    pub delivery_instructions: Option<String>,
+    #[serde(alias = "deliveryTime")]
+    pub delivery_time: Option<DeliveryTimeSlot>,
+}
+
+/// Change the delivery instructions or window on a published order.
+/// Allowed until shopping starts; the replaced values are appended to
+/// the order's history so earlier instructions stay on record.
+#[hdk_extern]
+pub fn update_delivery_details(input: UpdateDeliveryDetailsInput) -> ExternResult<ActionHash> {
+    let (newest_hash, mut cart) = latest_order_revision(input.cart_hash.clone())?;
+    if !matches!(
+        cart.status,
+        OrderStatus::Processing | OrderStatus::Confirmed
+    ) {
+        return Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "Delivery details are locked once shopping starts (status {:?})",
+            cart.status
+        ))));
+    }
+    if crate::tracking::order_customer(&input.cart_hash)? != agent_info()?.agent_initial_pubkey {
+        return Err(SummonError::not_authorized(
+            "Only the customer may change their delivery details",
+        )
+        .into());
+    }
+    if cart.delivery_instructions == input.delivery_instructions
+        && cart.delivery_time == input.delivery_time
+    {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Delivery details are unchanged".to_string()
+        )));
+    }
+
+    cart.delivery_details_history.push(DeliveryDetailsChange {
+        delivery_instructions: cart.delivery_instructions.take(),
+        delivery_time: cart.delivery_time.take(),
+        changed_at: sys_time()?.as_millis() as u64,
+    });
+    cart.delivery_instructions = input.delivery_instructions;
+    cart.delivery_time = input.delivery_time;
+    update_entry(newest_hash, &EntryTypes::CheckedOutCart(cart))
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct AmendOrderInput {
+    #[serde(alias = "cartHash")]
+    pub cart_hash: ActionHash,
+    #[serde(alias = "itemChanges")]
+    pub item_changes: Vec<crate::cart::CartItemChange>,
+}
+
+/// Emitted when a customer amends an order that is already published,
+/// so a fulfiller watching the order can pick up the new contents.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct OrderAmendedSignal {
+    pub cart_hash: ActionHash,
+    pub update_hash: ActionHash,
+    pub order_id: String,
+}
+
+/// Amend a published order in place while it is still Processing or
+/// Confirmed: apply the item changes, re-snapshot and re-price the
+/// whole order, and publish an update — no return_to_shopping round
+/// trip. A non-positive quantity removes the line.
+#[hdk_extern]
+pub fn amend_order(input: AmendOrderInput) -> ExternResult<ActionHash> {
+    let (newest_hash, mut cart) = latest_order_revision(input.cart_hash.clone())?;
+    if !matches!(
+        cart.status,
+        OrderStatus::Processing | OrderStatus::Confirmed
+    ) {
+        return Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "Order can no longer be amended (status {:?})",
+            cart.status
+        ))));
+    }
+    if crate::tracking::order_customer(&input.cart_hash)? != agent_info()?.agent_initial_pubkey {
+        return Err(SummonError::not_authorized("Only the customer may amend their order").into());
+    }
+
+    let now = sys_time()?.as_millis() as u64;
+    for change in input.item_changes {
+        if change.quantity <= 0.0 {
+            cart.products.retain(|item| {
+                !(item.group_hash == change.group_hash
+                    && item.product_index == change.product_index)
+            });
+            continue;
+        }
+        match cart.products.iter_mut().find(|item| {
+            item.group_hash == change.group_hash
+                && item.product_index == change.product_index
+        }) {
+            Some(item) => {
+                validate_quantity(change.quantity, item.sold_by.or(change.sold_by))
+                    .map_err(|reason| wasm_error!(WasmErrorInner::Guest(reason)))?;
+                item.quantity = change.quantity;
+                item.timestamp = now;
+                if change.note.is_some() {
+                    item.note = change.note;
+                }
+                if change.substitution_preference.is_some() {
+                    item.substitution_preference = change.substitution_preference;
+                }
+            }
+            None => {
+                validate_quantity(change.quantity, change.sold_by)
+                    .map_err(|reason| wasm_error!(WasmErrorInner::Guest(reason)))?;
+                cart.products.push(CartProduct {
+                    group_hash: change.group_hash,
+                    product_index: change.product_index,
+                    quantity: change.quantity,
+                    timestamp: now,
+                    note: change.note,
+                    substitution_preference: change.substitution_preference,
+                    sold_by: change.sold_by,
+                });
+            }
+        }
+    }
+    if cart.products.is_empty() {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Amendment would leave the order empty; use cancel_order instead".to_string()
+        )));
+    }
+
+    // Re-price the amended order exactly as checkout does.
+    cart.product_snapshots = fetch_product_snapshots(&cart.products)?;
+    cart.line_totals = cart
+        .products
+        .iter()
+        .zip(&cart.product_snapshots)
+        .map(|(item, snapshot)| {
+            let unit_price = snapshot.promo_price.unwrap_or(snapshot.price);
+            round_cents(unit_price * item.quantity)
+        })
+        .collect();
+    cart.subtotal = round_cents(cart.line_totals.iter().sum());
+    if let Some(promo_hash) = &cart.promo_code_hash {
+        let record = get(promo_hash.clone(), GetOptions::default())?.ok_or(SummonError::not_found("Applied promo code"))?;
+        let promo: PromoCode = record
+            .entry()
+            .to_app_option()
+            .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+            .ok_or(wasm_error!(WasmErrorInner::Guest(
+                "Record is not a PromoCode".to_string()
+            )))?;
+        cart.discount = round_cents(promo.discount.amount_for_subtotal(cart.subtotal));
+    }
+    let properties = dna_properties()?;
+    cart.tax_lines = compute_tax_lines(&properties.tax, &cart.product_snapshots, &cart.line_totals);
+    cart.tax = round_cents(cart.tax_lines.iter().map(|line| line.amount).sum());
+    cart.delivery_fee = match &cart.fulfillment_method {
+        Some(FulfillmentMethod::Pickup { .. }) => 0.0,
+        _ => round_cents(properties.delivery.fee_for_subtotal(cart.subtotal)),
+    };
+    cart.total = round_cents(cart.subtotal - cart.discount + cart.tax + cart.delivery_fee);
+    cart.gift_card_payment = cart.gift_card_payment.min(cart.total);
+
+    let update_hash = update_entry(newest_hash, &EntryTypes::CheckedOutCart(cart.clone()))?;
+    emit_signal(OrderAmendedSignal {
+        cart_hash: input.cart_hash,
+        update_hash: update_hash.clone(),
+        order_id: cart.id,
+    })?;
+    Ok(update_hash)
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct CancelOrderInput {
+    #[serde(alias = "cartHash")]
+    pub cart_hash: ActionHash,
+    pub reason: String,
+}
+
+/// Cancel an order outright, recording who and why, and put the items
+/// back in the customer's private cart. Only allowed before shopping
+/// starts; `return_to_shopping` stays the "edit my order" path.
+#[hdk_extern]
+pub fn cancel_order(input: CancelOrderInput) -> ExternResult<ActionHash> {
+    let (newest_hash, mut cart) = latest_order_revision(input.cart_hash.clone())?;
+    if !cart.status.can_transition_to(OrderStatus::Cancelled) {
+        return Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "Order can no longer be cancelled (status {:?})",
+            cart.status
+        ))));
+    }
+
+    let agent = agent_info()?.agent_initial_pubkey;
+    let now = sys_time()?.as_millis() as u64;
+
+    let mut private_cart = crate::cart::get_private_cart_impl()?;
+    for item in cart.products.iter().cloned() {
+        if !private_cart.items.iter().any(|existing| {
+            existing.group_hash == item.group_hash
+                && existing.product_index == item.product_index
+        }) {
+            private_cart.items.push(item);
+        }
+    }
+    private_cart.last_updated = now;
+    crate::cart::write_private_cart(private_cart)?;
+
+    cart.status = OrderStatus::Cancelled;
+    cart.status_history.push(StatusChange {
+        status: OrderStatus::Cancelled,
+        timestamp: now,
+        actor: agent.clone(),
+    });
+    cart.cancellation = Some(CancellationRecord {
+        reason: input.reason,
+        cancelled_by: agent,
+        cancelled_at: now,
+    });
+    let update_hash = update_entry(newest_hash, &EntryTypes::CheckedOutCart(cart.clone()))?;
+    retag_customer_link(&input.cart_hash, &cart)?;
+    // A cancelled pickup order gives its slot capacity back.
+    crate::pickup::release_slot_reservation(&input.cart_hash)?;
+    Ok(update_hash)
+}
+
+/// Pull a published order back into the private cart so the customer
+/// can edit it, marking the order Returned.
+#[hdk_extern]
+pub fn return_to_shopping(cart_hash: ActionHash) -> ExternResult<ActionHash> {
+    let (_, cart) = latest_order_revision(cart_hash.clone())?;
+
+    let now = sys_time()?.as_millis() as u64;
+    let mut private_cart = crate::cart::get_private_cart_impl()?;
+    for item in cart.products.iter().cloned() {
+        if !private_cart.items.iter().any(|existing| {
+            existing.group_hash == item.group_hash
+                && existing.product_index == item.product_index
+        }) {
+            private_cart.items.push(item);
+        }
+    }
+    private_cart.last_updated = now;
+    crate::cart::write_private_cart(private_cart)?;
+
+    Ok(transition_order_status(cart_hash, OrderStatus::Returned)?.0)
+}
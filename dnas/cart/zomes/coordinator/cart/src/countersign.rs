@@ -0,0 +1,137 @@
+use cart_integrity::*;
+use hdk::prelude::*;
+
+/// How long both agents have to complete a checkout session.
+const SESSION_MS: u64 = 60_000;
+
+/// A checkout countersigned by the customer and a store agent, so
+/// neither side can later claim the order contained different items,
+/// totals or delivery slot. The frontend shuttles the preflight request
+/// and responses between the two agents over remote calls.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct CountersignedCheckoutInput {
+    #[serde(alias = "storeAgent")]
+    pub store_agent: AgentPubKey,
+    /// The fully built order, exactly as both sides will sign it.
+    pub order: CheckedOutCart,
+}
+
+fn checked_out_cart_location(
+    order: &CheckedOutCart,
+) -> ExternResult<(ZomeIndex, EntryDefIndex)> {
+    let scoped: ScopedEntryDefIndex = (&EntryTypes::CheckedOutCart(order.clone())).try_into()?;
+    Ok((scoped.zome_index, scoped.zome_type))
+}
+
+/// Build the preflight request for a countersigned checkout. The
+/// customer calls this, then both agents accept the request before the
+/// session window closes.
+#[hdk_extern]
+pub fn get_checkout_preflight(
+    input: CountersignedCheckoutInput,
+) -> ExternResult<PreflightRequest> {
+    let customer = agent_info()?.agent_initial_pubkey;
+    let entry_hash = hash_entry(&input.order)?;
+    let (zome_index, entry_def_index) = checked_out_cart_location(&input.order)?;
+    let session_times = session_times_from_millis(SESSION_MS)?;
+
+    PreflightRequest::try_new(
+        entry_hash,
+        vec![(customer, vec![]), (input.store_agent, vec![])],
+        Vec::new(),
+        0,
+        false,
+        session_times,
+        ActionBase::Create(CreateBase::new(EntryType::App(AppEntryDef::new(
+            entry_def_index,
+            zome_index,
+            EntryVisibility::Public,
+        )))),
+        PreflightBytes(vec![]),
+    )
+    .map_err(|e| {
+        wasm_error!(WasmErrorInner::Guest(format!(
+            "Could not build checkout preflight: {:?}",
+            e
+        )))
+    })
+}
+
+/// Accept a checkout preflight, locking this agent's chain for the
+/// session window.
+#[hdk_extern]
+pub fn accept_checkout_preflight(
+    request: PreflightRequest,
+) -> ExternResult<PreflightResponse> {
+    match accept_countersigning_preflight_request(request)? {
+        PreflightRequestAcceptance::Accepted(response) => Ok(response),
+        other => Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "Checkout preflight was not accepted: {:?}",
+            other
+        )))),
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct CompleteCountersignedCheckoutInput {
+    pub order: CheckedOutCart,
+    /// Accepted responses from every signing agent, customer included.
+    pub responses: Vec<PreflightResponse>,
+}
+
+/// Commit the countersigned order. Both agents call this with the same
+/// order and the collected responses; the entry only becomes valid once
+/// everyone has committed within the session window.
+#[hdk_extern]
+pub fn complete_countersigned_checkout(
+    input: CompleteCountersignedCheckoutInput,
+) -> ExternResult<ActionHash> {
+    let session_data = CounterSigningSessionData::try_from_responses(input.responses, Vec::new())
+        .map_err(|e| {
+        wasm_error!(WasmErrorInner::Guest(format!(
+            "Could not build countersigning session: {:?}",
+            e
+        )))
+    })?;
+
+    let customer = session_data
+        .preflight_request()
+        .signing_agents
+        .first()
+        .map(|(signer, _)| signer.clone());
+
+    let (zome_index, entry_def_index) = checked_out_cart_location(&input.order)?;
+    let bytes = SerializedBytes::try_from(input.order.clone())
+        .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?;
+    let entry = Entry::CounterSign(
+        Box::new(session_data),
+        AppEntryBytes::try_from(bytes)
+            .map_err(|e| wasm_error!(WasmErrorInner::Guest(format!("{:?}", e))))?,
+    );
+
+    let order_hash = create(CreateInput::new(
+        EntryDefLocation::app(zome_index, entry_def_index),
+        EntryVisibility::Public,
+        entry,
+        // Countersigned commits must go on the locked chain top.
+        ChainTopOrdering::Strict,
+    ))?;
+
+    // The customer links the order off their key like a normal checkout
+    // so it shows up in their order history. Both signers run this
+    // extern, so only the customer — the first preflight signer, who
+    // built the order — creates the link; the store agent must not end
+    // up with someone else's order under their own key.
+    let agent = agent_info()?.agent_initial_pubkey;
+    if customer == Some(agent.clone()) {
+        create_link(
+            agent,
+            order_hash.clone(),
+            LinkTypes::CheckedOutCart,
+            crate::checkout::customer_order_tag(input.order.status, &input.order),
+        )?;
+    }
+    Ok(order_hash)
+}
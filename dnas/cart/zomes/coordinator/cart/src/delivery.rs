@@ -0,0 +1,300 @@
+use cart_integrity::*;
+use hdk::prelude::*;
+use summon_types::{ChunkTag, LinkTagCodec};
+
+use crate::checkout::transition_order_status;
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct CompleteDeliveryInput {
+    #[serde(alias = "orderHash")]
+    pub order_hash: ActionHash,
+    #[serde(alias = "mimeType")]
+    pub mime_type: String,
+    /// Photo bytes, split by the client the same way product images are.
+    #[serde(default)]
+    pub chunks: Vec<SerializedBytes>,
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+/// Mark an order Completed and attach proof of delivery: a photo stored
+/// as a `DeliveryProof` entry plus one `DeliveryProofChunk` per chunk,
+/// with the chunk index in the link tag so chunks can be reassembled in
+/// order.
+#[hdk_extern]
+pub fn complete_delivery(input: CompleteDeliveryInput) -> ExternResult<ActionHash> {
+    let (_, cart) = crate::checkout::latest_order_revision(input.order_hash.clone())?;
+    if cart
+        .product_snapshots
+        .iter()
+        .any(|snapshot| snapshot.age_restricted)
+        && cart.id_check.is_none()
+    {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Order has age-restricted items; record the ID check first".to_string()
+        )));
+    }
+    transition_order_status(input.order_hash.clone(), OrderStatus::Completed)?;
+
+    let proof = DeliveryProof {
+        order_hash: input.order_hash.clone(),
+        mime_type: input.mime_type,
+        chunk_count: input.chunks.len() as u32,
+        note: input.note,
+        delivered_at: sys_time()?.as_millis() as u64,
+    };
+    let proof_hash = create_entry(&EntryTypes::DeliveryProof(proof))?;
+    create_link(
+        input.order_hash,
+        proof_hash.clone(),
+        LinkTypes::DeliveryProof,
+        (),
+    )?;
+
+    for (index, bytes) in input.chunks.into_iter().enumerate() {
+        let chunk_hash = create_entry(&EntryTypes::DeliveryProofChunk(DeliveryProofChunk {
+            bytes,
+        }))?;
+        create_link(
+            proof_hash.clone(),
+            chunk_hash,
+            LinkTypes::DeliveryProofChunk,
+            ChunkTag(index as u32).encode(),
+        )?;
+    }
+
+    Ok(proof_hash)
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct DeliveryProofData {
+    pub proof: DeliveryProof,
+    pub chunks: Vec<SerializedBytes>,
+}
+
+/// The proof attached when an order was completed, reassembled for the
+/// customer. `None` while the order is still underway or was completed
+/// before proofs existed.
+#[hdk_extern]
+pub fn get_delivery_proof(order_hash: ActionHash) -> ExternResult<Option<DeliveryProofData>> {
+    let links = get_links(
+        GetLinksInputBuilder::try_new(order_hash, LinkTypes::DeliveryProof)?.build(),
+    )?;
+    let Some(proof_hash) = links
+        .into_iter()
+        .filter_map(|link| link.target.into_action_hash())
+        .next()
+    else {
+        return Ok(None);
+    };
+    let Some(record) = get(proof_hash.clone(), GetOptions::default())? else {
+        return Ok(None);
+    };
+    let Some(proof) = record
+        .entry()
+        .to_app_option::<DeliveryProof>()
+        .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+    else {
+        return Ok(None);
+    };
+
+    let chunk_links = get_links(
+        GetLinksInputBuilder::try_new(proof_hash, LinkTypes::DeliveryProofChunk)?.build(),
+    )?;
+    let mut indexed: Vec<(u32, SerializedBytes)> = Vec::new();
+    for link in chunk_links {
+        let index = ChunkTag::decode(&link.tag).map_or(0, |ChunkTag(index)| index);
+        let Some(hash) = link.target.into_action_hash() else {
+            continue;
+        };
+        let Some(chunk_record) = get(hash, GetOptions::default())? else {
+            continue;
+        };
+        if let Some(chunk) = chunk_record
+            .entry()
+            .to_app_option::<DeliveryProofChunk>()
+            .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        {
+            indexed.push((index, chunk.bytes));
+        }
+    }
+    indexed.sort_by_key(|(index, _)| *index);
+
+    Ok(Some(DeliveryProofData {
+        proof,
+        chunks: indexed.into_iter().map(|(_, bytes)| bytes).collect(),
+    }))
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct AttachShelfPhotoInput {
+    #[serde(alias = "orderHash")]
+    pub order_hash: ActionHash,
+    #[serde(alias = "groupHash")]
+    pub group_hash: ActionHash,
+    #[serde(alias = "productIndex")]
+    pub product_index: u32,
+    #[serde(alias = "mimeType")]
+    pub mime_type: String,
+    pub chunks: Vec<SerializedBytes>,
+}
+
+/// Attach a photo of the empty shelf to an out-of-stock order line, as
+/// evidence the item genuinely wasn't available. Only the assigned
+/// shopper may attach, and only to a line already marked out of stock.
+#[hdk_extern]
+pub fn attach_shelf_photo(input: AttachShelfPhotoInput) -> ExternResult<ActionHash> {
+    let agent = agent_info()?.agent_initial_pubkey;
+    if crate::shopper::order_claimer(&input.order_hash)?.as_ref() != Some(&agent) {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Only the assigned shopper may attach shelf photos".to_string()
+        )));
+    }
+    let (_, cart) = crate::checkout::latest_order_revision(input.order_hash.clone())?;
+    let position = cart
+        .products
+        .iter()
+        .position(|item| {
+            item.group_hash == input.group_hash && item.product_index == input.product_index
+        })
+        .ok_or(wasm_error!(WasmErrorInner::Guest(
+            "Order has no such line".to_string()
+        )))?;
+    if cart.item_fulfillments.get(position).cloned().flatten()
+        != Some(ItemFulfillment::OutOfStock)
+    {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Shelf photos attach to lines marked out of stock".to_string()
+        )));
+    }
+    if input.chunks.is_empty() {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Shelf photo needs at least one chunk".to_string()
+        )));
+    }
+
+    let photo = ShelfPhoto {
+        order_hash: input.order_hash.clone(),
+        group_hash: input.group_hash,
+        product_index: input.product_index,
+        mime_type: input.mime_type,
+        chunk_count: input.chunks.len() as u32,
+        taken_at: sys_time()?.as_millis() as u64,
+    };
+    let photo_hash = create_entry(&EntryTypes::ShelfPhoto(photo))?;
+    create_link(
+        input.order_hash,
+        photo_hash.clone(),
+        LinkTypes::ShelfPhoto,
+        (),
+    )?;
+    for (index, bytes) in input.chunks.into_iter().enumerate() {
+        let chunk_hash = create_entry(&EntryTypes::DeliveryProofChunk(DeliveryProofChunk {
+            bytes,
+        }))?;
+        create_link(
+            photo_hash.clone(),
+            chunk_hash,
+            LinkTypes::ShelfPhotoChunk,
+            ChunkTag(index as u32).encode(),
+        )?;
+    }
+    Ok(photo_hash)
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct ShelfPhotoData {
+    pub photo: ShelfPhoto,
+    pub chunks: Vec<SerializedBytes>,
+}
+
+/// Every shelf photo attached to an order, chunks reassembled in
+/// order, for the customer's and admins' verification.
+#[hdk_extern]
+pub fn get_shelf_photos(order_hash: ActionHash) -> ExternResult<Vec<ShelfPhotoData>> {
+    let links = get_links(
+        GetLinksInputBuilder::try_new(order_hash, LinkTypes::ShelfPhoto)?.build(),
+    )?;
+    let mut photos = Vec::new();
+    for link in links {
+        let Some(photo_hash) = link.target.into_action_hash() else {
+            continue;
+        };
+        let Some(record) = get(photo_hash.clone(), GetOptions::default())? else {
+            continue;
+        };
+        let Some(photo) = record
+            .entry()
+            .to_app_option::<ShelfPhoto>()
+            .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        else {
+            continue;
+        };
+
+        let chunk_links = get_links(
+            GetLinksInputBuilder::try_new(photo_hash, LinkTypes::ShelfPhotoChunk)?.build(),
+        )?;
+        let mut indexed: Vec<(u32, SerializedBytes)> = Vec::new();
+        for chunk_link in chunk_links {
+            let index = ChunkTag::decode(&chunk_link.tag).map_or(0, |ChunkTag(index)| index);
+            let Some(hash) = chunk_link.target.into_action_hash() else {
+                continue;
+            };
+            let Some(chunk_record) = get(hash, GetOptions::default())? else {
+                continue;
+            };
+            if let Some(chunk) = chunk_record
+                .entry()
+                .to_app_option::<DeliveryProofChunk>()
+                .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+            {
+                indexed.push((index, chunk.bytes));
+            }
+        }
+        indexed.sort_by_key(|(index, _)| *index);
+        photos.push(ShelfPhotoData {
+            photo,
+            chunks: indexed.into_iter().map(|(_, bytes)| bytes).collect(),
+        });
+    }
+    photos.sort_by_key(|entry| entry.photo.taken_at);
+    Ok(photos)
+}
+
+/// Record that a photo ID was checked at handoff. Required before an
+/// order carrying age-restricted items can complete; validation
+/// enforces the same rule.
+#[hdk_extern]
+pub fn confirm_id_check(order_hash: ActionHash) -> ExternResult<ActionHash> {
+    let agent = agent_info()?.agent_initial_pubkey;
+    if crate::shopper::order_claimer(&order_hash)?.as_ref() != Some(&agent) {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Only the assigned shopper may record an ID check".to_string()
+        )));
+    }
+    let (newest_hash, mut cart) = crate::checkout::latest_order_revision(order_hash)?;
+    if cart.status != OrderStatus::Delivering {
+        return Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "ID checks happen at handoff (status {:?})",
+            cart.status
+        ))));
+    }
+    if !cart
+        .product_snapshots
+        .iter()
+        .any(|snapshot| snapshot.age_restricted)
+    {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Order has no age-restricted items".to_string()
+        )));
+    }
+    cart.id_check = Some(IdCheck {
+        checked_at: sys_time()?.as_millis() as u64,
+        checked_by: agent,
+    });
+    update_entry(newest_hash, &EntryTypes::CheckedOutCart(cart))
+}
@@ -0,0 +1,158 @@
+//! Dev-gated demo data seeding for the whole app: drives the catalog
+//! seeder over the bridge, saves a few sample addresses, and publishes
+//! sample orders against the seeded groups. One call gives a new
+//! contributor (or a scenario test) a browsable, orderable network.
+//! Both this DNA and the catalog DNA must set `enable_dev_seeding`.
+
+use cart_integrity::*;
+use hdk::prelude::*;
+use summon_types::DemoRng;
+
+use crate::checkout::{checkout_cart_impl, CheckoutCartInput};
+
+const DEFAULT_ADDRESSES: usize = 3;
+const DEFAULT_ORDERS: usize = 5;
+
+/// Mirrors the catalog zome's spec; forwarded over the bridge.
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct CatalogSeedSpec {
+    #[serde(default)]
+    pub categories: Option<usize>,
+    #[serde(default)]
+    pub groups_per_subcategory: Option<usize>,
+    #[serde(default)]
+    pub products_per_group: Option<usize>,
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct CatalogSeedReport {
+    pub categories: usize,
+    pub product_groups: usize,
+    pub products: usize,
+    pub group_hashes: Vec<ActionHash>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct DemoSeedSpec {
+    #[serde(flatten)]
+    pub catalog: CatalogSeedSpec,
+    #[serde(default)]
+    pub addresses: Option<usize>,
+    #[serde(default)]
+    pub orders: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct DemoSeedReport {
+    pub catalog: CatalogSeedReport,
+    pub addresses: usize,
+    pub orders: Vec<ActionHash>,
+}
+
+/// Mirror of the address zome's `Address` entry, for seeding over the
+/// bridge.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+struct DemoAddress {
+    street: String,
+    unit: Option<String>,
+    city: String,
+    state: String,
+    zip: String,
+    country: Option<String>,
+    lat: f64,
+    lng: f64,
+    is_default: bool,
+    label: Option<String>,
+}
+
+const DEMO_STREETS: &[&str] = &[
+    "814 Alder Street",
+    "2203 Juniper Avenue",
+    "57 Rosewood Lane",
+    "1190 Harbor Drive",
+    "360 Meridian Court",
+];
+
+fn demo_address(rng: &mut DemoRng, position: usize, is_default: bool) -> DemoAddress {
+    DemoAddress {
+        street: DEMO_STREETS[position % DEMO_STREETS.len()].to_string(),
+        unit: (rng.below(3) == 0).then(|| format!("Apt {}", 1 + rng.below(40))),
+        city: "Portland".to_string(),
+        state: "OR".to_string(),
+        zip: format!("972{:02}", rng.below(30)),
+        country: Some("US".to_string()),
+        lat: 45.5 + rng.below(100) as f64 / 1000.0,
+        lng: -122.7 + rng.below(100) as f64 / 1000.0,
+        is_default,
+        label: None,
+    }
+}
+
+/// Seed demo data across the app: catalog, addresses, sample orders.
+/// The catalog seeder enforces its own `enable_dev_seeding` flag on the
+/// products DNA; this extern enforces the cart DNA's.
+#[hdk_extern]
+pub fn seed_demo_data(spec: DemoSeedSpec) -> ExternResult<DemoSeedReport> {
+    crate::features::require_feature("dev_seeding", |flags| flags.enable_dev_seeding)?;
+
+    let products_per_group = spec.catalog.products_per_group.unwrap_or(8).max(1);
+    let mut rng = DemoRng::new(spec.catalog.seed.unwrap_or(0).wrapping_add(1));
+
+    let catalog: CatalogSeedReport =
+        crate::bridge::call_catalog("seed_demo_data", spec.catalog)?;
+
+    let address_count = spec.addresses.unwrap_or(DEFAULT_ADDRESSES);
+    let mut address_hashes = Vec::new();
+    for position in 0..address_count {
+        let address = demo_address(&mut rng, position, position == 0);
+        let hash: ActionHash = crate::bridge::call_address("create_address", address)?;
+        address_hashes.push(hash);
+    }
+
+    let order_count = spec.orders.unwrap_or(DEFAULT_ORDERS);
+    let now = sys_time()?.as_millis() as u64;
+    let mut orders = Vec::new();
+    for _ in 0..order_count {
+        if catalog.group_hashes.is_empty() {
+            break;
+        }
+        let line_count = 1 + rng.below(4);
+        let cart_products: Vec<CartProduct> = (0..line_count)
+            .map(|_| CartProduct {
+                group_hash: catalog.group_hashes[rng.below(catalog.group_hashes.len())]
+                    .clone(),
+                product_index: rng.below(products_per_group) as u32,
+                quantity: (1 + rng.below(3)) as f64,
+                timestamp: now,
+                note: None,
+                substitution_preference: None,
+                sold_by: Some(SoldBy::Each),
+            })
+            .collect();
+        let order_hash = checkout_cart_impl(CheckoutCartInput {
+            address_hash: address_hashes.first().cloned(),
+            delivery_instructions: None,
+            delivery_time: None,
+            cart_products,
+            remember_notes: Vec::new(),
+            gift_card_hash: None,
+            fulfillment_method: None,
+            delivery_handoff: None,
+            pseudonymous_address: None,
+        })?;
+        orders.push(order_hash);
+    }
+
+    Ok(DemoSeedReport {
+        catalog,
+        addresses: address_hashes.len(),
+        orders,
+    })
+}
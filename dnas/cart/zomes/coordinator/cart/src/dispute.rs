@@ -0,0 +1,177 @@
+use cart_integrity::*;
+use hdk::prelude::*;
+
+use crate::shopper::order_claimer;
+use crate::tracking::order_customer;
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct OpenDisputeInput {
+    #[serde(alias = "orderHash")]
+    pub order_hash: ActionHash,
+    pub category: DisputeCategory,
+    pub description: String,
+    #[serde(default)]
+    pub evidence: Vec<ActionHash>,
+}
+
+/// Open a dispute on an order. Either party may raise one: the
+/// customer who placed it or the shopper who fulfilled it.
+#[hdk_extern]
+pub fn open_dispute(input: OpenDisputeInput) -> ExternResult<ActionHash> {
+    let agent = agent_info()?.agent_initial_pubkey;
+    let customer = order_customer(&input.order_hash)?;
+    let shopper = order_claimer(&input.order_hash)?;
+    if agent != customer && shopper.as_ref() != Some(&agent) {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Only the customer or the assigned shopper may dispute an order".to_string()
+        )));
+    }
+
+    let dispute = Dispute {
+        order_hash: input.order_hash.clone(),
+        category: input.category,
+        description: input.description,
+        evidence: input.evidence,
+        status: DisputeStatus::Open,
+        opened_at: sys_time()?.as_millis() as u64,
+        resolution: None,
+    };
+    let dispute_hash = create_entry(&EntryTypes::Dispute(dispute))?;
+    create_link(
+        input.order_hash,
+        dispute_hash.clone(),
+        LinkTypes::Dispute,
+        (),
+    )?;
+    create_link(agent, dispute_hash.clone(), LinkTypes::Dispute, ())?;
+    Ok(dispute_hash)
+}
+
+/// Resolve a dispute create hash to its newest revision.
+fn latest_dispute_revision(
+    dispute_hash: &ActionHash,
+) -> ExternResult<Option<(ActionHash, Dispute)>> {
+    let Some(details) = get_details(dispute_hash.clone(), GetOptions::default())? else {
+        return Ok(None);
+    };
+    let Details::Record(details) = details else {
+        return Ok(None);
+    };
+    let mut newest = details.record;
+    let mut updates = details.updates;
+    // Follow the chain to its tip: Open -> UnderReview -> Resolved is
+    // already three revisions, and each update chains from the last.
+    while let Some(update) = updates
+        .iter()
+        .max_by_key(|update| update.action().timestamp())
+        .cloned()
+    {
+        let Some(update_details) =
+            get_details(update.action_address().clone(), GetOptions::default())?
+        else {
+            break;
+        };
+        let Details::Record(update_details) = update_details else {
+            break;
+        };
+        newest = update_details.record;
+        updates = update_details.updates;
+    }
+    Ok(newest
+        .entry()
+        .to_app_option::<Dispute>()
+        .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        .map(|dispute| (newest.action_address().clone(), dispute)))
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct SetDisputeStatusInput {
+    #[serde(alias = "disputeHash")]
+    pub dispute_hash: ActionHash,
+    pub status: DisputeStatus,
+    #[serde(default)]
+    pub resolution: Option<String>,
+}
+
+/// Move a dispute along its state machine. Admin-gated, and enforced
+/// again in validation.
+#[hdk_extern]
+pub fn set_dispute_status(input: SetDisputeStatusInput) -> ExternResult<ActionHash> {
+    let admins = crate::checkout::dna_properties()?.admins;
+    if !admins.is_empty() && !admins.contains(&agent_info()?.agent_initial_pubkey) {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Only admin agents may move a dispute along".to_string()
+        )));
+    }
+    let (newest_hash, dispute) = latest_dispute_revision(&input.dispute_hash)?.ok_or(
+        wasm_error!(WasmErrorInner::Guest("Dispute not found".to_string())),
+    )?;
+    if !dispute.status.can_transition_to(input.status) {
+        return Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "Cannot move dispute from {:?} to {:?}",
+            dispute.status, input.status
+        ))));
+    }
+    if input.status == DisputeStatus::Resolved
+        && input.resolution.as_deref().unwrap_or("").trim().is_empty()
+    {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Resolving a dispute requires a resolution note".to_string()
+        )));
+    }
+    update_entry(
+        newest_hash,
+        &EntryTypes::Dispute(Dispute {
+            status: input.status,
+            resolution: input.resolution.or(dispute.resolution.clone()),
+            ..dispute
+        }),
+    )
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct DisputeWithHash {
+    pub dispute_hash: ActionHash,
+    pub raised_by: AgentPubKey,
+    pub dispute: Dispute,
+}
+
+/// Resolve the disputes linked from a base (an order or an agent key)
+/// to their newest revisions.
+fn disputes_at(base: impl Into<AnyLinkableHash>) -> ExternResult<Vec<DisputeWithHash>> {
+    let links = get_links(GetLinksInputBuilder::try_new(base.into(), LinkTypes::Dispute)?.build())?;
+    let mut disputes = Vec::new();
+    for link in links {
+        let Some(dispute_hash) = link.target.into_action_hash() else {
+            continue;
+        };
+        let Some(record) = get(dispute_hash.clone(), GetOptions::default())? else {
+            continue;
+        };
+        let Some((_, dispute)) = latest_dispute_revision(&dispute_hash)? else {
+            continue;
+        };
+        disputes.push(DisputeWithHash {
+            dispute_hash,
+            raised_by: record.action().author().clone(),
+            dispute,
+        });
+    }
+    disputes.sort_by_key(|entry| entry.dispute.opened_at);
+    Ok(disputes)
+}
+
+/// All disputes raised on an order, oldest first.
+#[hdk_extern]
+pub fn get_order_disputes(order_hash: ActionHash) -> ExternResult<Vec<DisputeWithHash>> {
+    disputes_at(order_hash)
+}
+
+/// Every dispute the caller has raised.
+#[hdk_extern]
+pub fn get_my_disputes(_: ()) -> ExternResult<Vec<DisputeWithHash>> {
+    disputes_at(agent_info()?.agent_initial_pubkey)
+}
@@ -0,0 +1,227 @@
+use cart_integrity::*;
+use hdk::prelude::*;
+use summon_types::PageRequest;
+
+use crate::history::own_order_history;
+use crate::receipt::{get_receipt, ReceiptWithHash};
+
+/// Orders returned per export page unless the caller asks otherwise.
+const DEFAULT_EXPORT_PAGE_SIZE: usize = 20;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    Json,
+    Csv,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct ExportOrderHistoryInput {
+    pub format: ExportFormat,
+    /// The cursor is an offset into the caller's history; comes from
+    /// `cursor` of the previous page.
+    #[serde(flatten)]
+    pub page: PageRequest,
+}
+
+/// Subset of the profiles-DNA `Address` entry the export needs.
+/// Coordinates are deliberately left out.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct ExportedAddress {
+    pub street: String,
+    pub unit: Option<String>,
+    pub city: String,
+    pub state: String,
+    pub zip: String,
+    pub label: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct ExportedLine {
+    pub name: String,
+    pub product_id: String,
+    pub category: String,
+    pub quantity: f64,
+    pub unit_price: f64,
+    pub line_total: f64,
+    pub fulfillment: Option<ItemFulfillment>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct ExportedOrder {
+    pub cart_hash: ActionHash,
+    pub id: String,
+    pub created_at: u64,
+    pub status: OrderStatus,
+    pub lines: Vec<ExportedLine>,
+    pub subtotal: f64,
+    pub discount: f64,
+    pub tax: f64,
+    pub delivery_fee: f64,
+    pub gift_card_payment: f64,
+    pub total: f64,
+    pub delivery_time: Option<DeliveryTimeSlot>,
+    pub delivery_instructions: Option<String>,
+    pub address: Option<ExportedAddress>,
+    pub receipt: Option<Receipt>,
+}
+
+/// One page of the export, shaped for the requested download format:
+/// full order objects for JSON, one row per line item for CSV.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum ExportPage {
+    Json { orders: Vec<ExportedOrder> },
+    Csv {
+        header: Vec<String>,
+        rows: Vec<Vec<String>>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct ExportOrderHistoryPage {
+    pub page: ExportPage,
+    /// Pass back as `cursor` to fetch the next page.
+    pub next_cursor: Option<u64>,
+}
+
+/// The caller's saved addresses, fetched once per export page over the
+/// bridge to the profiles DNA and matched to orders by hash.
+pub(crate) fn address_book() -> ExternResult<Vec<(ActionHash, ExportedAddress)>> {
+    crate::bridge::call_address("get_addresses", ())
+}
+
+fn export_order(
+    cart_hash: ActionHash,
+    cart: CheckedOutCart,
+    addresses: &[(ActionHash, ExportedAddress)],
+) -> ExternResult<ExportedOrder> {
+    let receipt = get_receipt(cart_hash.clone())?.map(|ReceiptWithHash { receipt, .. }| receipt);
+    let address = cart.address_hash.as_ref().and_then(|address_hash| {
+        addresses
+            .iter()
+            .find(|(hash, _)| hash == address_hash)
+            .map(|(_, address)| address.clone())
+    });
+
+    let mut lines = Vec::with_capacity(cart.products.len());
+    for (position, item) in cart.products.iter().enumerate() {
+        let snapshot = cart.product_snapshots.get(position);
+        lines.push(ExportedLine {
+            name: snapshot.map(|s| s.name.clone()).unwrap_or_default(),
+            product_id: snapshot.map(|s| s.product_id.clone()).unwrap_or_default(),
+            category: snapshot.map(|s| s.category.clone()).unwrap_or_default(),
+            quantity: item.quantity,
+            unit_price: snapshot
+                .map(|s| s.promo_price.unwrap_or(s.price))
+                .unwrap_or_default(),
+            line_total: cart.line_totals.get(position).copied().unwrap_or_default(),
+            fulfillment: cart.item_fulfillments.get(position).cloned().flatten(),
+        });
+    }
+
+    Ok(ExportedOrder {
+        cart_hash,
+        id: cart.id,
+        created_at: cart.created_at,
+        status: cart.status,
+        lines,
+        subtotal: cart.subtotal,
+        discount: cart.discount,
+        tax: cart.tax,
+        delivery_fee: cart.delivery_fee,
+        gift_card_payment: cart.gift_card_payment,
+        total: cart.total,
+        delivery_time: cart.delivery_time,
+        delivery_instructions: cart.delivery_instructions,
+        address,
+        receipt,
+    })
+}
+
+fn csv_rows(orders: &[ExportedOrder]) -> (Vec<String>, Vec<Vec<String>>) {
+    let header = [
+        "order_id",
+        "created_at",
+        "status",
+        "product",
+        "product_id",
+        "category",
+        "quantity",
+        "unit_price",
+        "line_total",
+        "order_total",
+        "address",
+    ]
+    .iter()
+    .map(|column| column.to_string())
+    .collect();
+
+    let mut rows = Vec::new();
+    for order in orders {
+        let address = order
+            .address
+            .as_ref()
+            .map(|address| {
+                format!(
+                    "{}, {}, {} {}",
+                    address.street, address.city, address.state, address.zip
+                )
+            })
+            .unwrap_or_default();
+        for line in &order.lines {
+            rows.push(vec![
+                order.id.clone(),
+                order.created_at.to_string(),
+                format!("{:?}", order.status).to_lowercase(),
+                line.name.clone(),
+                line.product_id.clone(),
+                line.category.clone(),
+                line.quantity.to_string(),
+                format!("{:.2}", line.unit_price),
+                format!("{:.2}", line.line_total),
+                format!("{:.2}", order.total),
+                address.clone(),
+            ]);
+        }
+    }
+    (header, rows)
+}
+
+/// Export the caller's complete order history — snapshots, receipts and
+/// the addresses used — in pages, so budgeting tools can download it as
+/// JSON or CSV.
+#[hdk_extern]
+pub fn export_order_history(
+    input: ExportOrderHistoryInput,
+) -> ExternResult<ExportOrderHistoryPage> {
+    let limit = input.page.limit_or(DEFAULT_EXPORT_PAGE_SIZE).max(1);
+    let cursor = input.page.offset();
+
+    let history = own_order_history()?;
+    let next_cursor = if cursor + limit < history.len() {
+        Some((cursor + limit) as u64)
+    } else {
+        None
+    };
+
+    let addresses = address_book()?;
+    let mut orders = Vec::new();
+    for (cart_hash, cart) in history.into_iter().skip(cursor).take(limit) {
+        orders.push(export_order(cart_hash, cart, &addresses)?);
+    }
+
+    let page = match input.format {
+        ExportFormat::Json => ExportPage::Json { orders },
+        ExportFormat::Csv => {
+            let (header, rows) = csv_rows(&orders);
+            ExportPage::Csv { header, rows }
+        }
+    };
+    Ok(ExportOrderHistoryPage { page, next_cursor })
+}
@@ -0,0 +1,98 @@
+use cart_integrity::*;
+use hdk::prelude::*;
+use std::collections::HashMap;
+
+/// An aggregated "buy it again" line derived from the caller's order
+/// history.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct FrequentlyPurchasedItem {
+    pub group_hash: ActionHash,
+    pub product_index: u32,
+    /// Number of orders this product appeared in.
+    pub times_ordered: u32,
+    pub total_quantity: f64,
+    /// `created_at` of the most recent order containing the product.
+    pub last_ordered: u64,
+    /// The most recent cart line, so the frontend can re-add it as-is.
+    pub last_item: CartProduct,
+}
+
+/// Scan the caller's own CheckedOutCart history from the source chain
+/// and return the most-ordered products, most frequent first.
+#[hdk_extern]
+pub fn get_frequently_purchased(limit: usize) -> ExternResult<Vec<FrequentlyPurchasedItem>> {
+    let filter = ChainQueryFilter::new()
+        .entry_type(UnitEntryTypes::CheckedOutCart.try_into()?)
+        .action_type(ActionType::Create)
+        .include_entries(true);
+    let records = query(filter)?;
+
+    let mut aggregated: HashMap<(ActionHash, u32), FrequentlyPurchasedItem> = HashMap::new();
+    for record in records {
+        // Count each order once, at its final contents: the chain also
+        // holds every update revision (amendments, delivery edits,
+        // redactions), which would recount all of the order's lines.
+        let (_, cart) = crate::checkout::latest_order_revision(record.action_address().clone())?;
+        // Returned orders went back into the cart; don't count them.
+        if cart.status == OrderStatus::Returned {
+            continue;
+        }
+        for item in cart.products {
+            let key = (item.group_hash.clone(), item.product_index);
+            let entry = aggregated
+                .entry(key)
+                .or_insert_with(|| FrequentlyPurchasedItem {
+                    group_hash: item.group_hash.clone(),
+                    product_index: item.product_index,
+                    times_ordered: 0,
+                    total_quantity: 0.0,
+                    last_ordered: 0,
+                    last_item: item.clone(),
+                });
+            entry.times_ordered += 1;
+            entry.total_quantity += item.quantity;
+            if cart.created_at >= entry.last_ordered {
+                entry.last_ordered = cart.created_at;
+                entry.last_item = item;
+            }
+        }
+    }
+
+    let mut items: Vec<FrequentlyPurchasedItem> = aggregated.into_values().collect();
+    items.sort_by(|a, b| {
+        b.times_ordered
+            .cmp(&a.times_ordered)
+            .then(b.last_ordered.cmp(&a.last_ordered))
+    });
+    items.truncate(limit);
+    Ok(items)
+}
+
+/// Merge a batch of items into the private cart in a single revision.
+/// Quantities of lines already in the cart are added to, new lines are
+/// appended.
+#[hdk_extern]
+pub fn add_all_to_cart(items: Vec<CartProduct>) -> ExternResult<ActionHash> {
+    let now = sys_time()?.as_millis() as u64;
+    let mut cart = crate::cart::get_private_cart_impl()?;
+
+    for item in items {
+        match cart.items.iter_mut().find(|existing| {
+            existing.group_hash == item.group_hash
+                && existing.product_index == item.product_index
+        }) {
+            Some(existing) => {
+                existing.quantity += item.quantity;
+                existing.timestamp = now;
+            }
+            None => cart.items.push(CartProduct {
+                timestamp: now,
+                ..item
+            }),
+        }
+    }
+
+    cart.last_updated = now;
+    crate::cart::write_private_cart(cart)
+}
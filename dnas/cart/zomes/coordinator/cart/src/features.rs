@@ -0,0 +1,36 @@
+//! Feature flags read from the DNA's properties, so one codebase can
+//! power differently-scoped deployments: a pickup-only store turns off
+//! shopper dispatch, a no-marketing one turns off promos, and so on.
+//! Flags default to on when the properties don't mention them.
+
+use hdk::prelude::*;
+use summon_types::SummonError;
+
+pub use summon_types::FeatureFlags;
+
+/// The flags this cell was installed with. Undecodable or absent
+/// properties mean the defaults: everything enabled.
+pub(crate) fn feature_flags() -> ExternResult<FeatureFlags> {
+    let properties = dna_info()?.modifiers.properties;
+    Ok(FeatureFlags::try_from(properties).unwrap_or_default())
+}
+
+/// Gate an extern on a flag; fails with a `feature_disabled` error
+/// naming the feature when the deployment has it switched off.
+pub(crate) fn require_feature(
+    name: &str,
+    enabled: fn(&FeatureFlags) -> bool,
+) -> ExternResult<()> {
+    if enabled(&feature_flags()?) {
+        Ok(())
+    } else {
+        Err(SummonError::feature_disabled(name).into())
+    }
+}
+
+/// What this deployment has switched on, so the UI can hide the
+/// affected screens instead of surfacing errors.
+#[hdk_extern]
+pub fn get_enabled_features(_: ()) -> ExternResult<FeatureFlags> {
+    feature_flags()
+}
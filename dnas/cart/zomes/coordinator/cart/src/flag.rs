@@ -0,0 +1,110 @@
+use cart_integrity::*;
+use hdk::prelude::*;
+
+/// A customer flag being pushed to the admin agents. Stored privately
+/// on each admin's chain by the receive handler; never published.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum FlagSignal {
+    Flagged { flag: CustomerFlag },
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct FlagCustomerInput {
+    #[serde(alias = "orderHash")]
+    pub order_hash: Option<ActionHash>,
+    pub customer: AgentPubKey,
+    pub reason: FlagReason,
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+/// Report a problematic order: a private entry on the reporting
+/// shopper's chain, pushed to each configured admin so dispatch can
+/// weigh it. Nothing is published to the DHT, so the flag is visible
+/// only to the reporter and the admins.
+#[hdk_extern]
+pub fn flag_customer(input: FlagCustomerInput) -> ExternResult<ActionHash> {
+    if crate::shopper::own_shopper_profile()?.is_none() {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Only registered shoppers may flag customers".to_string()
+        )));
+    }
+    if input.reason == FlagReason::Other && input.note.as_deref().unwrap_or("").trim().is_empty() {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Flags with reason \"other\" must carry a note".to_string()
+        )));
+    }
+    let agent = agent_info()?.agent_initial_pubkey;
+    if input.customer == agent {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Shoppers cannot flag themselves".to_string()
+        )));
+    }
+
+    let flag = CustomerFlag {
+        customer: input.customer,
+        order_hash: input.order_hash,
+        reason: input.reason,
+        note: input.note,
+        flagged_at: sys_time()?.as_millis() as u64,
+        reporter: None,
+    };
+    let flag_hash = create_entry(&EntryTypes::CustomerFlag(flag.clone()))?;
+
+    let admins: Vec<AgentPubKey> = crate::checkout::dna_properties()?
+        .admins
+        .into_iter()
+        .filter(|admin| *admin != agent)
+        .collect();
+    if !admins.is_empty() {
+        crate::signal::push_remote_signal(FlagSignal::Flagged { flag }, admins)?;
+    }
+    Ok(flag_hash)
+}
+
+/// Admin-side handler for [`FlagSignal`]: keep a private copy with the
+/// sender recorded as the reporter. Non-admin cells drop the signal.
+pub(crate) fn store_customer_flag(flag: &CustomerFlag, reporter: AgentPubKey) -> ExternResult<()> {
+    let agent = agent_info()?.agent_initial_pubkey;
+    if !crate::checkout::dna_properties()?.admins.contains(&agent) {
+        return Ok(());
+    }
+    create_entry(&EntryTypes::CustomerFlag(CustomerFlag {
+        reporter: Some(reporter),
+        ..flag.clone()
+    }))?;
+    Ok(())
+}
+
+/// Every flag on the caller's own chain, optionally narrowed to one
+/// customer, newest first. Chain privacy is the access control: only
+/// reporters and admins ever hold flags.
+#[hdk_extern]
+pub fn get_customer_flags(customer: Option<AgentPubKey>) -> ExternResult<Vec<CustomerFlag>> {
+    let records = query(
+        ChainQueryFilter::new()
+            .entry_type(UnitEntryTypes::CustomerFlag.try_into()?)
+            .include_entries(true),
+    )?;
+    let mut flags = Vec::new();
+    for record in records {
+        let Some(flag) = record
+            .entry()
+            .to_app_option::<CustomerFlag>()
+            .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        else {
+            continue;
+        };
+        if customer
+            .as_ref()
+            .is_some_and(|customer| flag.customer != *customer)
+        {
+            continue;
+        }
+        flags.push(flag);
+    }
+    flags.sort_by_key(|entry| std::cmp::Reverse(entry.flagged_at));
+    Ok(flags)
+}
@@ -0,0 +1,211 @@
+use cart_integrity::*;
+use hdk::prelude::*;
+
+use crate::checkout::round_cents;
+
+/// Anchor all issued gift cards hang off.
+pub(crate) fn gift_cards_anchor() -> ExternResult<TypedPath> {
+    Path::from("gift_cards").typed(LinkTypes::GiftCard)
+}
+
+fn hash_code(code: &str) -> ExternResult<Vec<u8>> {
+    hash_blake2b(code.as_bytes().to_vec(), 32)
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct IssueGiftCardInput {
+    /// The redemption code handed to the customer out of band. Only its
+    /// hash goes on the DHT.
+    pub code: String,
+    #[serde(alias = "initialBalance")]
+    pub initial_balance: f64,
+}
+
+/// Issue a gift card. Authorship is checked against the configured
+/// admin list in validation.
+#[hdk_extern]
+pub fn issue_gift_card(input: IssueGiftCardInput) -> ExternResult<ActionHash> {
+    crate::features::require_feature("gift_cards", |flags| flags.enable_gift_cards)?;
+    let anchor = gift_cards_anchor()?;
+    anchor.ensure()?;
+    let card = GiftCard {
+        code_hash: hash_code(&input.code)?,
+        initial_balance: input.initial_balance,
+    };
+    let card_hash = create_entry(&EntryTypes::GiftCard(card))?;
+    create_link(
+        anchor.path_entry_hash()?,
+        card_hash.clone(),
+        LinkTypes::GiftCard,
+        (),
+    )?;
+    Ok(card_hash)
+}
+
+fn claim_link(card_hash: &ActionHash) -> ExternResult<Option<Link>> {
+    let links = get_links(
+        GetLinksInputBuilder::try_new(card_hash.clone(), LinkTypes::GiftCardClaim)?.build(),
+    )?;
+    // First claim wins; later links are ignored.
+    Ok(links
+        .into_iter()
+        .min_by_key(|link| link.timestamp))
+}
+
+/// Claim a gift card by presenting its code. First presenter wins.
+#[hdk_extern]
+pub fn redeem_gift_card(code: String) -> ExternResult<ActionHash> {
+    crate::features::require_feature("gift_cards", |flags| flags.enable_gift_cards)?;
+    let code_hash = hash_code(&code)?;
+    let anchor = gift_cards_anchor()?;
+    let links = get_links(
+        GetLinksInputBuilder::try_new(anchor.path_entry_hash()?, LinkTypes::GiftCard)?.build(),
+    )?;
+
+    for link in links {
+        let Some(card_hash) = link.target.into_action_hash() else {
+            continue;
+        };
+        let Some(record) = get(card_hash.clone(), GetOptions::default())? else {
+            continue;
+        };
+        let Some(card) = record
+            .entry()
+            .to_app_option::<GiftCard>()
+            .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        else {
+            continue;
+        };
+        if card.code_hash != code_hash {
+            continue;
+        }
+
+        if claim_link(&card_hash)?.is_some() {
+            return Err(wasm_error!(WasmErrorInner::Guest(
+                "Gift card has already been claimed".to_string()
+            )));
+        }
+        let claim = GiftCardClaim {
+            gift_card_hash: card_hash.clone(),
+            claimed_at: sys_time()?.as_millis() as u64,
+        };
+        let claim_hash = create_entry(&EntryTypes::GiftCardClaim(claim))?;
+        create_link(
+            card_hash,
+            claim_hash.clone(),
+            LinkTypes::GiftCardClaim,
+            (),
+        )?;
+        return Ok(claim_hash);
+    }
+
+    Err(wasm_error!(WasmErrorInner::Guest(
+        "No gift card matches that code".to_string()
+    )))
+}
+
+/// The tip of a card's spend chain: the newest spend hung off the
+/// claim, if any.
+fn spend_chain_tip(claim_hash: &ActionHash) -> ExternResult<Option<(ActionHash, GiftCardSpend)>> {
+    let links = get_links(
+        GetLinksInputBuilder::try_new(claim_hash.clone(), LinkTypes::GiftCardSpend)?.build(),
+    )?;
+    let mut tip: Option<(ActionHash, GiftCardSpend)> = None;
+    for link in links {
+        let Some(hash) = link.target.into_action_hash() else {
+            continue;
+        };
+        let Some(record) = get(hash.clone(), GetOptions::default())? else {
+            continue;
+        };
+        if let Some(spend) = record
+            .entry()
+            .to_app_option::<GiftCardSpend>()
+            .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        {
+            if tip
+                .as_ref()
+                .map(|(_, current)| spend.timestamp > current.timestamp)
+                .unwrap_or(true)
+            {
+                tip = Some((hash, spend));
+            }
+        }
+    }
+    Ok(tip)
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct GiftCardBalance {
+    pub gift_card_hash: ActionHash,
+    pub claim_hash: Option<ActionHash>,
+    pub balance: f64,
+}
+
+#[hdk_extern]
+pub fn get_gift_card_balance(gift_card_hash: ActionHash) -> ExternResult<GiftCardBalance> {
+    let record = get(gift_card_hash.clone(), GetOptions::default())?.ok_or(wasm_error!(
+        WasmErrorInner::Guest("GiftCard not found".to_string())
+    ))?;
+    let card: GiftCard = record
+        .entry()
+        .to_app_option()
+        .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        .ok_or(wasm_error!(WasmErrorInner::Guest(
+            "Record is not a GiftCard".to_string()
+        )))?;
+
+    let claim = claim_link(&gift_card_hash)?.and_then(|link| link.target.into_action_hash());
+    let balance = match &claim {
+        Some(claim_hash) => match spend_chain_tip(claim_hash)? {
+            Some((_, tip)) => tip.balance_after,
+            None => card.initial_balance,
+        },
+        None => card.initial_balance,
+    };
+    Ok(GiftCardBalance {
+        gift_card_hash,
+        claim_hash: claim,
+        balance,
+    })
+}
+
+/// Draw down a claimed gift card toward an order, appending to its
+/// spend chain. Returns the amount actually applied, capped at the
+/// remaining balance.
+pub(crate) fn spend_from_gift_card(
+    gift_card_hash: ActionHash,
+    amount_due: f64,
+    order_id: &str,
+    now: u64,
+) -> ExternResult<f64> {
+    let claim_hash = claim_link(&gift_card_hash)?
+        .and_then(|link| link.target.into_action_hash())
+        .ok_or(wasm_error!(WasmErrorInner::Guest(
+            "Gift card has not been claimed".to_string()
+        )))?;
+
+    let balance = get_gift_card_balance(gift_card_hash.clone())?.balance;
+    let amount = round_cents(amount_due.min(balance));
+    if amount <= 0.0 {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Gift card has no remaining balance".to_string()
+        )));
+    }
+
+    let previous_spend_hash = spend_chain_tip(&claim_hash)?.map(|(hash, _)| hash);
+    let spend = GiftCardSpend {
+        gift_card_hash,
+        claim_hash: claim_hash.clone(),
+        previous_spend_hash,
+        amount,
+        balance_after: round_cents(balance - amount),
+        order_id: order_id.to_string(),
+        timestamp: now,
+    };
+    let spend_hash = create_entry(&EntryTypes::GiftCardSpend(spend))?;
+    create_link(claim_hash, spend_hash, LinkTypes::GiftCardSpend, ())?;
+    Ok(amount)
+}
@@ -0,0 +1,275 @@
+use cart_integrity::*;
+use hdk::prelude::*;
+
+use crate::bridge::{call_catalog, ProductReference};
+
+/// Every order the caller ever placed, newest first: create hashes off
+/// the source chain, each resolved to its newest revision — the create
+/// record alone is always Processing with nothing fulfilled yet.
+/// Returned orders are skipped.
+pub(crate) fn own_order_history() -> ExternResult<Vec<(ActionHash, CheckedOutCart)>> {
+    let filter = ChainQueryFilter::new()
+        .entry_type(UnitEntryTypes::CheckedOutCart.try_into()?)
+        .action_type(ActionType::Create);
+
+    let mut orders = Vec::new();
+    for record in query(filter)? {
+        let cart_hash = record.action_address().clone();
+        let (_, cart) = crate::checkout::latest_order_revision(cart_hash.clone())?;
+        if cart.status == OrderStatus::Returned {
+            continue;
+        }
+        orders.push((cart_hash, cart));
+    }
+    orders.sort_by_key(|entry| std::cmp::Reverse(entry.1.created_at));
+    Ok(orders)
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct OrderSummary {
+    pub cart_hash: ActionHash,
+    pub status: OrderStatus,
+    pub created_at: u64,
+    pub item_count: u32,
+    pub total: f64,
+}
+
+/// Summaries for the orders list screen: one small row per active
+/// order, decoded from link tags where possible so full product arrays
+/// are never downloaded. Orders linked with pre-summary tags fall back
+/// to fetching the entry.
+#[hdk_extern]
+pub fn get_order_summaries(_: ()) -> ExternResult<Vec<OrderSummary>> {
+    let agent = agent_info()?.agent_initial_pubkey;
+    let links = get_links(
+        GetLinksInputBuilder::try_new(agent, LinkTypes::CheckedOutCart)?.build(),
+    )?;
+
+    let mut summaries = Vec::new();
+    for link in links {
+        if link.tag.as_ref().as_slice() == b"archived" {
+            continue;
+        }
+        let tag = crate::checkout::decode_order_tag(&link);
+        let Some(cart_hash) = link.target.into_action_hash() else {
+            continue;
+        };
+        match (tag.status, tag.item_count, tag.total_cents) {
+            (Some(status), Some(item_count), Some(total_cents)) => {
+                summaries.push(OrderSummary {
+                    cart_hash,
+                    status,
+                    created_at: tag.created_at,
+                    item_count,
+                    total: total_cents as f64 / 100.0,
+                });
+            }
+            _ => {
+                let Ok((_, cart)) = crate::checkout::latest_order_revision(cart_hash.clone())
+                else {
+                    continue;
+                };
+                if cart.status == OrderStatus::Returned {
+                    continue;
+                }
+                summaries.push(OrderSummary {
+                    cart_hash,
+                    status: cart.status,
+                    created_at: cart.created_at,
+                    item_count: cart.products.len() as u32,
+                    total: cart.total,
+                });
+            }
+        }
+    }
+    summaries.sort_by_key(|entry| std::cmp::Reverse(entry.created_at));
+    Ok(summaries)
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct SearchOrdersInput {
+    /// Case-insensitive match against the product names snapshotted
+    /// into each order.
+    pub query: Option<String>,
+    /// Exact match against a catalog reference.
+    #[serde(alias = "groupHash")]
+    pub group_hash: Option<ActionHash>,
+    #[serde(alias = "productIndex")]
+    pub product_index: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct OrderSearchHit {
+    pub cart_hash: ActionHash,
+    pub created_at: u64,
+    pub status: OrderStatus,
+    /// Names of the products in the order that matched.
+    pub matched_products: Vec<String>,
+}
+
+/// Find past orders containing a product, by name or by catalog
+/// reference — "when did I last buy coffee filters" without opening
+/// every order. Runs entirely against the caller's own chain.
+#[hdk_extern]
+pub fn search_orders_by_product(input: SearchOrdersInput) -> ExternResult<Vec<OrderSearchHit>> {
+    if input.query.is_none() && input.group_hash.is_none() {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Provide a name query or a product reference to search by".to_string()
+        )));
+    }
+    let needle = input.query.as_ref().map(|query| query.to_lowercase());
+
+    let mut hits = Vec::new();
+    for (cart_hash, cart) in own_order_history()? {
+        let mut matched_products = Vec::new();
+        for (position, item) in cart.products.iter().enumerate() {
+            let name = cart
+                .product_snapshots
+                .get(position)
+                .map(|snapshot| snapshot.name.clone())
+                .unwrap_or_default();
+
+            let reference_match = match (&input.group_hash, input.product_index) {
+                (Some(group_hash), Some(product_index)) => {
+                    item.group_hash == *group_hash && item.product_index == product_index
+                }
+                (Some(group_hash), None) => item.group_hash == *group_hash,
+                _ => false,
+            };
+            let name_match = needle
+                .as_ref()
+                .map(|needle| name.to_lowercase().contains(needle))
+                .unwrap_or(false);
+
+            if reference_match || name_match {
+                matched_products.push(name);
+            }
+        }
+        if !matched_products.is_empty() {
+            hits.push(OrderSearchHit {
+                cart_hash,
+                created_at: cart.created_at,
+                status: cart.status,
+                matched_products,
+            });
+        }
+    }
+    Ok(hits)
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct ReorderReport {
+    pub cart_hash: ActionHash,
+    /// Lines added with their original references.
+    pub added: u32,
+    /// Lines whose group reference was stale and was re-resolved
+    /// through the stable product id.
+    pub remapped: u32,
+    /// Names of discontinued products that could not be added.
+    pub skipped: Vec<String>,
+}
+
+/// Copy a past order's items back into the private cart in one call.
+/// References that no longer resolve (the catalog was re-imported) are
+/// remapped through the product id snapshotted on the order; products
+/// gone from the catalog entirely are skipped and reported.
+#[hdk_extern]
+pub fn reorder(cart_hash: ActionHash) -> ExternResult<ReorderReport> {
+    let (_, order) = crate::checkout::latest_order_revision(cart_hash)?;
+
+    let references: Vec<ProductReference> = order
+        .products
+        .iter()
+        .map(|item| ProductReference {
+            group_hash: item.group_hash.clone(),
+            index: item.product_index,
+        })
+        .collect();
+    // Presence check only; the snapshot already has the display data.
+    let resolved: Vec<Option<ProductSnapshot>> =
+        call_catalog("resolve_product_references", references)?;
+
+    // Re-resolve stale lines through their stable product ids.
+    let stale_ids: Vec<String> = order
+        .products
+        .iter()
+        .zip(&resolved)
+        .enumerate()
+        .filter(|(_, (_, resolved))| resolved.is_none())
+        .filter_map(|(position, _)| {
+            order
+                .product_snapshots
+                .get(position)
+                .map(|snapshot| snapshot.product_id.clone())
+        })
+        .collect();
+    let remapped_refs: Vec<Option<ProductReference>> = if stale_ids.is_empty() {
+        Vec::new()
+    } else {
+        call_catalog("find_products_by_ids", stale_ids.clone())?
+    };
+    let mut remapped_iter = remapped_refs.into_iter();
+
+    let now = sys_time()?.as_millis() as u64;
+    let mut cart = crate::cart::get_private_cart_impl()?;
+    let mut added = 0;
+    let mut remapped = 0;
+    let mut skipped = Vec::new();
+
+    for (position, (item, still_there)) in
+        order.products.iter().zip(&resolved).enumerate()
+    {
+        let name = order
+            .product_snapshots
+            .get(position)
+            .map(|snapshot| snapshot.name.clone())
+            .unwrap_or_default();
+
+        let (group_hash, product_index) = if still_there.is_some() {
+            (item.group_hash.clone(), item.product_index)
+        } else {
+            match remapped_iter.next().flatten() {
+                Some(reference) => {
+                    remapped += 1;
+                    (reference.group_hash, reference.index)
+                }
+                None => {
+                    skipped.push(name);
+                    continue;
+                }
+            }
+        };
+
+        match cart.items.iter_mut().find(|existing| {
+            existing.group_hash == group_hash && existing.product_index == product_index
+        }) {
+            Some(existing) => {
+                existing.quantity += item.quantity;
+                existing.timestamp = now;
+            }
+            None => cart.items.push(CartProduct {
+                group_hash,
+                product_index,
+                quantity: item.quantity,
+                timestamp: now,
+                note: item.note.clone(),
+                substitution_preference: item.substitution_preference.clone(),
+                sold_by: item.sold_by,
+            }),
+        }
+        added += 1;
+    }
+
+    cart.last_updated = now;
+    let cart_hash = crate::cart::write_private_cart(cart)?;
+    Ok(ReorderReport {
+        cart_hash,
+        added,
+        remapped,
+        skipped,
+    })
+}
@@ -0,0 +1,153 @@
+use cart_integrity::*;
+use hdk::prelude::*;
+
+/// How long a customer invite admits its holder: 30 days. Pilot codes
+/// are handed out in batches and shouldn't linger forever; shopper
+/// invites are one-off onboarding and don't expire.
+const CUSTOMER_INVITE_TTL_MS: u64 = 30 * 24 * 60 * 60 * 1000;
+
+pub(crate) fn invite_claims_anchor() -> ExternResult<TypedPath> {
+    Path::from("invite_claims").typed(LinkTypes::InviteClaim)
+}
+
+/// Issue a signed shopper invite. Admin-gated; the returned value,
+/// msgpack-encoded, is what the invitee supplies as their membrane
+/// proof at install time, replacing out-of-band key exchange. The
+/// signature and role are verified again in `validate_agent_joining`.
+#[hdk_extern]
+pub fn issue_shopper_invite(invitee: AgentPubKey) -> ExternResult<SignedNetworkInvite> {
+    issue_invite(invitee, SHOPPER_INVITE_ROLE, None)
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct IssueCustomerInviteInput {
+    pub invitee: AgentPubKey,
+    /// How long the code stays valid, defaulting to
+    /// [`CUSTOMER_INVITE_TTL_MS`].
+    #[serde(default)]
+    pub ttl_ms: Option<u64>,
+}
+
+/// Issue a signed customer invite for a closed pilot network. Unlike
+/// shopper invites these expire, so a code that never gets used stops
+/// being a standing way in. The same code doubles as the membrane
+/// proof for the catalog DNA — both integrity zomes verify it against
+/// their own admin list.
+#[hdk_extern]
+pub fn issue_customer_invite(
+    input: IssueCustomerInviteInput,
+) -> ExternResult<SignedNetworkInvite> {
+    let now = sys_time()?.as_millis() as u64;
+    let expires_at = now + input.ttl_ms.unwrap_or(CUSTOMER_INVITE_TTL_MS);
+    issue_invite(input.invitee, CUSTOMER_INVITE_ROLE, Some(expires_at))
+}
+
+pub(crate) fn issue_invite(
+    invitee: AgentPubKey,
+    role: &str,
+    expires_at: Option<u64>,
+) -> ExternResult<SignedNetworkInvite> {
+    let agent = agent_info()?.agent_initial_pubkey;
+    let admins = crate::checkout::dna_properties()?.admins;
+    if !admins.is_empty() && !admins.contains(&agent) {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Only admin agents may issue invites".to_string()
+        )));
+    }
+    let invite = NetworkInvite {
+        invitee,
+        role: role.to_string(),
+        issued_at: sys_time()?.as_millis() as u64,
+        expires_at,
+    };
+    let signature = sign(agent.clone(), invite.clone())?;
+    Ok(SignedNetworkInvite {
+        invite,
+        issuer: agent,
+        signature,
+    })
+}
+
+/// The caller's own membrane proof decoded as an invite code. `None`
+/// on open networks and for pre-invite agents.
+pub(crate) fn own_invite() -> ExternResult<Option<SignedNetworkInvite>> {
+    let records = query(ChainQueryFilter::new().action_type(ActionType::AgentValidationPkg))?;
+    for record in records {
+        if let Action::AgentValidationPkg(pkg) = record.action() {
+            if let Some(proof) = &pkg.membrane_proof {
+                if let Ok(signed) = SignedNetworkInvite::try_from((**proof).clone()) {
+                    return Ok(Some(signed));
+                }
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// The role on the caller's own membrane proof, if they joined with an
+/// invite code.
+pub(crate) fn own_invite_role() -> ExternResult<Option<String>> {
+    Ok(own_invite()?.map(|signed| signed.invite.role))
+}
+
+/// Record that the caller's invite code has been consumed: an
+/// `InviteClaim` entry linked from the claims anchor. Runs from `init`
+/// and is a no-op for agents without a proof or who have already
+/// claimed, so re-inits don't pile up duplicates.
+pub(crate) fn record_invite_claim() -> ExternResult<()> {
+    let Some(invite) = own_invite()? else {
+        return Ok(());
+    };
+    let already_claimed = !query(
+        ChainQueryFilter::new().entry_type(UnitEntryTypes::InviteClaim.try_into()?),
+    )?
+    .is_empty();
+    if already_claimed {
+        return Ok(());
+    }
+    let claim = InviteClaim {
+        invite,
+        claimed_at: sys_time()?.as_millis() as u64,
+    };
+    let hash = create_entry(&EntryTypes::InviteClaim(claim))?;
+    create_link(
+        invite_claims_anchor()?.path_entry_hash()?,
+        hash,
+        LinkTypes::InviteClaim,
+        (),
+    )?;
+    Ok(())
+}
+
+/// Every recorded invite claim, for issuance bookkeeping: which minted
+/// codes were actually used, by whom and when. Codes are bound to one
+/// key so reuse by another agent is impossible; this is the audit
+/// trail, not the enforcement.
+#[hdk_extern]
+pub fn get_invite_claims(_: ()) -> ExternResult<Vec<InviteClaim>> {
+    let links = get_links(
+        GetLinksInputBuilder::try_new(
+            invite_claims_anchor()?.path_entry_hash()?,
+            LinkTypes::InviteClaim,
+        )?
+        .build(),
+    )?;
+    let mut claims = Vec::new();
+    for link in links {
+        let Some(hash) = link.target.into_action_hash() else {
+            continue;
+        };
+        if let Some(record) = get(hash, GetOptions::default())? {
+            if let Some(claim) = record
+                .entry()
+                .to_app_option::<InviteClaim>()
+                .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+            {
+                claims.push(claim);
+            }
+        }
+    }
+    claims.sort_by_key(|claim| claim.claimed_at);
+    Ok(claims)
+}
@@ -0,0 +1,195 @@
+//! Cart coordinator zome.
+//!
+//! The private cart lives on the agent's own chain as a `PrivateCart`
+//! entry, re-written on every change and pointed at by a link from the
+//! agent's key. Checkout publishes a public `CheckedOutCart` so
+//! fulfillment can see the order.
+
+mod batch;
+mod block;
+mod bridge;
+mod cart;
+mod chat;
+mod checkout;
+mod countersign;
+mod delivery;
+mod dev;
+mod dispute;
+mod export;
+mod favorites;
+mod features;
+mod flag;
+mod giftcard;
+mod history;
+mod invite;
+mod metrics;
+mod pickup;
+mod preference;
+mod privacy;
+mod promo;
+mod rating;
+mod receipt;
+mod refund;
+mod scheduler;
+mod shopper;
+mod signal;
+mod stats;
+mod substitution;
+mod template;
+mod tracking;
+
+pub use batch::*;
+pub use block::*;
+pub use bridge::*;
+pub use cart::*;
+pub use chat::*;
+pub use checkout::*;
+pub use countersign::*;
+pub use delivery::*;
+pub use dev::*;
+pub use dispute::*;
+pub use export::*;
+pub use favorites::*;
+pub use features::*;
+pub use flag::*;
+pub use giftcard::*;
+pub use history::*;
+pub use invite::*;
+pub use metrics::*;
+pub use pickup::*;
+pub use preference::*;
+pub use privacy::*;
+pub use promo::*;
+pub use rating::*;
+pub use receipt::*;
+pub use refund::*;
+pub use scheduler::*;
+pub use shopper::*;
+pub use stats::*;
+pub use substitution::*;
+pub use template::*;
+pub use tracking::*;
+
+use cart_integrity::OrderStatus;
+use hdk::prelude::*;
+
+/// Local events pushed to this agent's own UI over the app websocket,
+/// so open windows and paired devices update instantly instead of
+/// refetching on an interval. Remote counterparts arrive through
+/// [`RemoteCartSignal`] and are re-emitted on receipt.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum AppSignal {
+    /// The private cart was rewritten: an add, edit, clear, restore or
+    /// checkout wipe.
+    CartUpdated,
+    /// An order was published at checkout.
+    OrderCreated { order_hash: ActionHash },
+    /// The local agent moved an order through the fulfillment state
+    /// machine.
+    OrderStatusChanged {
+        order_hash: ActionHash,
+        status: OrderStatus,
+    },
+}
+
+/// Remote signals other agents push at us: substitution round-trips,
+/// live delivery tracking, order chat and the order-access handshake. Untagged so
+/// senders keep sending their concrete signal types; variants are tried
+/// in order, so the internally-tagged ones come before the bare
+/// [`AddressRequestSignal`] struct they would otherwise be mistaken
+/// for.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(untagged)]
+pub enum RemoteCartSignal {
+    Substitution(SubstitutionSignal),
+    Tracking(TrackingSignal),
+    OrderAccess(OrderAccessSignal),
+    OrderStatus(OrderStatusSignal),
+    Chat(ChatSignal),
+    Flag(FlagSignal),
+    Transfer(TransferSignal),
+    Weight(WeightSignal),
+    Dispatch(DispatchSignal),
+    AddressRequest(AddressRequestSignal),
+}
+
+/// Remote signals land here and are re-emitted to this agent's UI.
+/// Handshake signals are also answered in place, so grants happen
+/// without either side doing anything by hand.
+#[hdk_extern]
+pub fn recv_remote_signal(signal: RemoteCartSignal) -> ExternResult<()> {
+    let sender = call_info()?.provenance;
+    match &signal {
+        RemoteCartSignal::AddressRequest(request) => {
+            checkout::grant_delivery_address(&request.order_hash, sender)?;
+        }
+        RemoteCartSignal::OrderAccess(OrderAccessSignal::Requested { order_hash }) => {
+            shopper::grant_order_access(order_hash.clone(), sender)?;
+        }
+        RemoteCartSignal::OrderAccess(OrderAccessSignal::Granted { order_hash, secret }) => {
+            shopper::store_order_access(order_hash.clone(), *secret, sender)?;
+        }
+        RemoteCartSignal::Flag(FlagSignal::Flagged { flag }) => {
+            flag::store_customer_flag(flag, sender)?;
+        }
+        RemoteCartSignal::Transfer(TransferSignal::OrderTransferred {
+            order_hash,
+            new_shopper,
+            ..
+        })
+            // The incoming shopper starts their own access handshake;
+            // the customer's copy is informational.
+            if *new_shopper == agent_info()?.agent_initial_pubkey => {
+                shopper::request_order_access(order_hash.clone())?;
+            }
+        _ => {}
+    }
+    // Order-status pushes are normalized into the same typed
+    // [`AppSignal`] the local state machine emits, so the UI handles
+    // one order-status event regardless of which side moved the order.
+    if let RemoteCartSignal::OrderStatus(OrderStatusSignal::StatusChanged {
+        order_hash,
+        status,
+        ..
+    }) = &signal
+    {
+        return emit_signal(AppSignal::OrderStatusChanged {
+            order_hash: order_hash.clone(),
+            status: *status,
+        });
+    }
+    emit_signal(signal)
+}
+
+/// Grant every agent the capability to deliver remote signals to us
+/// (without this, substitution and tracking signals are silently
+/// dropped; sender-side checks gate who may send what) and ensure the
+/// shared anchors exist, so first-use paths aren't created lazily
+/// inside hot externs.
+#[hdk_extern]
+pub fn init(_: ()) -> ExternResult<InitCallbackResult> {
+    let mut functions = BTreeSet::new();
+    functions.insert((zome_info()?.name, FunctionName::from("recv_remote_signal")));
+    create_cap_grant(CapGrantEntry {
+        tag: "remote-signals".to_string(),
+        access: CapAccess::Unrestricted,
+        functions: GrantedFunctions::Listed(functions),
+    })?;
+
+    shopper::available_orders_anchor()?.ensure()?;
+    shopper::shoppers_anchor()?.ensure()?;
+    checkout::order_log_anchor()?.ensure()?;
+    promo::promo_codes_anchor()?.ensure()?;
+    giftcard::gift_cards_anchor()?.ensure()?;
+    refund::refund_requests_anchor()?.ensure()?;
+    invite::invite_claims_anchor()?.ensure()?;
+
+    // Consumed invite codes are published once, on the joiner's first
+    // init, so admins can see which minted codes were used.
+    invite::record_invite_claim()?;
+
+    schedule("run_due_jobs")?;
+
+    Ok(InitCallbackResult::Pass)
+}
@@ -0,0 +1,71 @@
+//! Source-chain metrics for the calling agent, so the chain-bloat cost
+//! of the cart-rewrite pattern can be measured on real chains instead
+//! of estimated.
+
+use cart_integrity::*;
+use hdk::prelude::*;
+
+pub use summon_types::AgentMetrics;
+
+fn entry_type_names() -> ExternResult<Vec<(EntryType, &'static str)>> {
+    Ok(vec![
+        (UnitEntryTypes::PrivateCart.try_into()?, "PrivateCart"),
+        (UnitEntryTypes::CheckedOutCart.try_into()?, "CheckedOutCart"),
+        (UnitEntryTypes::PromoCode.try_into()?, "PromoCode"),
+        (UnitEntryTypes::GiftCard.try_into()?, "GiftCard"),
+        (UnitEntryTypes::GiftCardClaim.try_into()?, "GiftCardClaim"),
+        (UnitEntryTypes::GiftCardSpend.try_into()?, "GiftCardSpend"),
+        (
+            UnitEntryTypes::SubstitutionProposal.try_into()?,
+            "SubstitutionProposal",
+        ),
+        (
+            UnitEntryTypes::SubstitutionResponse.try_into()?,
+            "SubstitutionResponse",
+        ),
+        (UnitEntryTypes::RefundRequest.try_into()?, "RefundRequest"),
+        (UnitEntryTypes::Receipt.try_into()?, "Receipt"),
+        (
+            UnitEntryTypes::ProductPreference.try_into()?,
+            "ProductPreference",
+        ),
+        (
+            UnitEntryTypes::ShoppingListTemplate.try_into()?,
+            "ShoppingListTemplate",
+        ),
+        (UnitEntryTypes::CartDelta.try_into()?, "CartDelta"),
+        (UnitEntryTypes::SavedCart.try_into()?, "SavedCart"),
+        (UnitEntryTypes::OrderBundle.try_into()?, "OrderBundle"),
+        (UnitEntryTypes::PickupSlot.try_into()?, "PickupSlot"),
+        (UnitEntryTypes::SlotReservation.try_into()?, "SlotReservation"),
+        (UnitEntryTypes::DeliveryProof.try_into()?, "DeliveryProof"),
+        (
+            UnitEntryTypes::DeliveryProofChunk.try_into()?,
+            "DeliveryProofChunk",
+        ),
+        (UnitEntryTypes::ShopperProfile.try_into()?, "ShopperProfile"),
+        (UnitEntryTypes::OrderClaim.try_into()?, "OrderClaim"),
+        (UnitEntryTypes::ChatMessage.try_into()?, "ChatMessage"),
+        (UnitEntryTypes::ShopperRating.try_into()?, "ShopperRating"),
+        (UnitEntryTypes::CustomerFlag.try_into()?, "CustomerFlag"),
+        (UnitEntryTypes::ShoppingBatch.try_into()?, "ShoppingBatch"),
+        (UnitEntryTypes::Dispute.try_into()?, "Dispute"),
+        (UnitEntryTypes::ShelfPhoto.try_into()?, "ShelfPhoto"),
+        (
+            UnitEntryTypes::PrivateDeliveryAddress.try_into()?,
+            "PrivateDeliveryAddress",
+        ),
+        (UnitEntryTypes::BlockedAgent.try_into()?, "BlockedAgent"),
+    ])
+}
+
+/// The caller's chain length, app-entry counts by type and approximate
+/// entry bytes written in this cell.
+#[hdk_extern]
+pub fn get_agent_metrics(_: ()) -> ExternResult<AgentMetrics> {
+    let records = query(ChainQueryFilter::new().include_entries(true))?;
+    Ok(summon_types::agent_metrics_from_records(
+        &records,
+        &entry_type_names()?,
+    ))
+}
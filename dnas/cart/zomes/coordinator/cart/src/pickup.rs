@@ -0,0 +1,329 @@
+use cart_integrity::*;
+use hdk::prelude::*;
+
+/// Per-store anchor pickup slots hang off, mirroring the promo-code
+/// anchor but scoped to one store location.
+fn pickup_slots_anchor(store_id: &str) -> ExternResult<TypedPath> {
+    Path::from(format!("pickup_slots.{}", store_id)).typed(LinkTypes::PickupSlot)
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct CreatePickupSlotInput {
+    #[serde(alias = "storeId")]
+    pub store_id: String,
+    pub date: u64,
+    #[serde(alias = "timeSlot")]
+    pub time_slot: String,
+    pub capacity: u32,
+}
+
+/// Publish a bookable pickup window for a store. Restricted to admin
+/// agents in validation.
+#[hdk_extern]
+pub fn create_pickup_slot(input: CreatePickupSlotInput) -> ExternResult<ActionHash> {
+    let slot = PickupSlot {
+        store_id: input.store_id.clone(),
+        date: input.date,
+        time_slot: input.time_slot,
+        capacity: input.capacity,
+    };
+    let slot_hash = create_entry(&EntryTypes::PickupSlot(slot))?;
+
+    let anchor = pickup_slots_anchor(&input.store_id)?;
+    anchor.ensure()?;
+    create_link(
+        anchor.path_entry_hash()?,
+        slot_hash.clone(),
+        LinkTypes::PickupSlot,
+        (),
+    )?;
+    Ok(slot_hash)
+}
+
+/// Every published slot for a store, with its entry hash.
+fn store_slots(store_id: &str) -> ExternResult<Vec<(ActionHash, PickupSlot)>> {
+    let anchor = pickup_slots_anchor(store_id)?;
+    let links = get_links(
+        GetLinksInputBuilder::try_new(anchor.path_entry_hash()?, LinkTypes::PickupSlot)?
+            .build(),
+    )?;
+
+    let mut slots = Vec::new();
+    for link in links {
+        let Some(hash) = link.target.into_action_hash() else {
+            continue;
+        };
+        let Some(record) = get(hash.clone(), GetOptions::default())? else {
+            continue;
+        };
+        if let Some(slot) = record
+            .entry()
+            .to_app_option::<PickupSlot>()
+            .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        {
+            slots.push((hash, slot));
+        }
+    }
+    Ok(slots)
+}
+
+/// Units of a slot's capacity currently held: live reservations plus
+/// orders booked before reservations existed.
+pub(crate) fn slot_booking_count(slot_hash: &ActionHash) -> ExternResult<u32> {
+    let legacy = get_links(
+        GetLinksInputBuilder::try_new(slot_hash.clone(), LinkTypes::PickupSlotOrder)?.build(),
+    )?;
+    let reservations = get_links(
+        GetLinksInputBuilder::try_new(slot_hash.clone(), LinkTypes::SlotReservation)?.build(),
+    )?;
+    Ok((legacy.len() + reservations.len()) as u32)
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct GetPickupSlotsInput {
+    #[serde(alias = "storeId")]
+    pub store_id: String,
+    /// Day to list, unix ms; matches the slot's `date` exactly.
+    pub date: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct AvailablePickupSlot {
+    pub slot_hash: ActionHash,
+    pub slot: PickupSlot,
+    pub booked: u32,
+    pub available: bool,
+}
+
+/// The pickup windows a store offers on one day, with how much capacity
+/// each has left.
+#[hdk_extern]
+pub fn get_available_pickup_slots(
+    input: GetPickupSlotsInput,
+) -> ExternResult<Vec<AvailablePickupSlot>> {
+    let mut available = Vec::new();
+    for (slot_hash, slot) in store_slots(&input.store_id)? {
+        if slot.date != input.date {
+            continue;
+        }
+        let booked = slot_booking_count(&slot_hash)?;
+        available.push(AvailablePickupSlot {
+            available: slot.capacity == 0 || booked < slot.capacity,
+            slot_hash,
+            slot,
+            booked,
+        });
+    }
+    available.sort_by(|a, b| a.slot.time_slot.cmp(&b.slot.time_slot));
+    Ok(available)
+}
+
+/// Find the published slot matching a pickup order's chosen window and
+/// check it still has capacity. Returns `None` when the store has no
+/// published slots for that day, which leaves freeform pickup times
+/// working for stores that don't schedule.
+pub(crate) fn find_bookable_slot(
+    store_id: &str,
+    chosen: &DeliveryTimeSlot,
+) -> ExternResult<Option<ActionHash>> {
+    let slots = store_slots(store_id)?;
+    if !slots.iter().any(|(_, slot)| slot.date == chosen.date) {
+        return Ok(None);
+    }
+
+    let matching = slots
+        .into_iter()
+        .find(|(_, slot)| slot.date == chosen.date && slot.time_slot == chosen.time_slot);
+    let Some((slot_hash, slot)) = matching else {
+        return Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "Store {} offers no pickup window \"{}\" on that day",
+            store_id, chosen.time_slot
+        ))));
+    };
+    if slot.capacity > 0 && slot_booking_count(&slot_hash)? >= slot.capacity {
+        return Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "Pickup window \"{}\" is fully booked",
+            slot.time_slot
+        ))));
+    }
+    Ok(Some(slot_hash))
+}
+
+/// Book an order into a slot: a `SlotReservation` entry holds one unit
+/// of the slot's capacity, linked from both the slot and the order so
+/// either side can find it.
+pub(crate) fn book_slot(slot_hash: ActionHash, order_hash: ActionHash) -> ExternResult<()> {
+    let reservation = SlotReservation {
+        slot_hash: slot_hash.clone(),
+        order_hash: order_hash.clone(),
+        reserved_at: sys_time()?.as_millis() as u64,
+    };
+    let reservation_hash = create_entry(&EntryTypes::SlotReservation(reservation))?;
+    create_link(
+        slot_hash,
+        reservation_hash.clone(),
+        LinkTypes::SlotReservation,
+        (),
+    )?;
+    create_link(
+        order_hash,
+        reservation_hash,
+        LinkTypes::SlotReservation,
+        (),
+    )?;
+    Ok(())
+}
+
+/// Release any capacity an order holds, called when it is cancelled.
+/// Deleting the slot-side link is what frees the unit for the next
+/// customer; the reservation entry is tombstoned for the audit trail.
+pub(crate) fn release_slot_reservation(order_hash: &ActionHash) -> ExternResult<()> {
+    let order_links = get_links(
+        GetLinksInputBuilder::try_new(order_hash.clone(), LinkTypes::SlotReservation)?.build(),
+    )?;
+    for link in order_links {
+        let Some(reservation_hash) = link.target.clone().into_action_hash() else {
+            continue;
+        };
+        let Some(record) = get(reservation_hash.clone(), GetOptions::default())? else {
+            continue;
+        };
+        let Some(reservation) = record
+            .entry()
+            .to_app_option::<SlotReservation>()
+            .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        else {
+            continue;
+        };
+
+        let slot_links = get_links(
+            GetLinksInputBuilder::try_new(reservation.slot_hash, LinkTypes::SlotReservation)?
+                .build(),
+        )?;
+        for slot_link in slot_links {
+            if slot_link.target.clone().into_action_hash() == Some(reservation_hash.clone()) {
+                delete_link(slot_link.create_link_hash)?;
+            }
+        }
+        delete_link(link.create_link_hash)?;
+        delete_entry(reservation_hash)?;
+    }
+    Ok(())
+}
+
+/// How long a handoff token stays valid once issued.
+const HANDOFF_TOKEN_TTL_MS: u64 = 10 * 60 * 1000;
+
+/// Customer-side half of curbside handoff: a short-lived signed token
+/// their app renders as a QR code at the counter. Only the order's
+/// customer can mint one, so possession of the code proves the order is
+/// being collected by (or for) whoever paid.
+#[hdk_extern]
+pub fn generate_handoff_token(order_hash: ActionHash) -> ExternResult<SignedHandoffToken> {
+    let agent = agent_info()?.agent_initial_pubkey;
+    if crate::tracking::order_customer(&order_hash)? != agent {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Only the customer who placed an order may generate its handoff token".to_string()
+        )));
+    }
+    let (_, cart) = crate::checkout::latest_order_revision(order_hash.clone())?;
+    if !matches!(
+        cart.fulfillment_method,
+        Some(FulfillmentMethod::Pickup { .. })
+    ) {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Handoff tokens only apply to pickup orders".to_string()
+        )));
+    }
+    if matches!(
+        cart.status,
+        OrderStatus::Completed | OrderStatus::Cancelled | OrderStatus::Returned
+    ) {
+        return Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "Order is no longer collectable (status {:?})",
+            cart.status
+        ))));
+    }
+
+    let now = sys_time()?.as_millis() as u64;
+    let token = HandoffToken {
+        order_hash,
+        issued_at: now,
+        expires_at: now + HANDOFF_TOKEN_TTL_MS,
+    };
+    let signature = sign(agent.clone(), token.clone())?;
+    Ok(SignedHandoffToken {
+        token,
+        signer: agent,
+        signature,
+    })
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct VerifyHandoffTokenInput {
+    #[serde(alias = "orderHash")]
+    pub order_hash: ActionHash,
+    pub token: SignedHandoffToken,
+}
+
+/// Staff-side half: check a scanned token against the order and record
+/// the verification on it, so a paid order can't be collected twice or
+/// by the wrong person. Open to the assigned fulfiller and admins.
+#[hdk_extern]
+pub fn verify_handoff_token(input: VerifyHandoffTokenInput) -> ExternResult<HandoffVerification> {
+    let agent = agent_info()?.agent_initial_pubkey;
+    let admins = crate::checkout::dna_properties()?.admins;
+    let is_staff = crate::shopper::order_claimer(&input.order_hash)?.as_ref() == Some(&agent)
+        || admins.is_empty()
+        || admins.contains(&agent);
+    if !is_staff {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Only the assigned fulfiller or an admin may verify handoff tokens".to_string()
+        )));
+    }
+
+    if input.token.token.order_hash != input.order_hash {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Token is for a different order".to_string()
+        )));
+    }
+    if input.token.signer != crate::tracking::order_customer(&input.order_hash)? {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Token was not signed by the order's customer".to_string()
+        )));
+    }
+    if !verify_signature(
+        input.token.signer.clone(),
+        input.token.signature.clone(),
+        input.token.token.clone(),
+    )? {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Token signature does not verify".to_string()
+        )));
+    }
+    let now = sys_time()?.as_millis() as u64;
+    if now > input.token.token.expires_at {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Token has expired; ask the customer to refresh their code".to_string()
+        )));
+    }
+
+    let (newest_hash, mut cart) =
+        crate::checkout::latest_order_revision(input.order_hash.clone())?;
+    if cart.handoff_verification.is_some() {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Order has already been collected".to_string()
+        )));
+    }
+    let verification = HandoffVerification {
+        verified_at: now,
+        verified_by: agent,
+    };
+    cart.handoff_verification = Some(verification.clone());
+    update_entry(newest_hash, &EntryTypes::CheckedOutCart(cart))?;
+    Ok(verification)
+}
@@ -0,0 +1,83 @@
+use cart_integrity::*;
+use hdk::prelude::*;
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct SavePreferenceInput {
+    #[serde(alias = "groupHash")]
+    pub group_hash: ActionHash,
+    #[serde(alias = "productIndex")]
+    pub product_index: u32,
+    pub note: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct PreferenceKey {
+    #[serde(alias = "groupHash")]
+    pub group_hash: ActionHash,
+    #[serde(alias = "productIndex")]
+    pub product_index: u32,
+}
+
+fn find_preference(key: &PreferenceKey) -> ExternResult<Option<(Link, ProductPreference)>> {
+    let agent = agent_info()?.agent_initial_pubkey;
+    let links = get_links(
+        GetLinksInputBuilder::try_new(agent, LinkTypes::ProductPreference)?.build(),
+    )?;
+    for link in links {
+        let Some(hash) = link.target.clone().into_action_hash() else {
+            continue;
+        };
+        let Some(record) = get(hash, GetOptions::default())? else {
+            continue;
+        };
+        if let Some(preference) = record
+            .entry()
+            .to_app_option::<ProductPreference>()
+            .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        {
+            if preference.group_hash == key.group_hash
+                && preference.product_index == key.product_index
+            {
+                return Ok(Some((link, preference)));
+            }
+        }
+    }
+    Ok(None)
+}
+
+#[hdk_extern]
+pub fn save_product_preference(input: SavePreferenceInput) -> ExternResult<ActionHash> {
+    let agent = agent_info()?.agent_initial_pubkey;
+    let key = PreferenceKey {
+        group_hash: input.group_hash.clone(),
+        product_index: input.product_index,
+    };
+    if let Some((link, _)) = find_preference(&key)? {
+        delete_link(link.create_link_hash)?;
+    }
+
+    let preference = ProductPreference {
+        group_hash: input.group_hash,
+        product_index: input.product_index,
+        note: input.note,
+        timestamp: sys_time()?.as_millis() as u64,
+    };
+    let hash = create_entry(&EntryTypes::ProductPreference(preference))?;
+    create_link(agent, hash.clone(), LinkTypes::ProductPreference, ())?;
+    Ok(hash)
+}
+
+#[hdk_extern]
+pub fn get_product_preference(key: PreferenceKey) -> ExternResult<Option<ProductPreference>> {
+    Ok(find_preference(&key)?.map(|(_, preference)| preference))
+}
+
+#[hdk_extern]
+pub fn delete_product_preference(key: PreferenceKey) -> ExternResult<()> {
+    if let Some((link, _)) = find_preference(&key)? {
+        delete_link(link.create_link_hash)?;
+    }
+    Ok(())
+}
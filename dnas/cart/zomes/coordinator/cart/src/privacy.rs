@@ -0,0 +1,146 @@
+use cart_integrity::*;
+use hdk::prelude::*;
+
+use crate::checkout::latest_order_revision;
+use crate::history::own_order_history;
+
+/// Marker written in place of free-text the customer asked to erase.
+const REDACTED: &str = "[redacted]";
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct ErasureReport {
+    pub addresses_deleted: u32,
+    pub preferences_deleted: u32,
+    /// Private cart snapshots, deltas and saved carts tombstoned.
+    pub cart_entries_deleted: u32,
+    /// Public orders whose address reference and notes were replaced
+    /// with redacted markers.
+    pub orders_redacted: u32,
+}
+
+/// Tombstone every create and update of one private entry type on the
+/// caller's chain.
+fn delete_own_entries(unit: UnitEntryTypes) -> ExternResult<u32> {
+    let filter = ChainQueryFilter::new().entry_type(unit.try_into()?);
+    let mut deleted = 0;
+    for record in query(filter)? {
+        if matches!(
+            record.action(),
+            Action::Create(_) | Action::Update(_)
+        ) {
+            delete_entry(record.action_address().clone())?;
+            deleted += 1;
+        }
+    }
+    Ok(deleted)
+}
+
+/// Remove every link of one type hanging off the caller's key.
+fn delete_agent_links(link_type: LinkTypes) -> ExternResult<()> {
+    let agent = agent_info()?.agent_initial_pubkey;
+    for link in get_links(GetLinksInputBuilder::try_new(agent, link_type)?.build())? {
+        delete_link(link.create_link_hash)?;
+    }
+    Ok(())
+}
+
+/// Delete the caller's saved addresses over the bridge to the profiles
+/// DNA.
+fn delete_all_addresses() -> ExternResult<u32> {
+    let addresses: Vec<(ActionHash, crate::export::ExportedAddress)> =
+        crate::bridge::call_address("get_addresses", ())?;
+
+    let mut deleted = 0;
+    for (address_hash, _) in addresses {
+        let _: ActionHash = crate::bridge::call_address("delete_address", address_hash)?;
+        deleted += 1;
+    }
+    Ok(deleted)
+}
+
+/// Rewrite one order with its personal data removed: the address
+/// reference is dropped and free-text fields become [`REDACTED`].
+/// Prices, statuses and history stay intact, so the order still
+/// validates and fulfillment records keep adding up.
+fn redact_order(cart_hash: ActionHash) -> ExternResult<bool> {
+    let (newest_hash, mut cart) = latest_order_revision(cart_hash)?;
+
+    let mut changed = false;
+    if cart.address_hash.is_some() {
+        cart.address_hash = None;
+        changed = true;
+    }
+    // The fulfillment method embeds the address for delivery orders, so
+    // it has to go along with the address field.
+    if matches!(
+        cart.fulfillment_method,
+        Some(FulfillmentMethod::Delivery { .. })
+    ) {
+        cart.fulfillment_method = None;
+        changed = true;
+    }
+    if cart
+        .delivery_instructions
+        .as_deref()
+        .is_some_and(|text| text != REDACTED)
+    {
+        cart.delivery_instructions = Some(REDACTED.to_string());
+        changed = true;
+    }
+    for item in &mut cart.products {
+        if item.note.as_deref().is_some_and(|text| text != REDACTED) {
+            item.note = Some(REDACTED.to_string());
+            changed = true;
+        }
+    }
+    for change in &mut cart.delivery_details_history {
+        if change
+            .delivery_instructions
+            .as_deref()
+            .is_some_and(|text| text != REDACTED)
+        {
+            change.delivery_instructions = Some(REDACTED.to_string());
+            changed = true;
+        }
+    }
+
+    if changed {
+        update_entry(newest_hash, &EntryTypes::CheckedOutCart(cart))?;
+    }
+    Ok(changed)
+}
+
+/// Erase the caller's personal data: saved addresses, product
+/// preferences, private carts (snapshots, deltas and saved-for-later
+/// copies) and the free text on their public orders. Public order
+/// records themselves stay, redacted, because counterparties rely on
+/// them.
+#[hdk_extern]
+pub fn erase_my_data(_: ()) -> ExternResult<ErasureReport> {
+    let mut addresses_deleted = delete_all_addresses()?;
+    addresses_deleted += delete_own_entries(UnitEntryTypes::PrivateDeliveryAddress)?;
+
+    let preferences_deleted = delete_own_entries(UnitEntryTypes::ProductPreference)?;
+    delete_agent_links(LinkTypes::ProductPreference)?;
+
+    let mut cart_entries_deleted = delete_own_entries(UnitEntryTypes::PrivateCart)?;
+    cart_entries_deleted += delete_own_entries(UnitEntryTypes::CartDelta)?;
+    cart_entries_deleted += delete_own_entries(UnitEntryTypes::SavedCart)?;
+    delete_agent_links(LinkTypes::PrivateCart)?;
+    delete_agent_links(LinkTypes::CartDelta)?;
+
+    let mut orders_redacted = 0;
+    for (cart_hash, _) in own_order_history()? {
+        if redact_order(cart_hash)? {
+            orders_redacted += 1;
+        }
+    }
+
+    Ok(ErasureReport {
+        addresses_deleted,
+        preferences_deleted,
+        cart_entries_deleted,
+        orders_redacted,
+    })
+}
@@ -0,0 +1,161 @@
+use cart_integrity::*;
+use hdk::prelude::*;
+
+/// Anchor all promo codes hang off, so they can be looked up by code.
+pub(crate) fn promo_codes_anchor() -> ExternResult<TypedPath> {
+    Path::from("promo_codes").typed(LinkTypes::PromoCode)
+}
+
+/// Create a promo code. Authorship is checked against the configured
+/// admin list in validation.
+#[hdk_extern]
+pub fn create_promo_code(promo: PromoCode) -> ExternResult<ActionHash> {
+    crate::features::require_feature("promos", |flags| flags.enable_promos)?;
+    let anchor = promo_codes_anchor()?;
+    anchor.ensure()?;
+    let promo_hash = create_entry(&EntryTypes::PromoCode(promo))?;
+    create_link(
+        anchor.path_entry_hash()?,
+        promo_hash.clone(),
+        LinkTypes::PromoCode,
+        (),
+    )?;
+    Ok(promo_hash)
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct PromoCodeWithHash {
+    pub promo_hash: ActionHash,
+    pub promo: PromoCode,
+}
+
+#[hdk_extern]
+pub fn get_promo_codes(_: ()) -> ExternResult<Vec<PromoCodeWithHash>> {
+    let anchor = promo_codes_anchor()?;
+    let links = get_links(
+        GetLinksInputBuilder::try_new(anchor.path_entry_hash()?, LinkTypes::PromoCode)?.build(),
+    )?;
+
+    let mut codes = Vec::new();
+    for link in links {
+        let Some(hash) = link.target.into_action_hash() else {
+            continue;
+        };
+        let Some(record) = get(hash.clone(), GetOptions::default())? else {
+            continue;
+        };
+        if let Some(promo) = record
+            .entry()
+            .to_app_option::<PromoCode>()
+            .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        {
+            codes.push(PromoCodeWithHash {
+                promo_hash: hash,
+                promo,
+            });
+        }
+    }
+    Ok(codes)
+}
+
+fn find_promo_code(code: &str) -> ExternResult<Option<PromoCodeWithHash>> {
+    Ok(get_promo_codes(())?
+        .into_iter()
+        .find(|entry| entry.promo.code == code))
+}
+
+/// Network-wide redemption count, from the use links hung off the promo
+/// entry at checkout.
+pub(crate) fn promo_use_count(promo_hash: &ActionHash) -> ExternResult<usize> {
+    let links = get_links(
+        GetLinksInputBuilder::try_new(promo_hash.clone(), LinkTypes::PromoCodeUse)?.build(),
+    )?;
+    Ok(links.len())
+}
+
+/// Whether the caller has already redeemed this code on a past order,
+/// from their own source chain.
+pub(crate) fn caller_already_used(promo_hash: &ActionHash) -> ExternResult<bool> {
+    let filter = ChainQueryFilter::new()
+        .entry_type(UnitEntryTypes::CheckedOutCart.try_into()?)
+        .include_entries(true);
+    for record in query(filter)? {
+        if let Some(cart) = record
+            .entry()
+            .to_app_option::<CheckedOutCart>()
+            .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        {
+            if cart.status != OrderStatus::Returned && cart.promo_code_hash.as_ref() == Some(promo_hash) {
+                return Ok(true);
+            }
+        }
+    }
+    Ok(false)
+}
+
+/// Check a promo code is currently redeemable by the caller, returning
+/// a Guest error describing why when it is not.
+pub(crate) fn check_promo_redeemable(
+    promo_hash: &ActionHash,
+    promo: &PromoCode,
+) -> ExternResult<()> {
+    let now = sys_time()?.as_millis() as u64;
+    if now < promo.valid_from || now > promo.valid_until {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Promo code is not currently valid".to_string()
+        )));
+    }
+    if promo.usage_limit > 0 && promo_use_count(promo_hash)? >= promo.usage_limit as usize {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Promo code has reached its usage limit".to_string()
+        )));
+    }
+    if caller_already_used(promo_hash)? {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Promo code was already redeemed on a previous order".to_string()
+        )));
+    }
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct AppliedPromo {
+    pub promo_hash: ActionHash,
+    pub promo: PromoCode,
+}
+
+/// Validate a code and attach it to the caller's private cart. The
+/// discount amount itself is computed at checkout from the final
+/// subtotal.
+#[hdk_extern]
+pub fn apply_promo_code(code: String) -> ExternResult<AppliedPromo> {
+    crate::features::require_feature("promos", |flags| flags.enable_promos)?;
+    let found = find_promo_code(&code)?.ok_or(wasm_error!(WasmErrorInner::Guest(
+        "Unknown promo code".to_string()
+    )))?;
+    check_promo_redeemable(&found.promo_hash, &found.promo)?;
+
+    let mut cart = crate::cart::get_private_cart_impl()?;
+    cart.promo_code_hash = Some(found.promo_hash.clone());
+    cart.last_updated = sys_time()?.as_millis() as u64;
+    crate::cart::write_private_cart(cart)?;
+
+    Ok(AppliedPromo {
+        promo_hash: found.promo_hash,
+        promo: found.promo,
+    })
+}
+
+/// Detach any applied promo code from the caller's private cart.
+#[hdk_extern]
+pub fn remove_promo_code(_: ()) -> ExternResult<()> {
+    let mut cart = crate::cart::get_private_cart_impl()?;
+    if cart.promo_code_hash.is_some() {
+        cart.promo_code_hash = None;
+        cart.last_updated = sys_time()?.as_millis() as u64;
+        crate::cart::write_private_cart(cart)?;
+    }
+    Ok(())
+}
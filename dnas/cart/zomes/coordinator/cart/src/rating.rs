@@ -0,0 +1,147 @@
+use cart_integrity::*;
+use hdk::prelude::*;
+
+use crate::checkout::latest_order_revision;
+use crate::shopper::order_claimer;
+use crate::tracking::order_customer;
+
+/// The profile create hash an agent's key links point at, if they have
+/// registered as a shopper.
+fn shopper_profile_hash(agent: &AgentPubKey) -> ExternResult<Option<ActionHash>> {
+    let links = get_links(
+        GetLinksInputBuilder::try_new(agent.clone(), LinkTypes::ShopperProfile)?.build(),
+    )?;
+    Ok(links
+        .into_iter()
+        .filter_map(|link| link.target.into_action_hash())
+        .next())
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct RateShopperInput {
+    #[serde(alias = "orderHash")]
+    pub order_hash: ActionHash,
+    pub stars: u8,
+    #[serde(default)]
+    pub comment: Option<String>,
+}
+
+/// Rate the shopper who fulfilled one of the caller's completed
+/// orders. One rating per order; the star range and customer-only
+/// authorship are enforced again in validation.
+#[hdk_extern]
+pub fn rate_shopper(input: RateShopperInput) -> ExternResult<ActionHash> {
+    crate::features::require_feature("reviews", |flags| flags.enable_reviews)?;
+    if order_customer(&input.order_hash)? != agent_info()?.agent_initial_pubkey {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Only the customer who placed an order may rate its shopper".to_string()
+        )));
+    }
+    let (_, cart) = latest_order_revision(input.order_hash.clone())?;
+    if cart.status != OrderStatus::Completed {
+        return Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "Orders can only be rated after delivery (status {:?})",
+            cart.status
+        ))));
+    }
+    if !order_ratings(&input.order_hash)?.is_empty() {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Order has already been rated".to_string()
+        )));
+    }
+    let shopper = order_claimer(&input.order_hash)?.ok_or(wasm_error!(
+        WasmErrorInner::Guest("Order has no assigned shopper to rate".to_string())
+    ))?;
+
+    let rating = ShopperRating {
+        order_hash: input.order_hash.clone(),
+        shopper: shopper.clone(),
+        stars: input.stars,
+        comment: input.comment,
+        rated_at: sys_time()?.as_millis() as u64,
+    };
+    let rating_hash = create_entry(&EntryTypes::ShopperRating(rating))?;
+    create_link(
+        input.order_hash,
+        rating_hash.clone(),
+        LinkTypes::ShopperRating,
+        (),
+    )?;
+    if let Some(profile_hash) = shopper_profile_hash(&shopper)? {
+        create_link(
+            profile_hash,
+            rating_hash.clone(),
+            LinkTypes::ShopperRating,
+            (),
+        )?;
+    }
+    Ok(rating_hash)
+}
+
+/// Resolve the ratings linked from a base (an order or a shopper
+/// profile).
+fn ratings_at(base: impl Into<AnyLinkableHash>) -> ExternResult<Vec<ShopperRating>> {
+    let links = get_links(
+        GetLinksInputBuilder::try_new(base.into(), LinkTypes::ShopperRating)?.build(),
+    )?;
+    let mut ratings = Vec::new();
+    for link in links {
+        let Some(rating_hash) = link.target.into_action_hash() else {
+            continue;
+        };
+        let Some(record) = get(rating_hash, GetOptions::default())? else {
+            continue;
+        };
+        if let Some(rating) = record
+            .entry()
+            .to_app_option::<ShopperRating>()
+            .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        {
+            ratings.push(rating);
+        }
+    }
+    Ok(ratings)
+}
+
+fn order_ratings(order_hash: &ActionHash) -> ExternResult<Vec<ShopperRating>> {
+    ratings_at(order_hash.clone())
+}
+
+/// Count and mean star rating across a shopper profile's ratings.
+/// `average` is `None` until the first rating lands.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct RatingSummary {
+    pub count: u32,
+    pub average: Option<f64>,
+}
+
+pub(crate) fn rating_summary(profile_hash: &ActionHash) -> ExternResult<RatingSummary> {
+    let ratings = ratings_at(profile_hash.clone())?;
+    let count = ratings.len() as u32;
+    let average = if count == 0 {
+        None
+    } else {
+        Some(ratings.iter().map(|rating| rating.stars as f64).sum::<f64>() / count as f64)
+    };
+    Ok(RatingSummary { count, average })
+}
+
+/// The rating a customer left on one of their orders, if any.
+#[hdk_extern]
+pub fn get_order_rating(order_hash: ActionHash) -> ExternResult<Option<ShopperRating>> {
+    Ok(order_ratings(&order_hash)?.into_iter().next())
+}
+
+/// All ratings left on a shopper's profile, newest first, for the
+/// review list under their aggregate score.
+#[hdk_extern]
+pub fn get_shopper_ratings(agent: AgentPubKey) -> ExternResult<Vec<ShopperRating>> {
+    let Some(profile_hash) = shopper_profile_hash(&agent)? else {
+        return Ok(Vec::new());
+    };
+    let mut ratings = ratings_at(profile_hash)?;
+    ratings.sort_by_key(|entry| std::cmp::Reverse(entry.rated_at));
+    Ok(ratings)
+}
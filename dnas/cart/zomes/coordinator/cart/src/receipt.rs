@@ -0,0 +1,165 @@
+use cart_integrity::*;
+use hdk::prelude::*;
+
+use summon_types::SummonError;
+
+use crate::checkout::{latest_order_revision, round_cents};
+
+/// A quantity correction for one line, e.g. the actual weight of a
+/// weight-based item.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct QuantityAdjustment {
+    #[serde(alias = "groupHash")]
+    pub group_hash: ActionHash,
+    #[serde(alias = "productIndex")]
+    pub product_index: u32,
+    #[serde(alias = "actualQuantity")]
+    pub actual_quantity: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct CreateReceiptInput {
+    #[serde(alias = "orderHash")]
+    pub order_hash: ActionHash,
+    #[serde(default)]
+    pub adjustments: Vec<QuantityAdjustment>,
+    #[serde(default)]
+    pub tip: f64,
+}
+
+/// Build the receipt for a completed order from its final revision:
+/// snapshot prices, recorded fulfillments, and any weight adjustments.
+/// Out-of-stock and refunded lines are charged at zero.
+#[hdk_extern]
+pub fn create_receipt(input: CreateReceiptInput) -> ExternResult<ActionHash> {
+    let (newest_hash, order) = latest_order_revision(input.order_hash.clone())?;
+    if order.status != OrderStatus::Completed {
+        return Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "Receipts can only be written for completed orders (status {:?})",
+            order.status
+        ))));
+    }
+    let agent = agent_info()?.agent_initial_pubkey;
+    let is_fulfiller = order
+        .status_history
+        .iter()
+        .rev()
+        .find(|change| {
+            matches!(
+                change.status,
+                OrderStatus::Shopping | OrderStatus::Delivering
+            )
+        })
+        .is_some_and(|change| change.actor == agent);
+    if !is_fulfiller {
+        return Err(SummonError::not_authorized(
+            "Only the shopper who fulfilled the order may write its receipt",
+        )
+        .into());
+    }
+    if get_receipt(input.order_hash.clone())?.is_some() {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Order already has a receipt".to_string()
+        )));
+    }
+
+    let mut lines = Vec::with_capacity(order.products.len());
+    for (position, (item, snapshot)) in order
+        .products
+        .iter()
+        .zip(&order.product_snapshots)
+        .enumerate()
+    {
+        let fulfillment = order
+            .item_fulfillments
+            .get(position)
+            .cloned()
+            .flatten();
+        let quantity = input
+            .adjustments
+            .iter()
+            .find(|adjustment| {
+                adjustment.group_hash == item.group_hash
+                    && adjustment.product_index == item.product_index
+            })
+            .map(|adjustment| adjustment.actual_quantity)
+            .unwrap_or(match fulfillment {
+                // Actual picked quantities recorded while shopping
+                // carry through without a separate adjustment.
+                Some(ItemFulfillment::Picked { actual_quantity }) => actual_quantity,
+                _ => item.quantity,
+            });
+        let unit_price = snapshot.promo_price.unwrap_or(snapshot.price);
+        let line_total = match fulfillment {
+            Some(ItemFulfillment::OutOfStock) | Some(ItemFulfillment::Refunded) => 0.0,
+            _ => round_cents(unit_price * quantity),
+        };
+        lines.push(ReceiptLine {
+            name: snapshot.name.clone(),
+            quantity,
+            unit_price,
+            line_total,
+            fulfillment,
+        });
+    }
+
+    let subtotal = round_cents(lines.iter().map(|line| line.line_total).sum());
+    let tip = round_cents(input.tip);
+    let receipt = Receipt {
+        order_hash: input.order_hash.clone(),
+        final_revision_hash: newest_hash,
+        lines,
+        subtotal,
+        discount: order.discount,
+        tax: order.tax,
+        delivery_fee: order.delivery_fee,
+        tip,
+        total: round_cents(subtotal - order.discount + order.tax + order.delivery_fee + tip),
+        created_at: sys_time()?.as_millis() as u64,
+    };
+
+    let receipt_hash = create_entry(&EntryTypes::Receipt(receipt))?;
+    create_link(
+        input.order_hash,
+        receipt_hash.clone(),
+        LinkTypes::Receipt,
+        (),
+    )?;
+    Ok(receipt_hash)
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct ReceiptWithHash {
+    pub receipt_hash: ActionHash,
+    pub receipt: Receipt,
+}
+
+/// The receipt for an order, if one has been written yet.
+#[hdk_extern]
+pub fn get_receipt(order_hash: ActionHash) -> ExternResult<Option<ReceiptWithHash>> {
+    let links = get_links(
+        GetLinksInputBuilder::try_new(order_hash, LinkTypes::Receipt)?.build(),
+    )?;
+    for link in links {
+        let Some(hash) = link.target.into_action_hash() else {
+            continue;
+        };
+        let Some(record) = get(hash.clone(), GetOptions::default())? else {
+            continue;
+        };
+        if let Some(receipt) = record
+            .entry()
+            .to_app_option::<Receipt>()
+            .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        {
+            return Ok(Some(ReceiptWithHash {
+                receipt_hash: hash,
+                receipt,
+            }));
+        }
+    }
+    Ok(None)
+}
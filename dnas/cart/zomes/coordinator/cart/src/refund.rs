@@ -0,0 +1,203 @@
+use cart_integrity::*;
+use hdk::prelude::*;
+
+use crate::checkout::latest_order_revision;
+
+/// Anchor admins list open refund requests from.
+pub(crate) fn refund_requests_anchor() -> ExternResult<TypedPath> {
+    Path::from("refund_requests").typed(LinkTypes::RefundRequest)
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct CreateRefundRequestInput {
+    #[serde(alias = "orderHash")]
+    pub order_hash: ActionHash,
+    pub items: Vec<RefundItem>,
+    pub reason: String,
+    #[serde(default, alias = "photoHashes")]
+    pub photo_hashes: Vec<ActionHash>,
+}
+
+/// File a refund request against a delivered order. The items must all
+/// be lines of that order.
+#[hdk_extern]
+pub fn create_refund_request(input: CreateRefundRequestInput) -> ExternResult<ActionHash> {
+    let (_, order) = latest_order_revision(input.order_hash.clone())?;
+    if order.status != OrderStatus::Completed {
+        return Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "Refunds can only be requested on completed orders (status {:?})",
+            order.status
+        ))));
+    }
+    for item in &input.items {
+        if !order.products.iter().any(|line| {
+            line.group_hash == item.group_hash && line.product_index == item.product_index
+        }) {
+            return Err(wasm_error!(WasmErrorInner::Guest(format!(
+                "Order has no line {}[{}]",
+                item.group_hash, item.product_index
+            ))));
+        }
+    }
+
+    let refund = RefundRequest {
+        order_hash: input.order_hash.clone(),
+        items: input.items,
+        reason: input.reason,
+        photo_hashes: input.photo_hashes,
+        status: RefundStatus::Requested,
+        requested_at: sys_time()?.as_millis() as u64,
+    };
+    let refund_hash = create_entry(&EntryTypes::RefundRequest(refund))?;
+
+    let anchor = refund_requests_anchor()?;
+    anchor.ensure()?;
+    create_link(
+        anchor.path_entry_hash()?,
+        refund_hash.clone(),
+        LinkTypes::RefundRequest,
+        (),
+    )?;
+    create_link(
+        input.order_hash,
+        refund_hash.clone(),
+        LinkTypes::RefundRequest,
+        (),
+    )?;
+    Ok(refund_hash)
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct UpdateRefundStatusInput {
+    #[serde(alias = "refundHash")]
+    pub refund_hash: ActionHash,
+    pub status: RefundStatus,
+}
+
+/// Resolve a refund request (approve, deny, mark refunded). Restricted
+/// to admin agents in validation.
+#[hdk_extern]
+pub fn update_refund_status(input: UpdateRefundStatusInput) -> ExternResult<ActionHash> {
+    let (newest_hash, mut refund) = latest_refund_revision(input.refund_hash)?;
+    if !refund.status.can_transition_to(input.status) {
+        return Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "Cannot move refund from {:?} to {:?}",
+            refund.status, input.status
+        ))));
+    }
+    refund.status = input.status;
+    update_entry(newest_hash, &EntryTypes::RefundRequest(refund))
+}
+
+/// The newest revision of a refund request, following its update chain.
+fn latest_refund_revision(
+    refund_hash: ActionHash,
+) -> ExternResult<(ActionHash, RefundRequest)> {
+    let details = get_details(refund_hash.clone(), GetOptions::default())?.ok_or(wasm_error!(
+        WasmErrorInner::Guest("RefundRequest not found".to_string())
+    ))?;
+    let Details::Record(details) = details else {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Expected record details".to_string()
+        )));
+    };
+
+    let mut newest = (refund_hash, details.record);
+    let mut updates = details.updates;
+    while let Some(update) = updates
+        .iter()
+        .max_by_key(|update| update.action().timestamp())
+        .cloned()
+    {
+        let hash = update.action_address().clone();
+        let Some(update_details) = get_details(hash.clone(), GetOptions::default())? else {
+            break;
+        };
+        let Details::Record(update_details) = update_details else {
+            break;
+        };
+        newest = (hash, update_details.record);
+        updates = update_details.updates;
+    }
+
+    let refund: RefundRequest = newest
+        .1
+        .entry()
+        .to_app_option()
+        .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        .ok_or(wasm_error!(WasmErrorInner::Guest(
+            "Record is not a RefundRequest".to_string()
+        )))?;
+    Ok((newest.0, refund))
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct RefundRequestWithHash {
+    pub refund_hash: ActionHash,
+    pub refund: RefundRequest,
+}
+
+fn resolve_refund_links(links: Vec<Link>) -> ExternResult<Vec<RefundRequestWithHash>> {
+    let mut refunds = Vec::new();
+    for link in links {
+        let Some(hash) = link.target.into_action_hash() else {
+            continue;
+        };
+        let Ok((_, refund)) = latest_refund_revision(hash.clone()) else {
+            continue;
+        };
+        refunds.push(RefundRequestWithHash {
+            refund_hash: hash,
+            refund,
+        });
+    }
+    refunds.sort_by_key(|entry| std::cmp::Reverse(entry.refund.requested_at));
+    Ok(refunds)
+}
+
+/// The caller's own refund requests, from their source chain.
+#[hdk_extern]
+pub fn get_my_refund_requests(_: ()) -> ExternResult<Vec<RefundRequestWithHash>> {
+    let filter = ChainQueryFilter::new()
+        .entry_type(UnitEntryTypes::RefundRequest.try_into()?)
+        .action_type(ActionType::Create)
+        .include_entries(true);
+
+    let mut refunds = Vec::new();
+    for record in query(filter)? {
+        let hash = record.action_address().clone();
+        if let Ok((_, refund)) = latest_refund_revision(hash.clone()) {
+            refunds.push(RefundRequestWithHash {
+                refund_hash: hash,
+                refund,
+            });
+        }
+    }
+    refunds.sort_by_key(|entry| std::cmp::Reverse(entry.refund.requested_at));
+    Ok(refunds)
+}
+
+/// Every refund request on the network, for the admin/fulfiller side.
+#[hdk_extern]
+pub fn get_all_refund_requests(_: ()) -> ExternResult<Vec<RefundRequestWithHash>> {
+    let anchor = refund_requests_anchor()?;
+    let links = get_links(
+        GetLinksInputBuilder::try_new(anchor.path_entry_hash()?, LinkTypes::RefundRequest)?
+            .build(),
+    )?;
+    resolve_refund_links(links)
+}
+
+/// Refund requests filed against one order.
+#[hdk_extern]
+pub fn get_refund_requests_for_order(
+    order_hash: ActionHash,
+) -> ExternResult<Vec<RefundRequestWithHash>> {
+    let links = get_links(
+        GetLinksInputBuilder::try_new(order_hash, LinkTypes::RefundRequest)?.build(),
+    )?;
+    resolve_refund_links(links)
+}
@@ -0,0 +1,111 @@
+//! Recurring-job scheduler. The conductor drives one five-minute tick
+//! (`run_due_jobs`, registered from `init`); the tick consults the job
+//! table, runs whatever is due, and records a private [`JobRun`] entry
+//! per run so each job keeps its own period regardless of how often the
+//! tick fires. Adding a recurring job is one table row, not a new
+//! scheduled extern.
+
+use std::collections::BTreeMap;
+
+use cart_integrity::*;
+use hdk::prelude::*;
+use summon_types::ScheduledJob;
+
+const MINUTE_MS: u64 = 60_000;
+const DAY_MS: u64 = 24 * 60 * MINUTE_MS;
+
+/// A job body: runs the work, reporting but not propagating errors
+/// upward (the scheduler records the attempt either way).
+type JobBody = fn() -> ExternResult<()>;
+
+/// The zome's recurring jobs, in the order they run within one tick.
+/// Job bodies swallow their own errors — a failing job is retried next
+/// period, never allowed to starve the jobs after it.
+const JOBS: &[(ScheduledJob, JobBody)] = &[
+    (
+        ScheduledJob {
+            name: "release_stale_claims",
+            period_ms: 15 * MINUTE_MS,
+        },
+        crate::shopper::release_stale_claims,
+    ),
+    (
+        ScheduledJob {
+            name: "resolve_expired_substitutions",
+            period_ms: 5 * MINUTE_MS,
+        },
+        crate::substitution::resolve_expired_substitutions,
+    ),
+    (
+        ScheduledJob {
+            name: "auto_archive_orders",
+            period_ms: DAY_MS,
+        },
+        crate::checkout::auto_archive_orders,
+    ),
+    (
+        ScheduledJob {
+            name: "compact_private_cart",
+            period_ms: DAY_MS,
+        },
+        crate::cart::compact_private_cart,
+    ),
+    (
+        ScheduledJob {
+            name: "expire_abandoned_cart",
+            period_ms: DAY_MS,
+        },
+        crate::cart::expire_abandoned_cart,
+    ),
+];
+
+/// When each job last ran, from the chain's [`JobRun`] entries.
+fn last_runs() -> ExternResult<BTreeMap<String, u64>> {
+    let filter = ChainQueryFilter::new()
+        .entry_type(UnitEntryTypes::JobRun.try_into()?)
+        .include_entries(true);
+    let mut last_runs = BTreeMap::new();
+    for record in query(filter)? {
+        if let Some(run) = record
+            .entry()
+            .to_app_option::<JobRun>()
+            .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        {
+            let newest = last_runs.entry(run.job).or_insert(0);
+            *newest = (*newest).max(run.ran_at);
+        }
+    }
+    Ok(last_runs)
+}
+
+pub(crate) fn run_due_jobs_inner() -> ExternResult<Vec<String>> {
+    let now = sys_time()?.as_millis() as u64;
+    let last_runs = last_runs()?;
+
+    let mut ran = Vec::new();
+    for (job, run) in JOBS {
+        if !job.due(now, last_runs.get(job.name).copied()) {
+            continue;
+        }
+        // The run is recorded whether or not the body succeeded, so a
+        // persistently failing job retries once per period instead of
+        // on every tick.
+        let _ = run();
+        create_entry(&EntryTypes::JobRun(JobRun {
+            job: job.name.to_string(),
+            ran_at: now,
+        }))?;
+        ran.push(job.name.to_string());
+    }
+    Ok(ran)
+}
+
+/// The scheduler tick. Registered once from `init`; also callable
+/// directly (payload `null`) so tests can force a pass without waiting
+/// out the cron.
+#[hdk_extern(infallible)]
+pub fn run_due_jobs(_: Option<Schedule>) -> Option<Schedule> {
+    let _ = run_due_jobs_inner();
+    // Every five minutes; per-job periods come from the table.
+    Some(Schedule::Persisted("0 */5 * * * *".to_string()))
+}
@@ -0,0 +1,1100 @@
+use cart_integrity::*;
+use hdk::prelude::*;
+
+use crate::tracking::order_customer;
+
+/// Directory anchor the fulfillment board lists shoppers from.
+pub(crate) fn shoppers_anchor() -> ExternResult<TypedPath> {
+    Path::from("shoppers").typed(LinkTypes::ShopperProfile)
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct RegisterShopperInput {
+    #[serde(alias = "displayName")]
+    pub display_name: String,
+    #[serde(default, alias = "serviceZones")]
+    pub service_zones: Vec<String>,
+    #[serde(default)]
+    pub vehicle: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct ShopperWithHash {
+    pub profile_hash: ActionHash,
+    pub agent: AgentPubKey,
+    pub profile: ShopperProfile,
+}
+
+/// The caller's shopper profile, newest revision, with the create hash
+/// the directory links point at.
+pub(crate) fn own_shopper_profile() -> ExternResult<Option<(ActionHash, ShopperProfile)>> {
+    let agent = agent_info()?.agent_initial_pubkey;
+    let links = get_links(
+        GetLinksInputBuilder::try_new(agent, LinkTypes::ShopperProfile)?.build(),
+    )?;
+    let Some(hash) = links
+        .into_iter()
+        .filter_map(|link| link.target.into_action_hash())
+        .next()
+    else {
+        return Ok(None);
+    };
+    Ok(latest_shopper_revision(&hash)?.map(|(_, profile)| (hash, profile)))
+}
+
+/// Resolve a profile create hash to its newest revision.
+fn latest_shopper_revision(
+    profile_hash: &ActionHash,
+) -> ExternResult<Option<(ActionHash, ShopperProfile)>> {
+    let Some(details) = get_details(profile_hash.clone(), GetOptions::default())? else {
+        return Ok(None);
+    };
+    let Details::Record(details) = details else {
+        return Ok(None);
+    };
+    let mut newest = details.record;
+    let mut updates = details.updates;
+    updates.sort_by_key(|update| update.action().timestamp());
+    if let Some(update) = updates.pop() {
+        if let Some(record) = get(update.action_address().clone(), GetOptions::default())? {
+            newest = record;
+        }
+    }
+    Ok(newest
+        .entry()
+        .to_app_option::<ShopperProfile>()
+        .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        .map(|profile| (newest.action_address().clone(), profile)))
+}
+
+/// Register the caller as a fulfiller. One profile per agent;
+/// re-registering updates the existing one instead of adding a
+/// directory duplicate.
+#[hdk_extern]
+pub fn register_shopper(input: RegisterShopperInput) -> ExternResult<ActionHash> {
+    crate::features::require_feature("shopper_dispatch", |flags| flags.enable_shopper_dispatch)?;
+    // On invite-gated networks the membrane proof carries the role;
+    // only agents who joined on a shopper invite (or admins) register.
+    let admins = crate::checkout::dna_properties()?.admins;
+    if !admins.is_empty()
+        && !admins.contains(&agent_info()?.agent_initial_pubkey)
+        && crate::invite::own_invite_role()?.as_deref() != Some(SHOPPER_INVITE_ROLE)
+    {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Registering as a shopper requires joining with a shopper invite".to_string()
+        )));
+    }
+    let profile = ShopperProfile {
+        display_name: input.display_name,
+        service_zones: input.service_zones,
+        availability: Vec::new(),
+        location_sharing: LocationSharing::default(),
+        vehicle: input.vehicle,
+        active: true,
+        registered_at: sys_time()?.as_millis() as u64,
+    };
+
+    if let Some((profile_hash, existing)) = own_shopper_profile()? {
+        let updated = ShopperProfile {
+            registered_at: existing.registered_at,
+            availability: existing.availability,
+            location_sharing: existing.location_sharing,
+            ..profile
+        };
+        let (newest_hash, _) = latest_shopper_revision(&profile_hash)?.ok_or(wasm_error!(
+            WasmErrorInner::Guest("ShopperProfile not found".to_string())
+        ))?;
+        update_entry(newest_hash, &EntryTypes::ShopperProfile(updated))?;
+        return Ok(profile_hash);
+    }
+
+    let profile_hash = create_entry(&EntryTypes::ShopperProfile(profile))?;
+    create_link(
+        agent_info()?.agent_initial_pubkey,
+        profile_hash.clone(),
+        LinkTypes::ShopperProfile,
+        (),
+    )?;
+    let anchor = shoppers_anchor()?;
+    anchor.ensure()?;
+    create_link(
+        anchor.path_entry_hash()?,
+        profile_hash.clone(),
+        LinkTypes::ShopperProfile,
+        (),
+    )?;
+    Ok(profile_hash)
+}
+
+/// Flip the caller's active flag: off when they stop taking orders, on
+/// when they come back. The profile stays in the directory either way;
+/// listings filter on the flag.
+#[hdk_extern]
+pub fn set_shopper_active(active: bool) -> ExternResult<ActionHash> {
+    let (profile_hash, profile) = own_shopper_profile()?.ok_or(wasm_error!(
+        WasmErrorInner::Guest("Caller has no shopper profile".to_string())
+    ))?;
+    let (newest_hash, _) = latest_shopper_revision(&profile_hash)?.ok_or(wasm_error!(
+        WasmErrorInner::Guest("ShopperProfile not found".to_string())
+    ))?;
+    update_entry(
+        newest_hash,
+        &EntryTypes::ShopperProfile(ShopperProfile { active, ..profile }),
+    )?;
+    Ok(profile_hash)
+}
+
+/// Set when the caller's device may push live locations to customers.
+#[hdk_extern]
+pub fn set_location_sharing(mode: LocationSharing) -> ExternResult<ActionHash> {
+    let (profile_hash, profile) = own_shopper_profile()?.ok_or(wasm_error!(
+        WasmErrorInner::Guest("Caller has no shopper profile".to_string())
+    ))?;
+    let (newest_hash, _) = latest_shopper_revision(&profile_hash)?.ok_or(wasm_error!(
+        WasmErrorInner::Guest("ShopperProfile not found".to_string())
+    ))?;
+    update_entry(
+        newest_hash,
+        &EntryTypes::ShopperProfile(ShopperProfile {
+            location_sharing: mode,
+            ..profile
+        }),
+    )?;
+    Ok(profile_hash)
+}
+
+/// Replace the caller's weekly availability windows. An empty list
+/// means any time.
+#[hdk_extern]
+pub fn set_availability(windows: Vec<AvailabilityWindow>) -> ExternResult<ActionHash> {
+    let (profile_hash, profile) = own_shopper_profile()?.ok_or(wasm_error!(
+        WasmErrorInner::Guest("Caller has no shopper profile".to_string())
+    ))?;
+    let (newest_hash, _) = latest_shopper_revision(&profile_hash)?.ok_or(wasm_error!(
+        WasmErrorInner::Guest("ShopperProfile not found".to_string())
+    ))?;
+    update_entry(
+        newest_hash,
+        &EntryTypes::ShopperProfile(ShopperProfile {
+            availability: windows,
+            ..profile
+        }),
+    )?;
+    Ok(profile_hash)
+}
+
+#[hdk_extern]
+pub fn get_my_shopper_profile(_: ()) -> ExternResult<Option<ShopperWithHash>> {
+    let agent = agent_info()?.agent_initial_pubkey;
+    Ok(own_shopper_profile()?.map(|(profile_hash, profile)| ShopperWithHash {
+        profile_hash,
+        agent,
+        profile,
+    }))
+}
+
+/// A shopper's public profile with their aggregate customer rating.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct ShopperProfileWithRating {
+    pub profile_hash: ActionHash,
+    pub agent: AgentPubKey,
+    pub profile: ShopperProfile,
+    pub rating: crate::rating::RatingSummary,
+}
+
+/// Look up any agent's shopper profile, newest revision, with the
+/// rating summary customers see before accepting a claim.
+#[hdk_extern]
+pub fn get_shopper_profile(
+    agent: AgentPubKey,
+) -> ExternResult<Option<ShopperProfileWithRating>> {
+    let links = get_links(
+        GetLinksInputBuilder::try_new(agent.clone(), LinkTypes::ShopperProfile)?.build(),
+    )?;
+    let Some(profile_hash) = links
+        .into_iter()
+        .filter_map(|link| link.target.into_action_hash())
+        .next()
+    else {
+        return Ok(None);
+    };
+    let Some((_, profile)) = latest_shopper_revision(&profile_hash)? else {
+        return Ok(None);
+    };
+    let rating = crate::rating::rating_summary(&profile_hash)?;
+    Ok(Some(ShopperProfileWithRating {
+        profile_hash,
+        agent,
+        profile,
+        rating,
+    }))
+}
+
+/// Every shopper currently taking orders, for the fulfillment board.
+#[hdk_extern]
+pub fn get_active_shoppers(_: ()) -> ExternResult<Vec<ShopperWithHash>> {
+    let anchor = shoppers_anchor()?;
+    let links = get_links(
+        GetLinksInputBuilder::try_new(anchor.path_entry_hash()?, LinkTypes::ShopperProfile)?
+            .build(),
+    )?;
+
+    let mut shoppers = Vec::new();
+    for link in links {
+        let Some(profile_hash) = link.target.into_action_hash() else {
+            continue;
+        };
+        let Some(record) = get(profile_hash.clone(), GetOptions::default())? else {
+            continue;
+        };
+        let agent = record.action().author().clone();
+        let Some((_, profile)) = latest_shopper_revision(&profile_hash)? else {
+            continue;
+        };
+        if !profile.active {
+            continue;
+        }
+        shoppers.push(ShopperWithHash {
+            profile_hash,
+            agent,
+            profile,
+        });
+    }
+    shoppers.sort_by(|a, b| a.profile.display_name.cmp(&b.profile.display_name));
+    Ok(shoppers)
+}
+
+/// Anchor new orders wait under until a shopper claims them.
+pub(crate) fn available_orders_anchor() -> ExternResult<TypedPath> {
+    Path::from("available_orders").typed(LinkTypes::AvailableOrder)
+}
+
+/// The shopper holding an order's claim, if any: the assignee on an
+/// admin pre-assignment, otherwise the claim's author.
+pub(crate) fn order_claimer(order_hash: &ActionHash) -> ExternResult<Option<AgentPubKey>> {
+    let links = get_links(
+        GetLinksInputBuilder::try_new(order_hash.clone(), LinkTypes::OrderClaim)?.build(),
+    )?;
+    for link in links {
+        let Some(claim_hash) = link.target.into_action_hash() else {
+            continue;
+        };
+        let Some(record) = get(claim_hash, GetOptions::default())? else {
+            continue;
+        };
+        if let Some(claim) = record
+            .entry()
+            .to_app_option::<OrderClaim>()
+            .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        {
+            if let Some(shopper) = claim.shopper {
+                return Ok(Some(shopper));
+            }
+        }
+        return Ok(Some(record.action().author().clone()));
+    }
+    Ok(None)
+}
+
+/// Write the claim entry and take the order out of the available pool.
+pub(crate) fn record_claim(order_hash: ActionHash, shopper: Option<AgentPubKey>) -> ExternResult<ActionHash> {
+    let assignee = match &shopper {
+        Some(shopper) => shopper.clone(),
+        None => agent_info()?.agent_initial_pubkey,
+    };
+    let claim = OrderClaim {
+        order_hash: order_hash.clone(),
+        claimed_at: sys_time()?.as_millis() as u64,
+        shopper,
+        transferred_from: None,
+    };
+    let claim_hash = create_entry(&EntryTypes::OrderClaim(claim))?;
+    create_link(
+        order_hash.clone(),
+        claim_hash.clone(),
+        LinkTypes::OrderClaim,
+        (),
+    )?;
+    // Index the claim from the shopper's key too, so their fulfillment
+    // history is discoverable for stats and admin review.
+    create_link(assignee, claim_hash.clone(), LinkTypes::OrderClaim, ())?;
+
+    // Remove the order from the pool so the board stops offering it.
+    let anchor = available_orders_anchor()?;
+    let links = get_links(
+        GetLinksInputBuilder::try_new(anchor.path_entry_hash()?, LinkTypes::AvailableOrder)?
+            .build(),
+    )?;
+    for link in links {
+        if link.target.clone().into_action_hash() == Some(order_hash.clone()) {
+            delete_link(link.create_link_hash)?;
+        }
+    }
+    Ok(claim_hash)
+}
+
+/// Take an open order off the available pool and record the assignment.
+/// Requires an active shopper profile; first claim wins, later callers
+/// get an error naming the race.
+#[hdk_extern]
+pub fn claim_order(order_hash: ActionHash) -> ExternResult<ActionHash> {
+    crate::features::require_feature("shopper_dispatch", |flags| flags.enable_shopper_dispatch)?;
+    match own_shopper_profile()? {
+        Some((_, profile)) if profile.active => {}
+        Some(_) => {
+            return Err(wasm_error!(WasmErrorInner::Guest(
+                "Shopper profile is inactive".to_string()
+            )))
+        }
+        None => {
+            return Err(wasm_error!(WasmErrorInner::Guest(
+                "Only registered shoppers may claim orders".to_string()
+            )))
+        }
+    }
+    if order_claimer(&order_hash)?.is_some() {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Order has already been claimed".to_string()
+        )));
+    }
+    if crate::block::is_blocked(&order_customer(&order_hash)?)? {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "You have declined orders from this customer".to_string()
+        )));
+    }
+    let claim_hash = record_claim(order_hash.clone(), None)?;
+    // Kick off the capability handshake with the customer's cell.
+    request_order_access(order_hash)?;
+    Ok(claim_hash)
+}
+
+/// An order put back in the pool after its shopper sat on the claim
+/// without starting to shop, pushed to that shopper and the admin
+/// agents so dispatch can follow up.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum DispatchSignal {
+    ClaimReleased {
+        order_hash: ActionHash,
+        shopper: AgentPubKey,
+    },
+}
+
+/// Scheduled on the customer's cell through [`crate::scheduler`]:
+/// orders still in `Processing` whose claim is older than the
+/// configured window go back to the available pool so one unresponsive
+/// shopper can't strand them. Runs here because only the customer can
+/// revoke their own access grant.
+pub(crate) fn release_stale_claims() -> ExternResult<()> {
+    let window = crate::checkout::dna_properties()?.expiry.stale_claim_ms;
+    if window == 0 {
+        return Ok(());
+    }
+    let now = sys_time()?.as_millis() as u64;
+    for entry in crate::checkout::get_checked_out_carts_impl()? {
+        if entry.cart.status != OrderStatus::Processing {
+            continue;
+        }
+        let links = get_links(
+            GetLinksInputBuilder::try_new(entry.cart_hash.clone(), LinkTypes::OrderClaim)?
+                .build(),
+        )?;
+        for link in links {
+            let Some(claim_hash) = link.target.clone().into_action_hash() else {
+                continue;
+            };
+            let Some(record) = get(claim_hash, GetOptions::default())? else {
+                continue;
+            };
+            let Some(claim) = record
+                .entry()
+                .to_app_option::<OrderClaim>()
+                .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+            else {
+                continue;
+            };
+            if now.saturating_sub(claim.claimed_at) <= window {
+                continue;
+            }
+            let shopper = claim
+                .shopper
+                .unwrap_or_else(|| record.action().author().clone());
+            release_claim(&entry.cart_hash, link.create_link_hash, shopper)?;
+        }
+    }
+    Ok(())
+}
+
+/// Drop one stale claim: revoke the order-access grant, put the order
+/// back under the available anchor and notify dispatch.
+fn release_claim(
+    order_hash: &ActionHash,
+    claim_link_hash: ActionHash,
+    shopper: AgentPubKey,
+) -> ExternResult<()> {
+    // Revoke the per-order capability issued at claim time, if the
+    // handshake got that far.
+    let tag = order_access_tag(order_hash);
+    let grants = query(
+        ChainQueryFilter::new()
+            .entry_type(EntryType::CapGrant)
+            .include_entries(true),
+    )?;
+    for grant in grants {
+        let Some(Entry::CapGrant(grant_entry)) = grant.entry().as_option() else {
+            continue;
+        };
+        if grant_entry.tag == tag {
+            delete_cap_grant(grant.action_address().clone())?;
+        }
+    }
+
+    delete_link(claim_link_hash)?;
+
+    // Offer the order to shoppers again.
+    let anchor = available_orders_anchor()?;
+    anchor.ensure()?;
+    create_link(
+        anchor.path_entry_hash()?,
+        order_hash.clone(),
+        LinkTypes::AvailableOrder,
+        (),
+    )?;
+
+    let me = agent_info()?.agent_initial_pubkey;
+    let mut recipients = crate::checkout::dna_properties()?.admins;
+    recipients.retain(|admin| *admin != me && *admin != shopper);
+    recipients.push(shopper.clone());
+    crate::signal::push_remote_signal(
+        DispatchSignal::ClaimReleased {
+            order_hash: order_hash.clone(),
+            shopper,
+        },
+        recipients,
+    )
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct AvailableOrder {
+    pub order_hash: ActionHash,
+    pub created_at: u64,
+    pub item_count: u32,
+    pub total: f64,
+    pub delivery_time: Option<DeliveryTimeSlot>,
+    pub fulfillment_method: Option<FulfillmentMethod>,
+    pub delivery_zone: Option<String>,
+}
+
+/// Every unclaimed order in the pool, unsorted.
+fn open_available_orders() -> ExternResult<Vec<AvailableOrder>> {
+    let anchor = available_orders_anchor()?;
+    let links = get_links(
+        GetLinksInputBuilder::try_new(anchor.path_entry_hash()?, LinkTypes::AvailableOrder)?
+            .build(),
+    )?;
+
+    let mut orders = Vec::new();
+    for link in links {
+        let Some(order_hash) = link.target.into_action_hash() else {
+            continue;
+        };
+        if order_claimer(&order_hash)?.is_some() {
+            continue;
+        }
+        let Some(record) = get(order_hash.clone(), GetOptions::default())? else {
+            continue;
+        };
+        // The shopper's own declines keep those customers' orders off
+        // their board entirely.
+        if crate::block::is_blocked(record.action().author())? {
+            continue;
+        }
+        let Some(cart) = record
+            .entry()
+            .to_app_option::<CheckedOutCart>()
+            .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        else {
+            continue;
+        };
+        orders.push(AvailableOrder {
+            order_hash,
+            created_at: cart.created_at,
+            item_count: cart.products.len() as u32,
+            total: cart.total,
+            delivery_time: cart.delivery_time,
+            fulfillment_method: cart.fulfillment_method,
+            delivery_zone: cart.delivery_zone,
+        });
+    }
+    Ok(orders)
+}
+
+/// Open orders waiting for a shopper, oldest first. Orders claimed
+/// between link publication and this read are filtered out.
+#[hdk_extern]
+pub fn get_available_orders(_: ()) -> ExternResult<Vec<AvailableOrder>> {
+    let mut orders = open_available_orders()?;
+    orders.sort_by_key(|order| order.created_at);
+    Ok(orders)
+}
+
+/// When an order's promised window starts, for urgency sorting. Orders
+/// without a slot sort last.
+fn slot_start(order: &AvailableOrder) -> u64 {
+    order
+        .delivery_time
+        .as_ref()
+        .map(|slot| slot.date + slot.start_minute.unwrap_or(0) as u64 * 60_000)
+        .unwrap_or(u64::MAX)
+}
+
+/// Day of week for a slot date (ms at midnight), 0 = Sunday. The epoch
+/// was a Thursday.
+fn slot_day_of_week(date: u64) -> u8 {
+    ((date / 86_400_000 + 4) % 7) as u8
+}
+
+/// Whether a delivery slot intersects any of the shopper's weekly
+/// windows. No windows means any time; slots without structured
+/// minutes count as the whole day.
+fn slot_in_availability(windows: &[AvailabilityWindow], slot: &DeliveryTimeSlot) -> bool {
+    if windows.is_empty() {
+        return true;
+    }
+    let day = slot_day_of_week(slot.date);
+    let slot_start = slot.start_minute.unwrap_or(0);
+    let slot_end = slot.end_minute.unwrap_or(24 * 60);
+    windows.iter().any(|window| {
+        window.day_of_week == day
+            && window.start_minute < slot_end
+            && slot_start < window.end_minute
+    })
+}
+
+/// The open orders this shopper should see, filtered to the zones they
+/// declared and the windows they work, sorted most-urgent slot first.
+/// Shoppers with no declared zones or windows see everything, and
+/// orders without a zone or slot are offered to everyone.
+#[hdk_extern]
+pub fn get_dispatchable_orders(_: ()) -> ExternResult<Vec<AvailableOrder>> {
+    let (_, profile) = own_shopper_profile()?.ok_or(wasm_error!(WasmErrorInner::Guest(
+        "Only registered shoppers may be dispatched orders".to_string()
+    )))?;
+
+    let mut orders: Vec<AvailableOrder> = open_available_orders()?
+        .into_iter()
+        .filter(|order| {
+            profile.service_zones.is_empty()
+                || match &order.delivery_zone {
+                    Some(zone) => profile.service_zones.contains(zone),
+                    None => true,
+                }
+        })
+        .filter(|order| match &order.delivery_time {
+            Some(slot) => slot_in_availability(&profile.availability, slot),
+            None => true,
+        })
+        .collect();
+    orders.sort_by_key(|order| (slot_start(order), order.created_at));
+    Ok(orders)
+}
+
+/// Shopper headcount for one hour of a day, for the coverage view.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct HourCoverage {
+    pub start_minute: u32,
+    pub end_minute: u32,
+    pub shoppers: u32,
+}
+
+/// Admin view: how many active shoppers cover each hour of a given day
+/// (ms at midnight), so understaffed windows can be closed to new
+/// orders. Shoppers with no declared windows count everywhere.
+#[hdk_extern]
+pub fn get_availability_coverage(date: u64) -> ExternResult<Vec<HourCoverage>> {
+    let admins = crate::checkout::dna_properties()?.admins;
+    if !admins.is_empty() && !admins.contains(&agent_info()?.agent_initial_pubkey) {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Only admin agents may view availability coverage".to_string()
+        )));
+    }
+
+    let day = slot_day_of_week(date);
+    let shoppers = get_active_shoppers(())?;
+    let mut coverage = Vec::with_capacity(24);
+    for hour in 0..24u32 {
+        let start_minute = hour * 60;
+        let end_minute = start_minute + 60;
+        let count = shoppers
+            .iter()
+            .filter(|shopper| {
+                shopper.profile.availability.is_empty()
+                    || shopper.profile.availability.iter().any(|window| {
+                        window.day_of_week == day
+                            && window.start_minute < end_minute
+                            && start_minute < window.end_minute
+                    })
+            })
+            .count() as u32;
+        coverage.push(HourCoverage {
+            start_minute,
+            end_minute,
+            shoppers: count,
+        });
+    }
+    Ok(coverage)
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct AssignOrderInput {
+    #[serde(alias = "orderHash")]
+    pub order_hash: ActionHash,
+    pub shopper: AgentPubKey,
+}
+
+/// Admin override: pre-assign an open order to a specific shopper
+/// instead of waiting for a claim. Validation holds assignments by
+/// non-admins to self-claims only.
+#[hdk_extern]
+pub fn assign_order(input: AssignOrderInput) -> ExternResult<ActionHash> {
+    crate::features::require_feature("shopper_dispatch", |flags| flags.enable_shopper_dispatch)?;
+    let admins = crate::checkout::dna_properties()?.admins;
+    if !admins.is_empty() && !admins.contains(&agent_info()?.agent_initial_pubkey) {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Only admin agents may assign orders".to_string()
+        )));
+    }
+    if order_claimer(&input.order_hash)?.is_some() {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Order has already been claimed".to_string()
+        )));
+    }
+    record_claim(input.order_hash, Some(input.shopper))
+}
+
+/// Tag on the per-order capability grant and claim, with the order
+/// hash embedded so a grant never covers more than the order it was
+/// issued for.
+const ORDER_ACCESS_TAG_PREFIX: &str = "order-access:";
+
+/// A mid-trip handoff, pushed to the customer (so their UI follows the
+/// new shopper) and to the new shopper (whose cell kicks off its own
+/// access handshake).
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum TransferSignal {
+    OrderTransferred {
+        order_hash: ActionHash,
+        previous_shopper: AgentPubKey,
+        new_shopper: AgentPubKey,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct TransferOrderInput {
+    #[serde(alias = "orderHash")]
+    pub order_hash: ActionHash,
+    #[serde(alias = "newShopper")]
+    pub new_shopper: AgentPubKey,
+}
+
+/// Hand a claimed order over to another shopper mid-trip: a new claim
+/// naming the successor, a handoff marker in the status history, and
+/// signals to the customer and the new shopper. The new shopper's cell
+/// re-runs the access handshake, so the customer's capability grant is
+/// re-issued to them.
+#[hdk_extern]
+pub fn transfer_order(input: TransferOrderInput) -> ExternResult<ActionHash> {
+    let agent = agent_info()?.agent_initial_pubkey;
+    let current = order_claimer(&input.order_hash)?.ok_or(wasm_error!(
+        WasmErrorInner::Guest("Order has no claim to transfer".to_string())
+    ))?;
+    let admins = crate::checkout::dna_properties()?.admins;
+    if current != agent && !(admins.is_empty() || admins.contains(&agent)) {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Only the assigned shopper or an admin may transfer an order".to_string()
+        )));
+    }
+    if input.new_shopper == current {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Order is already assigned to that shopper".to_string()
+        )));
+    }
+    let (newest_hash, mut cart) = crate::checkout::latest_order_revision(input.order_hash.clone())?;
+    if matches!(
+        cart.status,
+        OrderStatus::Completed | OrderStatus::Cancelled | OrderStatus::Returned
+    ) {
+        return Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "Order is no longer in flight (status {:?})",
+            cart.status
+        ))));
+    }
+
+    // Replace the claim: new entry naming the successor, old links
+    // removed so `order_claimer` resolves to them alone.
+    let old_links = get_links(
+        GetLinksInputBuilder::try_new(input.order_hash.clone(), LinkTypes::OrderClaim)?.build(),
+    )?;
+    let old_claim_hash = old_links
+        .iter()
+        .filter_map(|link| link.target.clone().into_action_hash())
+        .next()
+        .ok_or(wasm_error!(WasmErrorInner::Guest(
+            "Order claim link is missing".to_string()
+        )))?;
+    let claim = OrderClaim {
+        order_hash: input.order_hash.clone(),
+        claimed_at: sys_time()?.as_millis() as u64,
+        shopper: Some(input.new_shopper.clone()),
+        transferred_from: Some(old_claim_hash),
+    };
+    let claim_hash = create_entry(&EntryTypes::OrderClaim(claim))?;
+    create_link(
+        input.order_hash.clone(),
+        claim_hash.clone(),
+        LinkTypes::OrderClaim,
+        (),
+    )?;
+    create_link(
+        input.new_shopper.clone(),
+        claim_hash.clone(),
+        LinkTypes::OrderClaim,
+        (),
+    )?;
+    for link in old_links {
+        delete_link(link.create_link_hash)?;
+    }
+
+    // Handoff marker: a same-status history entry naming the incoming
+    // shopper.
+    cart.status_history.push(StatusChange {
+        status: cart.status,
+        timestamp: sys_time()?.as_millis() as u64,
+        actor: input.new_shopper.clone(),
+    });
+    update_entry(newest_hash, &EntryTypes::CheckedOutCart(cart))?;
+
+    let customer = order_customer(&input.order_hash)?;
+    crate::signal::push_remote_signal(
+        TransferSignal::OrderTransferred {
+            order_hash: input.order_hash,
+            previous_shopper: current,
+            new_shopper: input.new_shopper.clone(),
+        },
+        vec![customer, input.new_shopper],
+    )?;
+    Ok(claim_hash)
+}
+
+pub(crate) fn order_access_tag(order_hash: &ActionHash) -> String {
+    format!(
+        "{}{}",
+        ORDER_ACCESS_TAG_PREFIX,
+        ActionHashB64::from(order_hash.clone())
+    )
+}
+
+fn new_cap_secret() -> ExternResult<CapSecret> {
+    let bytes: [u8; CAP_SECRET_BYTES] = random_bytes(CAP_SECRET_BYTES as u32)?
+        .into_vec()
+        .try_into()
+        .map_err(|_| {
+            wasm_error!(WasmErrorInner::Guest(
+                "Failed to generate capability secret".to_string()
+            ))
+        })?;
+    Ok(bytes.into())
+}
+
+/// The claim-time handshake: the shopper asks, the customer's cell
+/// answers with a capability secret scoped to that one order.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum OrderAccessSignal {
+    Requested { order_hash: ActionHash },
+    Granted { order_hash: ActionHash, secret: CapSecret },
+}
+
+/// Ask the customer's cell to issue the per-order capability grant.
+/// Called automatically after a claim, and available to re-run if the
+/// claim had not yet propagated to the customer when the first request
+/// arrived.
+#[hdk_extern]
+pub fn request_order_access(order_hash: ActionHash) -> ExternResult<()> {
+    let customer = order_customer(&order_hash)?;
+    crate::signal::push_remote_signal(OrderAccessSignal::Requested { order_hash }, vec![customer])
+}
+
+/// Customer-side half of the handshake: verify the requester holds the
+/// order's claim, grant them order access plus the delivery address,
+/// and send the secret back.
+pub(crate) fn grant_order_access(
+    order_hash: ActionHash,
+    shopper: AgentPubKey,
+) -> ExternResult<()> {
+    let record = get(order_hash.clone(), GetOptions::default())?.ok_or(wasm_error!(
+        WasmErrorInner::Guest("CheckedOutCart not found".to_string())
+    ))?;
+    if record.action().author() != &agent_info()?.agent_initial_pubkey {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Order access only applies to the caller's own orders".to_string()
+        )));
+    }
+    if order_claimer(&order_hash)?.as_ref() != Some(&shopper) {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Requester does not hold this order's claim".to_string()
+        )));
+    }
+    // The customer's block list gates the handshake; admin-placed
+    // claims override it.
+    crate::block::enforce_not_blocked(&order_hash, &shopper)?;
+
+    let secret = new_cap_secret()?;
+    let mut assignees = BTreeSet::new();
+    assignees.insert(shopper.clone());
+    let mut functions = BTreeSet::new();
+    functions.insert((zome_info()?.name, FunctionName::from("remote_get_order")));
+    create_cap_grant(CapGrantEntry {
+        tag: order_access_tag(&order_hash),
+        access: CapAccess::Assigned { secret, assignees },
+        functions: GrantedFunctions::Listed(functions),
+    })?;
+
+    // The delivery address rides along on the same handshake.
+    crate::checkout::grant_delivery_address(&order_hash, shopper.clone())?;
+
+    crate::signal::push_remote_signal(
+        OrderAccessSignal::Granted { order_hash, secret },
+        vec![shopper],
+    )
+}
+
+/// Shopper-side half: persist the claim so later remote calls can
+/// present the secret.
+pub(crate) fn store_order_access(
+    order_hash: ActionHash,
+    secret: CapSecret,
+    grantor: AgentPubKey,
+) -> ExternResult<()> {
+    create_cap_claim(CapClaim::new(order_access_tag(&order_hash), grantor, secret))?;
+    Ok(())
+}
+
+/// Everything a shopper needs to start shopping an order, in one call.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct RemoteOrder {
+    pub order_hash: ActionHash,
+    /// The full order: lines with notes and substitution preferences,
+    /// product snapshots, totals and status history.
+    pub order: CheckedOutCart,
+    /// The delivery address, resolved from the customer's private
+    /// address book. `None` on pickup orders.
+    pub address: Option<crate::export::ExportedAddress>,
+}
+
+/// Serves an assigned order to its shopper. Runs on the customer's
+/// cell; callable remotely only through the per-order grant issued at
+/// claim time, which the tag check pins to this exact order.
+#[hdk_extern]
+pub fn remote_get_order(order_hash: ActionHash) -> ExternResult<RemoteOrder> {
+    match call_info()?.cap_grant {
+        CapGrant::ChainAuthor(_) => {}
+        CapGrant::RemoteAgent(grant) if grant.tag == order_access_tag(&order_hash) => {}
+        _ => {
+            return Err(wasm_error!(WasmErrorInner::Guest(
+                "Order has not been shared with the caller".to_string()
+            )))
+        }
+    }
+
+    let (_, order) = crate::checkout::latest_order_revision(order_hash.clone())?;
+    let address = match &order.address_hash {
+        Some(address_hash) => crate::export::address_book()?
+            .into_iter()
+            .find(|(hash, _)| hash == address_hash)
+            .map(|(_, address)| address),
+        None if order.pseudonymous => pseudonymous_order_address(&order_hash)?,
+        None => None,
+    };
+    Ok(RemoteOrder {
+        order_hash,
+        order,
+        address,
+    })
+}
+
+/// The inline address a pseudonymous order was placed with, from the
+/// private copy on the ordering chain. Only reachable through the
+/// order-access grant, so the address never leaves the handshake.
+fn pseudonymous_order_address(
+    order_hash: &ActionHash,
+) -> ExternResult<Option<crate::export::ExportedAddress>> {
+    let records = query(
+        ChainQueryFilter::new()
+            .entry_type(UnitEntryTypes::PrivateDeliveryAddress.try_into()?)
+            .include_entries(true),
+    )?;
+    for record in records {
+        let Some(address) = record
+            .entry()
+            .to_app_option::<PrivateDeliveryAddress>()
+            .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        else {
+            continue;
+        };
+        if address.order_hash == *order_hash {
+            return Ok(Some(crate::export::ExportedAddress {
+                street: address.street,
+                unit: address.unit,
+                city: address.city,
+                state: address.state,
+                zip: address.zip,
+                label: address.label,
+            }));
+        }
+    }
+    Ok(None)
+}
+
+/// Shopper-side fetch of a claimed order through the stored capability
+/// claim. Errors until the customer's cell has answered the handshake.
+#[hdk_extern]
+pub fn fetch_assigned_order(order_hash: ActionHash) -> ExternResult<RemoteOrder> {
+    let tag = order_access_tag(&order_hash);
+    let claims = query(
+        ChainQueryFilter::new()
+            .entry_type(EntryType::CapClaim)
+            .include_entries(true),
+    )?;
+    let claim = claims
+        .iter()
+        .find_map(|record| match record.entry().as_option() {
+            Some(Entry::CapClaim(claim)) if claim.tag == tag => Some(claim.clone()),
+            _ => None,
+        })
+        .ok_or(wasm_error!(WasmErrorInner::Guest(
+            "No access grant for this order yet; re-run request_order_access".to_string()
+        )))?;
+
+    let response = call_remote(
+        claim.grantor,
+        zome_info()?.name,
+        FunctionName::from("remote_get_order"),
+        Some(claim.secret),
+        order_hash,
+    )?;
+    match response {
+        ZomeCallResponse::Ok(io) => io
+            .decode()
+            .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string()))),
+        other => Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "Remote order fetch failed: {:?}",
+            other
+        )))),
+    }
+}
+
+/// The slice of a resolved catalog product the pick list needs. Extra
+/// fields in the catalog's response are ignored on decode.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "snake_case")]
+struct ProductLocation {
+    name: String,
+    aisle: Option<String>,
+    shelf: Option<String>,
+    #[serde(default)]
+    age_restricted: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct PickListItem {
+    /// Position of this line in the order, for `mark_item_picked`.
+    pub item_index: u32,
+    pub name: String,
+    pub quantity: f64,
+    pub sold_by: Option<SoldBy>,
+    pub note: Option<String>,
+    pub substitution_preference: Option<SubstitutionPreference>,
+    pub shelf: Option<String>,
+    /// Check a photo ID before handing this over.
+    pub age_restricted: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct PickListAisle {
+    /// `None` groups the items the catalog has no layout data for;
+    /// they come last.
+    pub aisle: Option<String>,
+    pub items: Vec<PickListItem>,
+}
+
+/// Sort key putting aisle "2" before aisle "10": numeric prefix first,
+/// then the raw label for non-numeric aisles.
+pub(crate) fn aisle_key(aisle: &Option<String>) -> (u8, u64, String) {
+    match aisle {
+        Some(label) => {
+            let digits: String = label.chars().take_while(|c| c.is_ascii_digit()).collect();
+            let number = digits.parse().unwrap_or(u64::MAX);
+            (0, number, label.clone())
+        }
+        None => (1, u64::MAX, String::new()),
+    }
+}
+
+/// An order's items grouped and ordered by aisle, with quantities and
+/// notes, so shoppers walk the store once instead of zig-zagging.
+/// Layout comes from the catalog bridge; items the catalog can no
+/// longer resolve fall back to their order snapshot under no aisle.
+#[hdk_extern]
+pub fn generate_pick_list(order_hash: ActionHash) -> ExternResult<Vec<PickListAisle>> {
+    let (_, order) = crate::checkout::latest_order_revision(order_hash)?;
+
+    let references = crate::bridge::product_references(&order.products);
+    let locations: Vec<Option<ProductLocation>> =
+        crate::bridge::call_catalog("resolve_product_references", references)?;
+
+    let mut aisles: Vec<PickListAisle> = Vec::new();
+    for (position, item) in order.products.iter().enumerate() {
+        let location = locations.get(position).cloned().flatten();
+        let snapshot = order.product_snapshots.get(position);
+        let pick = PickListItem {
+            item_index: position as u32,
+            name: location
+                .as_ref()
+                .map(|l| l.name.clone())
+                .or_else(|| snapshot.map(|s| s.name.clone()))
+                .unwrap_or_default(),
+            quantity: item.quantity,
+            sold_by: item.sold_by,
+            note: item.note.clone(),
+            substitution_preference: item.substitution_preference.clone(),
+            shelf: location.as_ref().and_then(|l| l.shelf.clone()),
+            age_restricted: location
+                .as_ref()
+                .map(|l| l.age_restricted)
+                .or_else(|| snapshot.map(|s| s.age_restricted))
+                .unwrap_or(false),
+        };
+        let aisle = location.and_then(|l| l.aisle);
+        match aisles.iter_mut().find(|group| group.aisle == aisle) {
+            Some(group) => group.items.push(pick),
+            None => aisles.push(PickListAisle {
+                aisle,
+                items: vec![pick],
+            }),
+        }
+    }
+    aisles.sort_by_key(|group| aisle_key(&group.aisle));
+    Ok(aisles)
+}
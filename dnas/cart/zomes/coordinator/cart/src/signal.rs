@@ -0,0 +1,22 @@
+//! Sender-side remote-signal helper. Remote signals are fire-and-
+//! forget, but the host call itself can fail transiently; every sender
+//! in this zome goes through here so each push gets the same few
+//! retries instead of silently dropping a chat message or status
+//! update. The receiving side is `recv_remote_signal` in `lib.rs`,
+//! under the init-time "remote-signals" cap grant.
+
+use hdk::prelude::*;
+use summon_types::with_retries;
+
+/// How many times one signal send is attempted before giving up.
+const SEND_ATTEMPTS: usize = 3;
+
+/// Push a remote signal to `recipients`, retrying transient failures.
+pub(crate) fn push_remote_signal<S>(signal: S, recipients: Vec<AgentPubKey>) -> ExternResult<()>
+where
+    S: Serialize + std::fmt::Debug,
+{
+    with_retries(SEND_ATTEMPTS, || {
+        send_remote_signal(&signal, recipients.clone())
+    })
+}
@@ -0,0 +1,337 @@
+use cart_integrity::*;
+use hdk::prelude::*;
+
+use crate::checkout::latest_order_revision;
+use crate::shopper::order_claimer;
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct GetShopperStatsInput {
+    pub shopper: AgentPubKey,
+    /// Only count claims made at or after this time (millis). Open
+    /// start when absent.
+    #[serde(default)]
+    pub since: Option<u64>,
+    /// Only count claims made before this time (millis). Open end when
+    /// absent.
+    #[serde(default)]
+    pub until: Option<u64>,
+}
+
+/// Aggregate fulfillment performance for one shopper over a period.
+/// Rates are `None` until at least one order qualifies for their
+/// denominator; the rating is the lifetime profile average, since
+/// ratings land well after the orders they describe.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct ShopperStats {
+    /// Orders the shopper claimed in the period and still holds (or
+    /// held through to a terminal status).
+    pub orders_claimed: u32,
+    pub orders_completed: u32,
+    pub orders_cancelled: u32,
+    /// Share of completed orders with a delivery deadline that were
+    /// delivered before it.
+    pub on_time_rate: Option<f64>,
+    /// Share of claimed orders where the shopper proposed at least one
+    /// substitution.
+    pub substitution_rate: Option<f64>,
+    pub average_rating: Option<f64>,
+    pub rating_count: u32,
+}
+
+/// When a completed order was delivered: the newest `Completed` entry
+/// in its status history.
+fn completed_at(cart: &CheckedOutCart) -> Option<u64> {
+    cart.status_history
+        .iter()
+        .rev()
+        .find(|change| change.status == OrderStatus::Completed)
+        .map(|change| change.timestamp)
+}
+
+/// The order's delivery deadline in millis, if it was placed against a
+/// timed slot.
+fn delivery_deadline(cart: &CheckedOutCart) -> Option<u64> {
+    let slot = cart.delivery_time.as_ref()?;
+    let end_minute = slot.end_minute?;
+    Some(slot.date + end_minute as u64 * 60 * 1000)
+}
+
+/// Performance statistics for a shopper's dashboard and admin review.
+/// Counts the orders indexed from the shopper's key, skipping any whose
+/// claim has since moved to another shopper.
+#[hdk_extern]
+pub fn get_shopper_stats(input: GetShopperStatsInput) -> ExternResult<ShopperStats> {
+    let agent = agent_info()?.agent_initial_pubkey;
+    let admins = crate::checkout::dna_properties()?.admins;
+    if input.shopper != agent && !admins.is_empty() && !admins.contains(&agent) {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Only the shopper themselves or an admin may view shopper stats".to_string()
+        )));
+    }
+
+    let links = get_links(
+        GetLinksInputBuilder::try_new(input.shopper.clone(), LinkTypes::OrderClaim)?.build(),
+    )?;
+    let mut order_hashes: Vec<ActionHash> = Vec::new();
+    for link in links {
+        let Some(claim_hash) = link.target.into_action_hash() else {
+            continue;
+        };
+        let Some(record) = get(claim_hash, GetOptions::default())? else {
+            continue;
+        };
+        let Some(claim) = record
+            .entry()
+            .to_app_option::<OrderClaim>()
+            .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        else {
+            continue;
+        };
+        if input.since.is_some_and(|since| claim.claimed_at < since)
+            || input.until.is_some_and(|until| claim.claimed_at >= until)
+        {
+            continue;
+        }
+        if !order_hashes.contains(&claim.order_hash) {
+            order_hashes.push(claim.order_hash);
+        }
+    }
+
+    let mut stats = ShopperStats {
+        orders_claimed: 0,
+        orders_completed: 0,
+        orders_cancelled: 0,
+        on_time_rate: None,
+        substitution_rate: None,
+        average_rating: None,
+        rating_count: 0,
+    };
+    let mut with_deadline = 0u32;
+    let mut on_time = 0u32;
+    let mut with_substitutions = 0u32;
+    for order_hash in order_hashes {
+        // Skip orders whose claim has moved on: they count toward
+        // whoever holds them now.
+        if order_claimer(&order_hash)?.as_ref() != Some(&input.shopper) {
+            continue;
+        }
+        let (_, cart) = latest_order_revision(order_hash.clone())?;
+        stats.orders_claimed += 1;
+        match cart.status {
+            OrderStatus::Completed => {
+                stats.orders_completed += 1;
+                if let (Some(delivered), Some(deadline)) =
+                    (completed_at(&cart), delivery_deadline(&cart))
+                {
+                    with_deadline += 1;
+                    if delivered <= deadline {
+                        on_time += 1;
+                    }
+                }
+            }
+            OrderStatus::Cancelled => stats.orders_cancelled += 1,
+            _ => {}
+        }
+        let proposals = get_links(
+            GetLinksInputBuilder::try_new(order_hash, LinkTypes::SubstitutionProposal)?.build(),
+        )?;
+        if !proposals.is_empty() {
+            with_substitutions += 1;
+        }
+    }
+
+    if with_deadline > 0 {
+        stats.on_time_rate = Some(on_time as f64 / with_deadline as f64);
+    }
+    if stats.orders_claimed > 0 {
+        stats.substitution_rate = Some(with_substitutions as f64 / stats.orders_claimed as f64);
+    }
+    if let Some(profile) = crate::shopper::get_shopper_profile(input.shopper)? {
+        stats.average_rating = profile.rating.average;
+        stats.rating_count = profile.rating.count;
+    }
+    Ok(stats)
+}
+
+/// When an order was claimed: the claim entry's timestamp.
+fn claimed_at(order_hash: &ActionHash) -> ExternResult<Option<u64>> {
+    let links = get_links(
+        GetLinksInputBuilder::try_new(order_hash.clone(), LinkTypes::OrderClaim)?.build(),
+    )?;
+    for link in links {
+        let Some(claim_hash) = link.target.into_action_hash() else {
+            continue;
+        };
+        let Some(record) = get(claim_hash, GetOptions::default())? else {
+            continue;
+        };
+        if let Some(claim) = record
+            .entry()
+            .to_app_option::<OrderClaim>()
+            .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        {
+            return Ok(Some(claim.claimed_at));
+        }
+    }
+    Ok(None)
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct GetFulfillmentMetricsInput {
+    /// Only count orders placed at or after this time (millis).
+    #[serde(default)]
+    pub since: Option<u64>,
+    /// Only count orders placed before this time (millis).
+    #[serde(default)]
+    pub until: Option<u64>,
+}
+
+/// Running totals for one slice of orders, shared between the overall
+/// figures and the per-zone rows.
+#[derive(Default)]
+struct SlaAccumulator {
+    orders: u32,
+    completed: u32,
+    cancelled: u32,
+    claim_to_delivery_total_ms: u64,
+    claim_to_delivery_count: u32,
+    with_deadline: u32,
+    late: u32,
+}
+
+impl SlaAccumulator {
+    fn record(&mut self, cart: &CheckedOutCart, order_hash: &ActionHash) -> ExternResult<()> {
+        self.orders += 1;
+        match cart.status {
+            OrderStatus::Completed => {
+                self.completed += 1;
+                if let Some(delivered) = completed_at(cart) {
+                    if let Some(claimed) = claimed_at(order_hash)? {
+                        self.claim_to_delivery_total_ms +=
+                            delivered.saturating_sub(claimed);
+                        self.claim_to_delivery_count += 1;
+                    }
+                    if let Some(deadline) = delivery_deadline(cart) {
+                        self.with_deadline += 1;
+                        if delivered > deadline {
+                            self.late += 1;
+                        }
+                    }
+                }
+            }
+            OrderStatus::Cancelled => self.cancelled += 1,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn average_claim_to_delivery_ms(&self) -> Option<u64> {
+        if self.claim_to_delivery_count == 0 {
+            None
+        } else {
+            Some(self.claim_to_delivery_total_ms / self.claim_to_delivery_count as u64)
+        }
+    }
+
+    fn late_rate(&self) -> Option<f64> {
+        if self.with_deadline == 0 {
+            None
+        } else {
+            Some(self.late as f64 / self.with_deadline as f64)
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct ZoneMetrics {
+    /// `None` groups orders placed without a delivery zone (pickups and
+    /// pre-zone orders).
+    pub zone: Option<String>,
+    pub orders: u32,
+    pub completed: u32,
+    pub cancelled: u32,
+    pub average_claim_to_delivery_ms: Option<u64>,
+    pub late_rate: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct FulfillmentMetrics {
+    pub orders: u32,
+    pub completed: u32,
+    pub cancelled: u32,
+    /// Mean time from claim to delivery across completed orders with a
+    /// claim on record.
+    pub average_claim_to_delivery_ms: Option<u64>,
+    /// Share of completed orders with a timed slot that were delivered
+    /// after the window closed.
+    pub late_rate: Option<f64>,
+    pub zones: Vec<ZoneMetrics>,
+}
+
+/// Network-wide fulfillment SLA figures over a period, computed from
+/// the status-history timestamps every order carries. Admin-gated;
+/// walks the order log anchor.
+#[hdk_extern]
+pub fn get_fulfillment_metrics(
+    input: GetFulfillmentMetricsInput,
+) -> ExternResult<FulfillmentMetrics> {
+    let admins = crate::checkout::dna_properties()?.admins;
+    if !admins.is_empty() && !admins.contains(&agent_info()?.agent_initial_pubkey) {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Only admin agents may view fulfillment metrics".to_string()
+        )));
+    }
+
+    let anchor = crate::checkout::order_log_anchor()?;
+    let links = get_links(
+        GetLinksInputBuilder::try_new(anchor.path_entry_hash()?, LinkTypes::OrderLog)?.build(),
+    )?;
+
+    let mut overall = SlaAccumulator::default();
+    let mut zones: Vec<(Option<String>, SlaAccumulator)> = Vec::new();
+    for link in links {
+        let Some(order_hash) = link.target.into_action_hash() else {
+            continue;
+        };
+        let (_, cart) = latest_order_revision(order_hash.clone())?;
+        if input.since.is_some_and(|since| cart.created_at < since)
+            || input.until.is_some_and(|until| cart.created_at >= until)
+        {
+            continue;
+        }
+        overall.record(&cart, &order_hash)?;
+        let zone = match zones.iter_mut().find(|(zone, _)| *zone == cart.delivery_zone) {
+            Some((_, accumulator)) => accumulator,
+            None => {
+                zones.push((cart.delivery_zone.clone(), SlaAccumulator::default()));
+                &mut zones.last_mut().expect("just pushed").1
+            }
+        };
+        zone.record(&cart, &order_hash)?;
+    }
+
+    zones.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(FulfillmentMetrics {
+        orders: overall.orders,
+        completed: overall.completed,
+        cancelled: overall.cancelled,
+        average_claim_to_delivery_ms: overall.average_claim_to_delivery_ms(),
+        late_rate: overall.late_rate(),
+        zones: zones
+            .into_iter()
+            .map(|(zone, accumulator)| ZoneMetrics {
+                zone,
+                orders: accumulator.orders,
+                completed: accumulator.completed,
+                cancelled: accumulator.cancelled,
+                average_claim_to_delivery_ms: accumulator.average_claim_to_delivery_ms(),
+                late_rate: accumulator.late_rate(),
+            })
+            .collect(),
+    })
+}
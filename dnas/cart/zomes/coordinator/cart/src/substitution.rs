@@ -0,0 +1,315 @@
+use cart_integrity::*;
+use hdk::prelude::*;
+
+use crate::checkout::latest_order_revision;
+
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct ProposeSubstitutionInput {
+    #[serde(alias = "orderHash")]
+    pub order_hash: ActionHash,
+    #[serde(alias = "groupHash")]
+    pub group_hash: ActionHash,
+    #[serde(alias = "productIndex")]
+    pub product_index: u32,
+    #[serde(alias = "proposedGroupHash")]
+    pub proposed_group_hash: ActionHash,
+    #[serde(alias = "proposedProductIndex")]
+    pub proposed_product_index: u32,
+    pub note: Option<String>,
+}
+
+/// Pushed to the customer when a shopper proposes a substitution, and
+/// back to the shopper when the customer answers.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum SubstitutionSignal {
+    Proposed {
+        proposal_hash: ActionHash,
+        proposal: SubstitutionProposal,
+    },
+    Responded {
+        proposal_hash: ActionHash,
+        approved: bool,
+        /// The response window closed and the stored preference
+        /// answered, not the customer.
+        #[serde(default)]
+        auto: bool,
+    },
+}
+
+/// The customer who placed an order: the author of its create action.
+fn order_customer(order_hash: &ActionHash) -> ExternResult<AgentPubKey> {
+    let record = get(order_hash.clone(), GetOptions::default())?.ok_or(wasm_error!(
+        WasmErrorInner::Guest("CheckedOutCart not found".to_string())
+    ))?;
+    Ok(record.action().author().clone())
+}
+
+/// Record a proposed substitution against an order and push it to the
+/// customer, instead of negotiating off-platform.
+#[hdk_extern]
+pub fn propose_substitution(input: ProposeSubstitutionInput) -> ExternResult<ActionHash> {
+    let (_, order) = latest_order_revision(input.order_hash.clone())?;
+    if order.status != OrderStatus::Shopping {
+        return Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "Substitutions can only be proposed while shopping (status {:?})",
+            order.status
+        ))));
+    }
+    if !order.products.iter().any(|item| {
+        item.group_hash == input.group_hash && item.product_index == input.product_index
+    }) {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Order has no such line".to_string()
+        )));
+    }
+
+    let now = sys_time()?.as_millis() as u64;
+    let proposal = SubstitutionProposal {
+        order_hash: input.order_hash.clone(),
+        group_hash: input.group_hash,
+        product_index: input.product_index,
+        proposed_group_hash: input.proposed_group_hash,
+        proposed_product_index: input.proposed_product_index,
+        note: input.note,
+        proposed_at: now,
+        expires_at: now
+            + crate::checkout::dna_properties()?
+                .substitutions
+                .response_window_minutes
+                * 60
+                * 1000,
+    };
+    let proposal_hash = create_entry(&EntryTypes::SubstitutionProposal(proposal.clone()))?;
+    create_link(
+        input.order_hash.clone(),
+        proposal_hash.clone(),
+        LinkTypes::SubstitutionProposal,
+        (),
+    )?;
+
+    let customer = order_customer(&input.order_hash)?;
+    crate::signal::push_remote_signal(
+        SubstitutionSignal::Proposed {
+            proposal_hash: proposal_hash.clone(),
+            proposal,
+        },
+        vec![customer],
+    )?;
+    Ok(proposal_hash)
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct RespondToSubstitutionInput {
+    #[serde(alias = "proposalHash")]
+    pub proposal_hash: ActionHash,
+    pub approve: bool,
+}
+
+fn existing_response(proposal_hash: &ActionHash) -> ExternResult<Option<SubstitutionResponse>> {
+    let links = get_links(
+        GetLinksInputBuilder::try_new(proposal_hash.clone(), LinkTypes::SubstitutionResponse)?
+            .build(),
+    )?;
+    for link in links {
+        let Some(hash) = link.target.into_action_hash() else {
+            continue;
+        };
+        let Some(record) = get(hash, GetOptions::default())? else {
+            continue;
+        };
+        if let Some(response) = record
+            .entry()
+            .to_app_option::<SubstitutionResponse>()
+            .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        {
+            return Ok(Some(response));
+        }
+    }
+    Ok(None)
+}
+
+/// Approve or reject a proposed substitution. Only the customer may
+/// answer, and only inside the response window.
+#[hdk_extern]
+pub fn respond_to_substitution(input: RespondToSubstitutionInput) -> ExternResult<ActionHash> {
+    let record = get(input.proposal_hash.clone(), GetOptions::default())?.ok_or(wasm_error!(
+        WasmErrorInner::Guest("SubstitutionProposal not found".to_string())
+    ))?;
+    let shopper = record.action().author().clone();
+    let proposal: SubstitutionProposal = record
+        .entry()
+        .to_app_option()
+        .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        .ok_or(wasm_error!(WasmErrorInner::Guest(
+            "Record is not a SubstitutionProposal".to_string()
+        )))?;
+
+    let agent = agent_info()?.agent_initial_pubkey;
+    if order_customer(&proposal.order_hash)? != agent {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Only the customer may respond to a substitution".to_string()
+        )));
+    }
+    let now = sys_time()?.as_millis() as u64;
+    if now > proposal.expires_at {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Response window has closed; the stored substitution preference applies".to_string()
+        )));
+    }
+    if existing_response(&input.proposal_hash)?.is_some() {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Substitution has already been answered".to_string()
+        )));
+    }
+
+    let response = SubstitutionResponse {
+        proposal_hash: input.proposal_hash.clone(),
+        approved: input.approve,
+        responded_at: now,
+        auto: false,
+    };
+    let response_hash = create_entry(&EntryTypes::SubstitutionResponse(response))?;
+    create_link(
+        input.proposal_hash.clone(),
+        response_hash.clone(),
+        LinkTypes::SubstitutionResponse,
+        (),
+    )?;
+
+    crate::signal::push_remote_signal(
+        SubstitutionSignal::Responded {
+            proposal_hash: input.proposal_hash,
+            approved: input.approve,
+            auto: false,
+        },
+        vec![shopper],
+    )?;
+    Ok(response_hash)
+}
+
+/// What the line's stored preference says about a concrete proposal:
+/// shopper's choice approves it, a matching specific item approves it,
+/// and everything else (no-sub, refund, no preference) rejects it.
+fn preference_answer(order: &CheckedOutCart, proposal: &SubstitutionProposal) -> bool {
+    let preference = order
+        .products
+        .iter()
+        .find(|item| {
+            item.group_hash == proposal.group_hash
+                && item.product_index == proposal.product_index
+        })
+        .and_then(|item| item.substitution_preference.clone());
+    match preference {
+        Some(SubstitutionPreference::ShopperChoice) => true,
+        Some(SubstitutionPreference::SpecificItem {
+            group_hash,
+            product_index,
+        }) => {
+            group_hash == proposal.proposed_group_hash
+                && product_index == proposal.proposed_product_index
+        }
+        _ => false,
+    }
+}
+
+/// Answer any of the caller's own proposals whose response window has
+/// closed, using the line's stored substitution preference, and notify
+/// both parties. Scheduled through [`crate::scheduler`] on the
+/// shopper's cell; windows are measured in minutes, so it runs every
+/// tick.
+pub(crate) fn resolve_expired_substitutions() -> ExternResult<()> {
+    let now = sys_time()?.as_millis() as u64;
+    let records = query(
+        ChainQueryFilter::new()
+            .entry_type(UnitEntryTypes::SubstitutionProposal.try_into()?)
+            .include_entries(true),
+    )?;
+    for record in records {
+        let Some(proposal) = record
+            .entry()
+            .to_app_option::<SubstitutionProposal>()
+            .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        else {
+            continue;
+        };
+        if now <= proposal.expires_at {
+            continue;
+        }
+        let proposal_hash = record.action_address().clone();
+        if existing_response(&proposal_hash)?.is_some() {
+            continue;
+        }
+
+        let (_, order) = latest_order_revision(proposal.order_hash.clone())?;
+        let approved = preference_answer(&order, &proposal);
+        let response = SubstitutionResponse {
+            proposal_hash: proposal_hash.clone(),
+            approved,
+            responded_at: now,
+            auto: true,
+        };
+        let response_hash = create_entry(&EntryTypes::SubstitutionResponse(response))?;
+        create_link(
+            proposal_hash.clone(),
+            response_hash,
+            LinkTypes::SubstitutionResponse,
+            (),
+        )?;
+
+        let signal = SubstitutionSignal::Responded {
+            proposal_hash,
+            approved,
+            auto: true,
+        };
+        // The customer learns their silence was taken as an answer;
+        // the shopper's own UI hears it locally.
+        crate::signal::push_remote_signal(&signal, vec![order_customer(&proposal.order_hash)?])?;
+        emit_signal(signal)?;
+    }
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct ProposalWithResponse {
+    pub proposal_hash: ActionHash,
+    pub proposal: SubstitutionProposal,
+    pub response: Option<SubstitutionResponse>,
+}
+
+#[hdk_extern]
+pub fn get_substitution_proposals(
+    order_hash: ActionHash,
+) -> ExternResult<Vec<ProposalWithResponse>> {
+    let links = get_links(
+        GetLinksInputBuilder::try_new(order_hash, LinkTypes::SubstitutionProposal)?.build(),
+    )?;
+
+    let mut proposals = Vec::new();
+    for link in links {
+        let Some(hash) = link.target.into_action_hash() else {
+            continue;
+        };
+        let Some(record) = get(hash.clone(), GetOptions::default())? else {
+            continue;
+        };
+        if let Some(proposal) = record
+            .entry()
+            .to_app_option::<SubstitutionProposal>()
+            .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        {
+            let response = existing_response(&hash)?;
+            proposals.push(ProposalWithResponse {
+                proposal_hash: hash,
+                proposal,
+                response,
+            });
+        }
+    }
+    proposals.sort_by_key(|entry| entry.proposal.proposed_at);
+    Ok(proposals)
+}
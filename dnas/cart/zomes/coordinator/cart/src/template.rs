@@ -0,0 +1,152 @@
+use cart_integrity::*;
+use hdk::prelude::*;
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct CreateTemplateInput {
+    pub name: String,
+    pub items: Vec<CartProduct>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct UpdateTemplateInput {
+    #[serde(alias = "templateHash")]
+    pub template_hash: ActionHash,
+    pub name: String,
+    pub items: Vec<CartProduct>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct TemplateWithHash {
+    pub template_hash: ActionHash,
+    pub template: ShoppingListTemplate,
+}
+
+#[hdk_extern]
+pub fn create_shopping_list_template(input: CreateTemplateInput) -> ExternResult<ActionHash> {
+    let agent = agent_info()?.agent_initial_pubkey;
+    let template = ShoppingListTemplate {
+        name: input.name,
+        items: input.items,
+        created_at: sys_time()?.as_millis() as u64,
+    };
+    let hash = create_entry(&EntryTypes::ShoppingListTemplate(template))?;
+    create_link(agent, hash.clone(), LinkTypes::ShoppingListTemplate, ())?;
+    Ok(hash)
+}
+
+#[hdk_extern]
+pub fn get_shopping_list_templates(_: ()) -> ExternResult<Vec<TemplateWithHash>> {
+    let agent = agent_info()?.agent_initial_pubkey;
+    let links = get_links(
+        GetLinksInputBuilder::try_new(agent, LinkTypes::ShoppingListTemplate)?.build(),
+    )?;
+
+    let mut templates = Vec::new();
+    for link in links {
+        let Some(hash) = link.target.into_action_hash() else {
+            continue;
+        };
+        let Some(record) = get(hash.clone(), GetOptions::default())? else {
+            continue;
+        };
+        if let Some(template) = record
+            .entry()
+            .to_app_option::<ShoppingListTemplate>()
+            .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        {
+            templates.push(TemplateWithHash {
+                template_hash: hash,
+                template,
+            });
+        }
+    }
+    templates.sort_by_key(|entry| std::cmp::Reverse(entry.template.created_at));
+    Ok(templates)
+}
+
+#[hdk_extern]
+pub fn update_shopping_list_template(input: UpdateTemplateInput) -> ExternResult<ActionHash> {
+    let agent = agent_info()?.agent_initial_pubkey;
+    let record = get(input.template_hash.clone(), GetOptions::default())?.ok_or(wasm_error!(
+        WasmErrorInner::Guest("ShoppingListTemplate not found".to_string())
+    ))?;
+    let previous: ShoppingListTemplate = record
+        .entry()
+        .to_app_option()
+        .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        .ok_or(wasm_error!(WasmErrorInner::Guest(
+            "Record is not a ShoppingListTemplate".to_string()
+        )))?;
+
+    let template = ShoppingListTemplate {
+        name: input.name,
+        items: input.items,
+        created_at: previous.created_at,
+    };
+    let new_hash = update_entry(
+        input.template_hash.clone(),
+        &EntryTypes::ShoppingListTemplate(template),
+    )?;
+
+    let links = get_links(
+        GetLinksInputBuilder::try_new(agent.clone(), LinkTypes::ShoppingListTemplate)?.build(),
+    )?;
+    for link in links {
+        if link.target.clone().into_action_hash() == Some(input.template_hash.clone()) {
+            delete_link(link.create_link_hash)?;
+        }
+    }
+    create_link(agent, new_hash.clone(), LinkTypes::ShoppingListTemplate, ())?;
+    Ok(new_hash)
+}
+
+#[hdk_extern]
+pub fn delete_shopping_list_template(template_hash: ActionHash) -> ExternResult<ActionHash> {
+    let agent = agent_info()?.agent_initial_pubkey;
+    let links = get_links(
+        GetLinksInputBuilder::try_new(agent, LinkTypes::ShoppingListTemplate)?.build(),
+    )?;
+    for link in links {
+        if link.target.clone().into_action_hash() == Some(template_hash.clone()) {
+            delete_link(link.create_link_hash)?;
+        }
+    }
+    delete_entry(template_hash)
+}
+
+/// Merge a template's items into the current private cart. Lines already
+/// in the cart keep their existing quantity; only missing lines are
+/// added.
+#[hdk_extern]
+pub fn instantiate_template_into_cart(template_hash: ActionHash) -> ExternResult<ActionHash> {
+    let record = get(template_hash, GetOptions::default())?.ok_or(wasm_error!(
+        WasmErrorInner::Guest("ShoppingListTemplate not found".to_string())
+    ))?;
+    let template: ShoppingListTemplate = record
+        .entry()
+        .to_app_option()
+        .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        .ok_or(wasm_error!(WasmErrorInner::Guest(
+            "Record is not a ShoppingListTemplate".to_string()
+        )))?;
+
+    let now = sys_time()?.as_millis() as u64;
+    let mut cart = crate::cart::get_private_cart_impl()?;
+    for item in template.items {
+        let exists = cart.items.iter().any(|existing| {
+            existing.group_hash == item.group_hash
+                && existing.product_index == item.product_index
+        });
+        if !exists {
+            cart.items.push(CartProduct {
+                timestamp: now,
+                ..item
+            });
+        }
+    }
+    cart.last_updated = now;
+    crate::cart::write_private_cart(cart)
+}
@@ -0,0 +1,134 @@
+use cart_integrity::*;
+use hdk::prelude::*;
+
+use crate::checkout::latest_order_revision;
+
+/// Live tracking traffic for an order in transit. Locations are relayed
+/// as remote signals only and never written to anyone's chain.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum TrackingSignal {
+    /// Where the fulfiller currently is, pushed to the customer.
+    Location {
+        order_hash: ActionHash,
+        lat: f64,
+        lng: f64,
+        sent_at: u64,
+    },
+    /// The customer is watching; pushed to the fulfiller so their
+    /// device starts streaming locations.
+    Subscribe { order_hash: ActionHash },
+    /// Shopping progress, pushed to the customer as each line is
+    /// picked ("7 of 23 items").
+    ItemPicked {
+        order_hash: ActionHash,
+        item_index: u32,
+        picked_count: u32,
+        item_count: u32,
+    },
+}
+
+/// The agent who moved an order into Delivering — the one actually
+/// carrying it.
+fn order_fulfiller(cart: &CheckedOutCart) -> ExternResult<AgentPubKey> {
+    cart.status_history
+        .iter()
+        .rev()
+        .find(|change| change.status == OrderStatus::Delivering)
+        .map(|change| change.actor.clone())
+        .ok_or(wasm_error!(WasmErrorInner::Guest(
+            "Order has not entered delivery".to_string()
+        )))
+}
+
+/// The customer who placed an order: the author of its create action.
+pub(crate) fn order_customer(order_hash: &ActionHash) -> ExternResult<AgentPubKey> {
+    let record = get(order_hash.clone(), GetOptions::default())?.ok_or(wasm_error!(
+        WasmErrorInner::Guest("CheckedOutCart not found".to_string())
+    ))?;
+    Ok(record.action().author().clone())
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct SendDeliveryLocationInput {
+    #[serde(alias = "orderHash")]
+    pub order_hash: ActionHash,
+    pub lat: f64,
+    pub lng: f64,
+}
+
+/// Push the fulfiller's current position to the customer, honouring
+/// the shopper's own location-sharing setting: never blocks outright,
+/// only-delivering (the default) limits it to in-transit orders, and
+/// always extends it back to the shopping phase. Nothing is persisted.
+#[hdk_extern]
+pub fn send_delivery_location(input: SendDeliveryLocationInput) -> ExternResult<()> {
+    let agent = agent_info()?.agent_initial_pubkey;
+    let sharing = crate::shopper::own_shopper_profile()?
+        .map(|(_, profile)| profile.location_sharing)
+        .unwrap_or_default();
+    if sharing == LocationSharing::Never {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Location sharing is turned off in this shopper's settings".to_string()
+        )));
+    }
+
+    let (_, cart) = latest_order_revision(input.order_hash.clone())?;
+    match cart.status {
+        OrderStatus::Delivering => {
+            if order_fulfiller(&cart)? != agent {
+                return Err(wasm_error!(WasmErrorInner::Guest(
+                    "Only the delivering agent may send locations".to_string()
+                )));
+            }
+        }
+        OrderStatus::Shopping if sharing == LocationSharing::Always => {
+            if crate::shopper::order_claimer(&input.order_hash)?.as_ref() != Some(&agent) {
+                return Err(wasm_error!(WasmErrorInner::Guest(
+                    "Only the assigned shopper may send locations".to_string()
+                )));
+            }
+        }
+        _ => {
+            return Err(wasm_error!(WasmErrorInner::Guest(format!(
+                "Order is not in transit (status {:?})",
+                cart.status
+            ))));
+        }
+    }
+
+    let customer = order_customer(&input.order_hash)?;
+    crate::signal::push_remote_signal(
+        TrackingSignal::Location {
+            order_hash: input.order_hash,
+            lat: input.lat,
+            lng: input.lng,
+            sent_at: sys_time()?.as_millis() as u64,
+        },
+        vec![customer],
+    )
+}
+
+/// Tell the fulfiller's device the customer is watching the map, so it
+/// starts calling [`send_delivery_location`]. Customer-only. Returns
+/// `false` without signalling when the fulfiller's settings say never,
+/// so the UI falls back to status-only updates.
+#[hdk_extern]
+pub fn subscribe_to_delivery(order_hash: ActionHash) -> ExternResult<bool> {
+    if order_customer(&order_hash)? != agent_info()?.agent_initial_pubkey {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Only the customer may subscribe to their delivery".to_string()
+        )));
+    }
+    let (_, cart) = latest_order_revision(order_hash.clone())?;
+    let fulfiller = order_fulfiller(&cart)?;
+    let sharing = crate::shopper::get_shopper_profile(fulfiller.clone())?
+        .map(|entry| entry.profile.location_sharing)
+        .unwrap_or_default();
+    if sharing == LocationSharing::Never {
+        return Ok(false);
+    }
+    crate::signal::push_remote_signal(TrackingSignal::Subscribe { order_hash }, vec![fulfiller])?;
+    Ok(true)
+}
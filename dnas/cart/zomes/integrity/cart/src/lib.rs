@@ -0,0 +1,2507 @@
+use hdi::prelude::*;
+
+/// What the shopper should do when a cart line is unavailable, instead
+/// of guessing from free-text notes.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SubstitutionPreference {
+    /// Never substitute; skip the item.
+    NoSub,
+    /// The shopper picks the closest match.
+    ShopperChoice,
+    /// Substitute with this exact catalog product.
+    SpecificItem {
+        group_hash: ActionHash,
+        product_index: u32,
+    },
+    /// Refund the line instead of substituting.
+    Refund,
+}
+
+/// How a product is sold, mirrored from the catalog onto the cart line
+/// so quantity rules can be enforced without a bridged call.
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[hdk_entry_helper]
+#[serde(rename_all = "snake_case")]
+pub enum SoldBy {
+    Each,
+    Weight,
+}
+
+/// No single line may ask for more than this, in units or pounds.
+pub const MAX_LINE_QUANTITY: f64 = 100.0;
+/// Weight-based quantities must land on this increment.
+pub const WEIGHT_STEP: f64 = 0.25;
+/// Slack for comparing client-supplied quantities, which are f64.
+const QUANTITY_EPSILON: f64 = 1e-6;
+
+/// Quantity rules per unit of sale. Lines written before units were
+/// recorded (`None`) only get the positive/maximum checks.
+pub fn validate_quantity(quantity: f64, sold_by: Option<SoldBy>) -> Result<(), String> {
+    if quantity <= 0.0 {
+        return Err("Quantity must be positive".to_string());
+    }
+    if quantity > MAX_LINE_QUANTITY {
+        return Err(format!(
+            "Quantity {} exceeds the per-line maximum of {}",
+            quantity, MAX_LINE_QUANTITY
+        ));
+    }
+    match sold_by {
+        Some(SoldBy::Each) if (quantity - quantity.round()).abs() > QUANTITY_EPSILON => {
+            return Err(format!(
+                "Items sold by unit need whole quantities, got {}",
+                quantity
+            ));
+        }
+        Some(SoldBy::Weight) => {
+            let steps = quantity / WEIGHT_STEP;
+            if (steps - steps.round()).abs() > QUANTITY_EPSILON {
+                return Err(format!(
+                    "Weight quantities must be in steps of {} lb, got {}",
+                    WEIGHT_STEP, quantity
+                ));
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// A single product line in a cart. Products are referenced by the
+/// ProductGroup entry that contains them in the catalog DNA plus the
+/// index of the product inside that group.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct CartProduct {
+    pub group_hash: ActionHash,
+    pub product_index: u32,
+    /// Quantity is f64 so weight-based items ("1.25 lb") work the same
+    /// as unit-based items.
+    pub quantity: f64,
+    /// Client timestamp (ms) of the last change to this line.
+    pub timestamp: u64,
+    pub note: Option<String>,
+    #[serde(default)]
+    pub substitution_preference: Option<SubstitutionPreference>,
+    /// Unit of sale, used to validate the quantity. Absent on lines
+    /// written before units were recorded.
+    #[serde(default)]
+    pub sold_by: Option<SoldBy>,
+}
+
+/// The agent's current shopping cart. Stored as a private entry and
+/// re-created wholesale on every change, with a link from the agent's
+/// key to the newest revision.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct PrivateCart {
+    pub items: Vec<CartProduct>,
+    pub last_updated: u64,
+    /// Promo code applied to this cart, carried into checkout.
+    #[serde(default)]
+    pub promo_code_hash: Option<ActionHash>,
+}
+
+/// A private cart set aside after going idle too long, so stale prices
+/// and quantities are never silently checked out. The customer can
+/// restore it from their saved list.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct SavedCart {
+    pub items: Vec<CartProduct>,
+    pub saved_at: u64,
+}
+
+/// A single change to the private cart. Recording deltas instead of
+/// re-serializing the whole cart keeps quantity tweaks from bloating
+/// the source chain.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+#[serde(tag = "type")]
+pub enum CartDeltaOp {
+    Add(CartProduct),
+    Update {
+        group_hash: ActionHash,
+        product_index: u32,
+        quantity: f64,
+        note: Option<String>,
+        #[serde(default)]
+        substitution_preference: Option<SubstitutionPreference>,
+    },
+    Remove {
+        group_hash: ActionHash,
+        product_index: u32,
+    },
+}
+
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct CartDelta {
+    pub op: CartDeltaOp,
+    pub timestamp: u64,
+}
+
+/// Delivery window chosen at checkout.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct DeliveryTimeSlot {
+    /// Unix timestamp (ms) for the start of the delivery day.
+    pub date: u64,
+    /// Display window, e.g. "2pm-4pm".
+    pub time_slot: String,
+    /// Window start as minutes from midnight of `date`. Absent on
+    /// slots from before structured windows were recorded.
+    #[serde(default)]
+    pub start_minute: Option<u32>,
+    /// Window end as minutes from midnight of `date`.
+    #[serde(default)]
+    pub end_minute: Option<u32>,
+}
+
+const MINUTES_PER_DAY: u32 = 24 * 60;
+const MS_PER_MINUTE: u64 = 60 * 1000;
+
+/// Hours a store fulfills orders in, minutes from midnight. The default
+/// is around the clock, for networks that don't configure hours.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct StoreHoursConfig {
+    #[serde(default)]
+    pub open_minute: u32,
+    #[serde(default = "StoreHoursConfig::default_close_minute")]
+    pub close_minute: u32,
+}
+
+impl StoreHoursConfig {
+    fn default_close_minute() -> u32 {
+        MINUTES_PER_DAY
+    }
+}
+
+impl Default for StoreHoursConfig {
+    fn default() -> Self {
+        Self {
+            open_minute: 0,
+            close_minute: Self::default_close_minute(),
+        }
+    }
+}
+
+/// Sanity rules for a chosen delivery or pickup window: a real date, a
+/// window that ends after it starts, inside configured store hours and
+/// not already past when the order was created. Keeps nonsense slots
+/// (end before start, dates in 1970) off the fulfillment board.
+pub fn validate_delivery_slot(
+    slot: &DeliveryTimeSlot,
+    order_created_at: u64,
+    hours: &StoreHoursConfig,
+) -> Result<(), String> {
+    if slot.time_slot.trim().is_empty() {
+        return Err("Delivery slot must carry a display window".to_string());
+    }
+    if slot.date == 0 {
+        return Err("Delivery slot has no date".to_string());
+    }
+    if let (Some(start), Some(end)) = (slot.start_minute, slot.end_minute) {
+        if end <= start {
+            return Err(format!(
+                "Delivery window ends at minute {} before it starts at {}",
+                end, start
+            ));
+        }
+    }
+    for minute in [slot.start_minute, slot.end_minute].into_iter().flatten() {
+        if minute > MINUTES_PER_DAY {
+            return Err(format!("Delivery window minute {} is past midnight", minute));
+        }
+        if minute < hours.open_minute || minute > hours.close_minute {
+            return Err(format!(
+                "Delivery window minute {} is outside store hours {}-{}",
+                minute, hours.open_minute, hours.close_minute
+            ));
+        }
+    }
+    let slot_end = slot.date + slot.end_minute.unwrap_or(MINUTES_PER_DAY) as u64 * MS_PER_MINUTE;
+    if slot_end < order_created_at {
+        return Err("Delivery slot was already past when the order was created".to_string());
+    }
+    Ok(())
+}
+
+/// Groups the per-store orders produced by one split checkout, so the
+/// UI can track them together.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct OrderBundle {
+    /// The per-store `CheckedOutCart` creates, in checkout order.
+    pub order_hashes: Vec<ActionHash>,
+    pub created_at: u64,
+}
+
+/// How the order reaches the customer: delivered to a saved address or
+/// picked up at a store during a chosen slot.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FulfillmentMethod {
+    Delivery { address_hash: ActionHash },
+    Pickup {
+        store_id: String,
+        slot: DeliveryTimeSlot,
+    },
+}
+
+/// How the order changes hands at the door, structured so the shopper
+/// app can render explicit handoff steps instead of parsing free-text
+/// instructions.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum DeliveryHandoff {
+    LeaveAtDoor,
+    HandToCustomer {
+        /// The fulfiller must sight photo ID before handing over.
+        #[serde(default)]
+        id_check: bool,
+    },
+    Doorman,
+}
+
+/// A bookable curbside-pickup window at one store, published by admin
+/// agents and discovered through a per-store anchor.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct PickupSlot {
+    pub store_id: String,
+    /// Unix timestamp (ms) for the start of the pickup day.
+    pub date: u64,
+    /// Display window, e.g. "2pm-4pm".
+    pub time_slot: String,
+    /// Orders that may book this window; 0 means unlimited.
+    pub capacity: u32,
+}
+
+pub fn validate_pickup_slot(
+    slot: PickupSlot,
+    author: &AgentPubKey,
+) -> ExternResult<ValidateCallbackResult> {
+    let properties = DnaProperties::try_from(dna_info()?.modifiers.properties).unwrap_or_default();
+    if !properties.admins.is_empty() && !properties.admins.contains(author) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only admin agents may publish pickup slots".to_string(),
+        ));
+    }
+    if slot.store_id.trim().is_empty() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Pickup slot must name a store".to_string(),
+        ));
+    }
+    if slot.time_slot.trim().is_empty() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Pickup slot must carry a display window".to_string(),
+        ));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// One unit of a pickup slot's capacity, claimed at checkout and
+/// deleted again if the order is cancelled.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct SlotReservation {
+    pub slot_hash: ActionHash,
+    pub order_hash: ActionHash,
+    pub reserved_at: u64,
+}
+
+pub fn validate_slot_reservation(
+    reservation: SlotReservation,
+    author: &AgentPubKey,
+) -> ExternResult<ValidateCallbackResult> {
+    let slot_record = must_get_valid_record(reservation.slot_hash)?;
+    if slot_record
+        .entry()
+        .to_app_option::<PickupSlot>()
+        .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        .is_none()
+    {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Slot reservation references a non-PickupSlot entry".to_string(),
+        ));
+    }
+    let order_record = must_get_valid_record(reservation.order_hash)?;
+    if order_record.action().author() != author {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only the order's author may reserve a slot for it".to_string(),
+        ));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// Proof a completed order actually arrived: a photo stored in chunks
+/// (same mechanism as product images) plus an optional note from the
+/// fulfiller.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct DeliveryProof {
+    pub order_hash: ActionHash,
+    pub mime_type: String,
+    pub chunk_count: u32,
+    pub note: Option<String>,
+    pub delivered_at: u64,
+}
+
+pub fn validate_delivery_proof(proof: DeliveryProof) -> ExternResult<ValidateCallbackResult> {
+    let order_record = must_get_valid_record(proof.order_hash)?;
+    if order_record
+        .entry()
+        .to_app_option::<CheckedOutCart>()
+        .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        .is_none()
+    {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Delivery proof references a non-order entry".to_string(),
+        ));
+    }
+    if proof.chunk_count == 0 && proof.note.is_none() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Delivery proof needs a photo or a note".to_string(),
+        ));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// One chunk of delivery-photo bytes, linked from its `DeliveryProof`
+/// with the chunk index encoded in the link tag.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct DeliveryProofChunk {
+    pub bytes: SerializedBytes,
+}
+
+/// A shopper's photo of the empty shelf behind an out-of-stock line,
+/// so customers and admins can verify the item genuinely wasn't there.
+/// Chunked like delivery proofs, reusing [`DeliveryProofChunk`] for
+/// the bytes.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct ShelfPhoto {
+    pub order_hash: ActionHash,
+    /// The order line the photo is evidence for.
+    pub group_hash: ActionHash,
+    pub product_index: u32,
+    pub mime_type: String,
+    pub chunk_count: u32,
+    pub taken_at: u64,
+}
+
+pub fn validate_shelf_photo(photo: ShelfPhoto) -> ExternResult<ValidateCallbackResult> {
+    if photo.chunk_count == 0 {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Shelf photo needs at least one chunk".to_string(),
+        ));
+    }
+    let order_record = must_get_valid_record(photo.order_hash)?;
+    if order_record
+        .entry()
+        .to_app_option::<CheckedOutCart>()
+        .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        .is_none()
+    {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Shelf photo references a non-order entry".to_string(),
+        ));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// A weekly recurring window a shopper works, minutes from midnight on
+/// `day_of_week` (0 = Sunday).
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct AvailabilityWindow {
+    pub day_of_week: u8,
+    pub start_minute: u32,
+    pub end_minute: u32,
+}
+
+/// When a shopper's device is allowed to push live location signals.
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+#[hdk_entry_helper]
+#[serde(rename_all = "snake_case")]
+pub enum LocationSharing {
+    /// From claim to handoff.
+    Always,
+    /// Only while the order is out for delivery.
+    #[default]
+    OnlyDelivering,
+    /// Never; customers get status-only updates.
+    Never,
+}
+
+
+/// A registered fulfiller: someone who shops and delivers orders. One
+/// per agent, listed on the fulfillment board while `active`.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct ShopperProfile {
+    pub display_name: String,
+    /// Names of the delivery service zones this shopper covers. Empty
+    /// means anywhere.
+    #[serde(default)]
+    pub service_zones: Vec<String>,
+    /// Weekly windows this shopper works. Empty means any time, like
+    /// zones.
+    #[serde(default)]
+    pub availability: Vec<AvailabilityWindow>,
+    /// Whether this shopper's device sends live locations.
+    #[serde(default)]
+    pub location_sharing: LocationSharing,
+    pub vehicle: Option<String>,
+    /// Cleared instead of deleting the profile, so order history keeps
+    /// resolving.
+    pub active: bool,
+    pub registered_at: u64,
+}
+
+pub fn validate_shopper_profile(profile: ShopperProfile) -> ExternResult<ValidateCallbackResult> {
+    if profile.display_name.trim().is_empty() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Shopper profile must carry a display name".to_string(),
+        ));
+    }
+    for window in &profile.availability {
+        if window.day_of_week > 6 {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Availability day must be 0 (Sunday) through 6 (Saturday)".to_string(),
+            ));
+        }
+        if window.end_minute <= window.start_minute || window.end_minute > MINUTES_PER_DAY {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Availability window must end after it starts, within the day".to_string(),
+            ));
+        }
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// A shopper's claim on an open order. First write wins: coordinators
+/// refuse to claim an order that already has a claim link, and the
+/// claim is what later capability handshakes check assignment against.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct OrderClaim {
+    pub order_hash: ActionHash,
+    pub claimed_at: u64,
+    /// Set when an admin pre-assigns the order to a shopper other than
+    /// the claim's author. Absent on self-claims.
+    #[serde(default)]
+    pub shopper: Option<AgentPubKey>,
+    /// The claim this one took over from, on mid-trip handoffs. Lets
+    /// validation accept a departing shopper naming their successor.
+    #[serde(default)]
+    pub transferred_from: Option<ActionHash>,
+}
+
+pub fn validate_order_claim(
+    claim: OrderClaim,
+    author: &AgentPubKey,
+) -> ExternResult<ValidateCallbackResult> {
+    let order_record = must_get_valid_record(claim.order_hash.clone())?;
+    if order_record
+        .entry()
+        .to_app_option::<CheckedOutCart>()
+        .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        .is_none()
+    {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Order claim references a non-order entry".to_string(),
+        ));
+    }
+    if order_record.action().author() == author {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Customers cannot claim their own orders".to_string(),
+        ));
+    }
+    if claim.shopper.as_ref().is_some_and(|shopper| shopper != author) {
+        // Naming someone else takes either admin rights or a prior
+        // claim of one's own being handed over.
+        let handed_over = match &claim.transferred_from {
+            Some(prior_hash) => {
+                let prior_record = must_get_valid_record(prior_hash.clone())?;
+                let prior = prior_record
+                    .entry()
+                    .to_app_option::<OrderClaim>()
+                    .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?;
+                match prior {
+                    Some(prior) => {
+                        prior.order_hash == claim.order_hash
+                            && prior
+                                .shopper
+                                .as_ref()
+                                .unwrap_or(prior_record.action().author())
+                                == author
+                    }
+                    None => false,
+                }
+            }
+            None => false,
+        };
+        if !handed_over {
+            let properties =
+                DnaProperties::try_from(dna_info()?.modifiers.properties).unwrap_or_default();
+            if !properties.admins.is_empty() && !properties.admins.contains(author) {
+                return Ok(ValidateCallbackResult::Invalid(
+                    "Only admin agents may assign orders to other shoppers".to_string(),
+                ));
+            }
+        }
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// Several compatible orders a shopper took on as one store trip, so
+/// small baskets can share the travel. Coordinators enforce
+/// compatibility (same slot day, overlapping windows) and claim each
+/// order individually; the batch entry ties the claims together for
+/// the combined pick list.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct ShoppingBatch {
+    pub order_hashes: Vec<ActionHash>,
+    pub created_at: u64,
+}
+
+pub fn validate_shopping_batch(
+    batch: ShoppingBatch,
+    author: &AgentPubKey,
+) -> ExternResult<ValidateCallbackResult> {
+    if batch.order_hashes.len() < 2 {
+        return Ok(ValidateCallbackResult::Invalid(
+            "A shopping batch combines at least two orders".to_string(),
+        ));
+    }
+    let properties = DnaProperties::try_from(dna_info()?.modifiers.properties).unwrap_or_default();
+    let cap = properties.limits.max_batch_orders;
+    if cap > 0 && batch.order_hashes.len() > cap as usize {
+        return Ok(ValidateCallbackResult::Invalid(format!(
+            "Batch has {} orders, over the limit of {}",
+            batch.order_hashes.len(),
+            cap
+        )));
+    }
+    let mut seen: Vec<&ActionHash> = Vec::new();
+    for order_hash in &batch.order_hashes {
+        if seen.contains(&order_hash) {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Batch lists the same order twice".to_string(),
+            ));
+        }
+        seen.push(order_hash);
+        let order_record = must_get_valid_record(order_hash.clone())?;
+        if order_record
+            .entry()
+            .to_app_option::<CheckedOutCart>()
+            .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+            .is_none()
+        {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Batch references a non-order entry".to_string(),
+            ));
+        }
+        if order_record.action().author() == author {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Customers cannot batch their own orders".to_string(),
+            ));
+        }
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// Possible reasons a shopper flags an order's customer.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FlagReason {
+    /// Nobody answered for a hand-to-customer delivery.
+    NoShow,
+    /// The delivery location felt unsafe to approach.
+    UnsafeAddress,
+    /// Abusive behaviour in chat or at the door.
+    Abusive,
+    Other,
+}
+
+/// A shopper's structured report about a problematic order, stored as a
+/// private entry. The reporter keeps their own copy and pushes one to
+/// each admin agent, so dispatch can factor it in without the flag ever
+/// appearing on the public DHT.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct CustomerFlag {
+    pub customer: AgentPubKey,
+    pub order_hash: Option<ActionHash>,
+    pub reason: FlagReason,
+    pub note: Option<String>,
+    pub flagged_at: u64,
+    /// Set on admin-stored copies, where the entry author is the admin
+    /// rather than the shopper who reported it.
+    #[serde(default)]
+    pub reporter: Option<AgentPubKey>,
+}
+
+/// A customer's post-delivery rating of the shopper who fulfilled one
+/// of their orders. One per order; linked from both the order and the
+/// shopper's profile so aggregates can be read either way.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct ShopperRating {
+    pub order_hash: ActionHash,
+    pub shopper: AgentPubKey,
+    /// 1 to 5.
+    pub stars: u8,
+    pub comment: Option<String>,
+    pub rated_at: u64,
+}
+
+pub fn validate_shopper_rating(
+    rating: ShopperRating,
+    author: &AgentPubKey,
+) -> ExternResult<ValidateCallbackResult> {
+    if !(1..=5).contains(&rating.stars) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Ratings run from 1 to 5 stars".to_string(),
+        ));
+    }
+    let order_record = must_get_valid_record(rating.order_hash)?;
+    if order_record
+        .entry()
+        .to_app_option::<CheckedOutCart>()
+        .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        .is_none()
+    {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Shopper rating references a non-order entry".to_string(),
+        ));
+    }
+    if order_record.action().author() != author {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only the customer who placed an order may rate its shopper".to_string(),
+        ));
+    }
+    if rating.shopper == *author {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Customers cannot rate themselves".to_string(),
+        ));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// One message in an order's chat between the customer and the
+/// assigned shopper. Coordinators restrict who may write and read;
+/// integrity keeps the deterministic checks.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct ChatMessage {
+    pub order_hash: ActionHash,
+    pub text: String,
+    pub sent_at: u64,
+}
+
+pub fn validate_chat_message(message: ChatMessage) -> ExternResult<ValidateCallbackResult> {
+    if message.text.trim().is_empty() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Chat messages cannot be empty".to_string(),
+        ));
+    }
+    if message.text.chars().count() > 2000 {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Chat messages are limited to 2000 characters".to_string(),
+        ));
+    }
+    let order_record = must_get_valid_record(message.order_hash)?;
+    if order_record
+        .entry()
+        .to_app_option::<CheckedOutCart>()
+        .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        .is_none()
+    {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Chat message references a non-order entry".to_string(),
+        ));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// A superseded set of delivery details, kept on the order so the
+/// shopper always sees the latest instructions while disputes can still
+/// reference what was asked for earlier.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct DeliveryDetailsChange {
+    pub delivery_instructions: Option<String>,
+    pub delivery_time: Option<DeliveryTimeSlot>,
+    /// Unix ms at which these details were replaced.
+    pub changed_at: u64,
+}
+
+/// Catalog details captured at checkout time, so the order history
+/// still shows the right products after a catalog re-import replaces
+/// the groups it references.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct ProductSnapshot {
+    pub name: String,
+    /// Stable import-feed id, so stale group references can be
+    /// re-resolved after a catalog re-import.
+    #[serde(default)]
+    pub product_id: String,
+    /// Primary category at checkout time; drives tax rate lookup.
+    #[serde(default)]
+    pub category: String,
+    pub price: f64,
+    pub promo_price: Option<f64>,
+    pub size: String,
+    pub image_url: Option<String>,
+    /// Store/vendor the product was sold by; drives split checkout in
+    /// multi-store catalogs.
+    #[serde(default)]
+    pub store_id: Option<String>,
+    /// Requires an ID check at handoff. Mirrored from the catalog so
+    /// the rule survives catalog re-imports.
+    #[serde(default)]
+    pub age_restricted: bool,
+}
+
+/// How a promo code reduces the order subtotal.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Discount {
+    Percent { percent: f64 },
+    Fixed { amount: f64 },
+}
+
+/// A promotional discount code, created by admin agents and applied to
+/// a cart before checkout.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct PromoCode {
+    pub code: String,
+    pub discount: Discount,
+    /// Validity window, unix ms.
+    pub valid_from: u64,
+    pub valid_until: u64,
+    /// Total redemptions allowed across the network; 0 means unlimited.
+    pub usage_limit: u32,
+}
+
+impl Discount {
+    /// The discount a code grants on a given subtotal. A fixed discount
+    /// never exceeds the subtotal.
+    pub fn amount_for_subtotal(&self, subtotal: f64) -> f64 {
+        match self {
+            Discount::Percent { percent } => subtotal * percent / 100.0,
+            Discount::Fixed { amount } => amount.min(subtotal),
+        }
+    }
+}
+
+pub fn validate_promo_code(
+    promo: PromoCode,
+    author: &AgentPubKey,
+) -> ExternResult<ValidateCallbackResult> {
+    let properties = DnaProperties::try_from(dna_info()?.modifiers.properties).unwrap_or_default();
+    if !properties.admins.is_empty() && !properties.admins.contains(author) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only admin agents may create promo codes".to_string(),
+        ));
+    }
+    if promo.code.trim().is_empty() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Promo code must not be empty".to_string(),
+        ));
+    }
+    if promo.valid_until <= promo.valid_from {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Promo code validity window must end after it starts".to_string(),
+        ));
+    }
+    match promo.discount {
+        Discount::Percent { percent } if !(0.0..=100.0).contains(&percent) || percent == 0.0 => {
+            Ok(ValidateCallbackResult::Invalid(
+                "Percent discount must be between 0 and 100".to_string(),
+            ))
+        }
+        Discount::Fixed { amount } if amount <= 0.0 => Ok(ValidateCallbackResult::Invalid(
+            "Fixed discount must be positive".to_string(),
+        )),
+        _ => Ok(ValidateCallbackResult::Valid),
+    }
+}
+
+/// A prepaid balance issued by an admin. Only the blake2b hash of the
+/// redemption code is public; whoever presents the matching code first
+/// claims the card.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct GiftCard {
+    /// blake2b-256 hash of the redemption code.
+    pub code_hash: Vec<u8>,
+    pub initial_balance: f64,
+}
+
+/// The claim binding a gift card to the agent that redeemed its code.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct GiftCardClaim {
+    pub gift_card_hash: ActionHash,
+    pub claimed_at: u64,
+}
+
+/// One drawdown against a claimed gift card. Spends form a hash chain
+/// through `previous_spend_hash`, so two spends claiming the same
+/// predecessor are a detectable double-spend fork, and the running
+/// balance is validated link by link.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct GiftCardSpend {
+    pub gift_card_hash: ActionHash,
+    pub claim_hash: ActionHash,
+    pub previous_spend_hash: Option<ActionHash>,
+    pub amount: f64,
+    pub balance_after: f64,
+    /// The order id this spend paid toward.
+    pub order_id: String,
+    pub timestamp: u64,
+}
+
+pub fn validate_gift_card(
+    card: GiftCard,
+    author: &AgentPubKey,
+) -> ExternResult<ValidateCallbackResult> {
+    let properties = DnaProperties::try_from(dna_info()?.modifiers.properties).unwrap_or_default();
+    if !properties.admins.is_empty() && !properties.admins.contains(author) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only admin agents may issue gift cards".to_string(),
+        ));
+    }
+    if card.code_hash.len() != 32 {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Gift card code hash must be 32 bytes".to_string(),
+        ));
+    }
+    if card.initial_balance <= 0.0 {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Gift card balance must be positive".to_string(),
+        ));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+pub fn validate_gift_card_spend(
+    spend: GiftCardSpend,
+    author: &AgentPubKey,
+) -> ExternResult<ValidateCallbackResult> {
+    if spend.amount <= 0.0 {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Gift card spend amount must be positive".to_string(),
+        ));
+    }
+
+    let claim_record = must_get_valid_record(spend.claim_hash.clone())?;
+    if claim_record.action().author() != author {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only the agent that claimed a gift card may spend from it".to_string(),
+        ));
+    }
+    let claim: GiftCardClaim = claim_record
+        .entry()
+        .to_app_option()
+        .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        .ok_or(wasm_error!(WasmErrorInner::Guest(
+            "Spend references a non-GiftCardClaim entry".to_string()
+        )))?;
+    if claim.gift_card_hash != spend.gift_card_hash {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Spend and claim reference different gift cards".to_string(),
+        ));
+    }
+
+    let balance_before = match &spend.previous_spend_hash {
+        Some(previous_hash) => {
+            let previous_record = must_get_valid_record(previous_hash.clone())?;
+            let previous: GiftCardSpend = previous_record
+                .entry()
+                .to_app_option()
+                .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+                .ok_or(wasm_error!(WasmErrorInner::Guest(
+                    "Previous spend is not a GiftCardSpend entry".to_string()
+                )))?;
+            if previous.gift_card_hash != spend.gift_card_hash {
+                return Ok(ValidateCallbackResult::Invalid(
+                    "Spend chain crosses gift cards".to_string(),
+                ));
+            }
+            previous.balance_after
+        }
+        None => {
+            let card_record = must_get_valid_record(spend.gift_card_hash.clone())?;
+            let card: GiftCard = card_record
+                .entry()
+                .to_app_option()
+                .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+                .ok_or(wasm_error!(WasmErrorInner::Guest(
+                    "Spend references a non-GiftCard entry".to_string()
+                )))?;
+            card.initial_balance
+        }
+    };
+
+    let expected_after = balance_before - spend.amount;
+    if expected_after < -MONEY_EPSILON {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Gift card spend exceeds remaining balance".to_string(),
+        ));
+    }
+    if (spend.balance_after - expected_after).abs() > MONEY_EPSILON {
+        return Ok(ValidateCallbackResult::Invalid(format!(
+            "Gift card balance after spend {} does not match expected {}",
+            spend.balance_after, expected_after
+        )));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// Tax rate override for one product category.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct CategoryTaxRate {
+    pub category: String,
+    pub rate: f64,
+}
+
+/// Tax configuration, read from DNA properties so each network can set
+/// its own rates without a code change.
+#[derive(Clone, PartialEq, Default)]
+#[hdk_entry_helper]
+pub struct TaxConfig {
+    /// Rate applied to categories without an override, e.g. 0.0825.
+    #[serde(default)]
+    pub default_rate: f64,
+    #[serde(default)]
+    pub category_rates: Vec<CategoryTaxRate>,
+}
+
+impl TaxConfig {
+    pub fn rate_for_category(&self, category: &str) -> f64 {
+        self.category_rates
+            .iter()
+            .find(|override_| override_.category == category)
+            .map(|override_| override_.rate)
+            .unwrap_or(self.default_rate)
+    }
+}
+
+/// Delivery fee rules, read from DNA properties alongside [`TaxConfig`].
+#[derive(Clone, PartialEq, Default)]
+#[hdk_entry_helper]
+pub struct DeliveryFeeConfig {
+    #[serde(default)]
+    pub base_fee: f64,
+    /// Orders with a subtotal at or over this deliver free; 0 disables.
+    #[serde(default)]
+    pub free_over: f64,
+    /// Added on top of the base fee below `small_order_minimum`.
+    #[serde(default)]
+    pub small_order_surcharge: f64,
+    #[serde(default)]
+    pub small_order_minimum: f64,
+}
+
+impl DeliveryFeeConfig {
+    pub fn fee_for_subtotal(&self, subtotal: f64) -> f64 {
+        if self.free_over > 0.0 && subtotal >= self.free_over {
+            return 0.0;
+        }
+        let mut fee = self.base_fee;
+        if subtotal < self.small_order_minimum {
+            fee += self.small_order_surcharge;
+        }
+        fee
+    }
+}
+
+/// Hard caps on what a single order may contain, read from DNA
+/// properties. A cap of 0 is unenforced.
+#[derive(Clone, PartialEq, Default)]
+#[hdk_entry_helper]
+pub struct CartLimitsConfig {
+    #[serde(default)]
+    pub max_distinct_items: u32,
+    #[serde(default)]
+    pub max_total_quantity: f64,
+    #[serde(default)]
+    pub max_order_value: f64,
+    /// Most orders a shopper may combine into one shopping batch. Zero
+    /// means unlimited, like the other caps.
+    #[serde(default)]
+    pub max_batch_orders: u32,
+}
+
+impl CartLimitsConfig {
+    /// Check an order's size and value against the configured caps.
+    pub fn check(&self, cart: &CheckedOutCart) -> Result<(), String> {
+        if self.max_distinct_items > 0 && cart.products.len() > self.max_distinct_items as usize {
+            return Err(format!(
+                "Order has {} distinct items, over the limit of {}",
+                cart.products.len(),
+                self.max_distinct_items
+            ));
+        }
+        let total_quantity: f64 = cart.products.iter().map(|item| item.quantity).sum();
+        if self.max_total_quantity > 0.0
+            && total_quantity > self.max_total_quantity + QUANTITY_EPSILON
+        {
+            return Err(format!(
+                "Order quantity {} is over the limit of {}",
+                total_quantity, self.max_total_quantity
+            ));
+        }
+        if self.max_order_value > 0.0 && cart.total > self.max_order_value + MONEY_EPSILON {
+            return Err(format!(
+                "Order value {} is over the limit of {}",
+                cart.total, self.max_order_value
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Inputs to the delivery ETA estimate, read from DNA properties.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct DeliveryEtaConfig {
+    /// Minutes to shop and pack an order.
+    #[serde(default = "DeliveryEtaConfig::default_prep_minutes")]
+    pub prep_minutes: u64,
+    /// Minutes of travel to the customer.
+    #[serde(default = "DeliveryEtaConfig::default_travel_minutes")]
+    pub travel_minutes: u64,
+    /// Extra minutes per open order already booked into the same slot.
+    #[serde(default = "DeliveryEtaConfig::default_minutes_per_open_order")]
+    pub minutes_per_open_order: u64,
+    /// Width of the promised arrival window.
+    #[serde(default = "DeliveryEtaConfig::default_window_minutes")]
+    pub window_minutes: u64,
+}
+
+impl DeliveryEtaConfig {
+    fn default_prep_minutes() -> u64 {
+        30
+    }
+    fn default_travel_minutes() -> u64 {
+        15
+    }
+    fn default_minutes_per_open_order() -> u64 {
+        10
+    }
+    fn default_window_minutes() -> u64 {
+        30
+    }
+}
+
+impl Default for DeliveryEtaConfig {
+    fn default() -> Self {
+        Self {
+            prep_minutes: Self::default_prep_minutes(),
+            travel_minutes: Self::default_travel_minutes(),
+            minutes_per_open_order: Self::default_minutes_per_open_order(),
+            window_minutes: Self::default_window_minutes(),
+        }
+    }
+}
+
+/// The arrival window promised to the customer, unix ms.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct DeliveryEstimate {
+    pub earliest: u64,
+    pub latest: u64,
+}
+
+/// Private-cart housekeeping rules, read from DNA properties alongside
+/// [`TaxConfig`].
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct CartExpiryConfig {
+    /// A cart untouched for this long is set aside as a [`SavedCart`];
+    /// 0 disables the job.
+    #[serde(default = "CartExpiryConfig::default_abandoned_after_ms")]
+    pub abandoned_after_ms: u64,
+    /// A claimed order not advanced to Shopping within this long goes
+    /// back to the available pool; 0 disables the job.
+    #[serde(default = "CartExpiryConfig::default_stale_claim_ms")]
+    pub stale_claim_ms: u64,
+}
+
+impl CartExpiryConfig {
+    fn default_abandoned_after_ms() -> u64 {
+        30 * 24 * 60 * 60 * 1000
+    }
+
+    fn default_stale_claim_ms() -> u64 {
+        45 * 60 * 1000
+    }
+}
+
+impl Default for CartExpiryConfig {
+    fn default() -> Self {
+        Self {
+            abandoned_after_ms: Self::default_abandoned_after_ms(),
+            stale_claim_ms: Self::default_stale_claim_ms(),
+        }
+    }
+}
+
+/// Properties this DNA is installed with. Missing fields fall back to
+/// defaults (no tax, no delivery fee).
+#[derive(Clone, PartialEq, Default)]
+#[hdk_entry_helper]
+pub struct DnaProperties {
+    #[serde(default)]
+    pub tax: TaxConfig,
+    #[serde(default)]
+    pub delivery: DeliveryFeeConfig,
+    #[serde(default)]
+    pub expiry: CartExpiryConfig,
+    #[serde(default)]
+    pub limits: CartLimitsConfig,
+    #[serde(default)]
+    pub eta: DeliveryEtaConfig,
+    #[serde(default)]
+    pub substitutions: SubstitutionConfig,
+    #[serde(default)]
+    pub weights: WeightToleranceConfig,
+    #[serde(default)]
+    pub hours: StoreHoursConfig,
+    /// Agents allowed to manage promo codes and other store config.
+    /// Empty means unrestricted (development networks).
+    #[serde(default)]
+    pub admins: Vec<AgentPubKey>,
+}
+
+pub use summon_types::{
+    NetworkInvite, SignedNetworkInvite, CUSTOMER_INVITE_ROLE, SHOPPER_INVITE_ROLE,
+};
+
+/// Membrane check: the shared invite rules applied with this DNA's
+/// admin list. `joined_at` is the AgentValidationPkg action timestamp,
+/// which is what invite expiry is measured against.
+pub fn validate_agent_joining(
+    agent: AgentPubKey,
+    membrane_proof: &Option<MembraneProof>,
+    joined_at: Timestamp,
+) -> ExternResult<ValidateCallbackResult> {
+    let properties = DnaProperties::try_from(dna_info()?.modifiers.properties).unwrap_or_default();
+    summon_types::validate_invite_membrane(
+        agent,
+        membrane_proof,
+        &properties.admins,
+        joined_at.as_millis() as u64,
+    )
+}
+
+/// Public record that an invite code has been consumed, written by the
+/// invitee on first init and linked from the "invite_claims" anchor.
+/// Single use is structural — a code only admits the key it names — so
+/// claims exist for issuance bookkeeping: admins see which minted
+/// codes were actually used.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct InviteClaim {
+    pub invite: SignedNetworkInvite,
+    pub claimed_at: u64,
+}
+
+/// A claim must come from the invitee it names, carry a signature that
+/// verifies, and (on admin networks) name an admin as issuer. Claims
+/// are append-only audit data, so nothing else is checked.
+pub fn validate_invite_claim(
+    claim: InviteClaim,
+    author: &AgentPubKey,
+) -> ExternResult<ValidateCallbackResult> {
+    if claim.invite.invite.invitee != *author {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Invite claims may only be recorded by the invitee".to_string(),
+        ));
+    }
+    let properties = DnaProperties::try_from(dna_info()?.modifiers.properties).unwrap_or_default();
+    if !properties.admins.is_empty() && !properties.admins.contains(&claim.invite.issuer) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Claimed invite was not issued by an admin on this network".to_string(),
+        ));
+    }
+    if !verify_signature(
+        claim.invite.issuer.clone(),
+        claim.invite.signature.clone(),
+        claim.invite.invite,
+    )? {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Claimed invite's signature does not verify".to_string(),
+        ));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// One tax amount on an order, per category actually purchased.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct TaxLine {
+    pub category: String,
+    pub rate: f64,
+    pub amount: f64,
+}
+
+/// What a dispute is about.
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[hdk_entry_helper]
+#[serde(rename_all = "snake_case")]
+pub enum DisputeCategory {
+    MissingItems,
+    WrongItems,
+    DamagedItems,
+    LateDelivery,
+    PaymentIssue,
+    Other,
+}
+
+/// Where a dispute is in its lifecycle. Resolved is terminal.
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[hdk_entry_helper]
+#[serde(rename_all = "snake_case")]
+pub enum DisputeStatus {
+    Open,
+    UnderReview,
+    Resolved,
+}
+
+impl DisputeStatus {
+    pub fn can_transition_to(&self, next: DisputeStatus) -> bool {
+        use DisputeStatus::*;
+        matches!((self, next), (Open, UnderReview) | (Open, Resolved) | (UnderReview, Resolved))
+    }
+}
+
+/// A formal complaint about an order, raised by either party, so refund
+/// fights have one shared record instead of scattered chat history.
+/// Admin agents move it through the state machine via updates.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct Dispute {
+    pub order_hash: ActionHash,
+    pub category: DisputeCategory,
+    pub description: String,
+    /// Supporting records: delivery proofs, refund requests, receipts.
+    #[serde(default)]
+    pub evidence: Vec<ActionHash>,
+    pub status: DisputeStatus,
+    pub opened_at: u64,
+    /// Filled in by the resolving admin.
+    #[serde(default)]
+    pub resolution: Option<String>,
+}
+
+pub fn validate_dispute(dispute: Dispute) -> ExternResult<ValidateCallbackResult> {
+    if dispute.description.trim().is_empty() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Disputes must describe the problem".to_string(),
+        ));
+    }
+    if dispute.status != DisputeStatus::Open {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Disputes open as open".to_string(),
+        ));
+    }
+    if dispute.resolution.is_some() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "A new dispute cannot carry a resolution".to_string(),
+        ));
+    }
+    let order_record = must_get_valid_record(dispute.order_hash)?;
+    if order_record
+        .entry()
+        .to_app_option::<CheckedOutCart>()
+        .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        .is_none()
+    {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Dispute references a non-order entry".to_string(),
+        ));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+pub fn validate_dispute_update(
+    original_action_hash: ActionHash,
+    new_dispute: &Dispute,
+    author: &AgentPubKey,
+) -> ExternResult<ValidateCallbackResult> {
+    let original_record = must_get_valid_record(original_action_hash)?;
+    let original: Dispute = original_record
+        .entry()
+        .to_app_option()
+        .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        .ok_or(wasm_error!(WasmErrorInner::Guest(
+            "Updated record is not a Dispute".to_string()
+        )))?;
+
+    if new_dispute.order_hash != original.order_hash
+        || new_dispute.category != original.category
+        || new_dispute.description != original.description
+        || new_dispute.opened_at != original.opened_at
+    {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only a dispute's status, evidence and resolution may change".to_string(),
+        ));
+    }
+    if new_dispute.status != original.status {
+        if !original.status.can_transition_to(new_dispute.status) {
+            return Ok(ValidateCallbackResult::Invalid(format!(
+                "Illegal dispute status transition: {:?} -> {:?}",
+                original.status, new_dispute.status
+            )));
+        }
+        let properties =
+            DnaProperties::try_from(dna_info()?.modifiers.properties).unwrap_or_default();
+        if !properties.admins.is_empty() && !properties.admins.contains(author) {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Only admin agents may move a dispute along".to_string(),
+            ));
+        }
+    }
+    if new_dispute.status == DisputeStatus::Resolved
+        && new_dispute.resolution.as_deref().unwrap_or("").trim().is_empty()
+    {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Resolving a dispute requires a resolution note".to_string(),
+        ));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// The shopper's confirmation that they checked a photo ID at
+/// handoff, required before completing orders with age-restricted
+/// items.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct IdCheck {
+    pub checked_at: u64,
+    pub checked_by: AgentPubKey,
+}
+
+/// What a customer signs to prove they are the one collecting a pickup
+/// order: the QR code their app shows at the counter.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct HandoffToken {
+    pub order_hash: ActionHash,
+    pub issued_at: u64,
+    /// Tokens are short-lived so a photographed code goes stale before
+    /// it can be replayed.
+    pub expires_at: u64,
+}
+
+/// A handoff token plus the customer's signature over it, carried in
+/// the QR code and checked by store staff.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct SignedHandoffToken {
+    pub token: HandoffToken,
+    pub signer: AgentPubKey,
+    pub signature: Signature,
+}
+
+/// Proof a valid handoff token was presented at collection, recorded on
+/// the order by whoever scanned it.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct HandoffVerification {
+    pub verified_at: u64,
+    pub verified_by: AgentPubKey,
+}
+
+/// Where an order is in its lifecycle. Serialized snake_case, so the
+/// wire values match the strings the frontend already uses
+/// ("processing", "returned", ...).
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[hdk_entry_helper]
+#[serde(rename_all = "snake_case")]
+pub enum OrderStatus {
+    Processing,
+    Confirmed,
+    Shopping,
+    Delivering,
+    Completed,
+    /// Pulled back into the customer's private cart for editing.
+    Returned,
+    Cancelled,
+}
+
+impl OrderStatus {
+    /// The legal state machine. Completed, Returned and Cancelled are
+    /// terminal.
+    pub fn can_transition_to(&self, next: OrderStatus) -> bool {
+        use OrderStatus::*;
+        matches!(
+            (self, next),
+            (Processing, Confirmed)
+                | (Processing, Returned)
+                | (Processing, Cancelled)
+                | (Confirmed, Shopping)
+                | (Confirmed, Returned)
+                | (Confirmed, Cancelled)
+                | (Shopping, Delivering)
+                | (Delivering, Completed)
+        )
+    }
+
+    /// Transitions only the customer who placed the order may make.
+    pub fn customer_only(next: OrderStatus) -> bool {
+        matches!(next, OrderStatus::Returned | OrderStatus::Cancelled)
+    }
+}
+
+/// One entry in an order's status trail: what it became, when, and who
+/// moved it there.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct StatusChange {
+    pub status: OrderStatus,
+    pub timestamp: u64,
+    pub actor: AgentPubKey,
+}
+
+/// What actually happened to one order line during shopping.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ItemFulfillment {
+    Fulfilled,
+    /// Replaced with another catalog product.
+    Substituted {
+        group_hash: ActionHash,
+        product_index: u32,
+    },
+    OutOfStock,
+    Refunded,
+    /// Picked with the actual quantity bagged, which can differ from
+    /// the requested amount for weight-based items.
+    Picked { actual_quantity: f64 },
+}
+
+/// Substitution flow knobs. The response window is how long a customer
+/// has to answer a proposal before their stored preference applies.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct SubstitutionConfig {
+    #[serde(default = "SubstitutionConfig::default_response_window_minutes")]
+    pub response_window_minutes: u64,
+}
+
+impl SubstitutionConfig {
+    fn default_response_window_minutes() -> u64 {
+        5
+    }
+}
+
+impl Default for SubstitutionConfig {
+    fn default() -> Self {
+        Self {
+            response_window_minutes: Self::default_response_window_minutes(),
+        }
+    }
+}
+
+/// How far a confirmed weight may drift from the requested quantity
+/// before the customer has to approve it, as a percentage.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct WeightToleranceConfig {
+    #[serde(default = "WeightToleranceConfig::default_tolerance_percent")]
+    pub tolerance_percent: f64,
+}
+
+impl WeightToleranceConfig {
+    fn default_tolerance_percent() -> f64 {
+        15.0
+    }
+
+    /// Whether an actual weight is close enough to the requested one to
+    /// apply without asking the customer.
+    pub fn within(&self, requested: f64, actual: f64) -> bool {
+        if requested <= 0.0 {
+            return false;
+        }
+        (actual - requested).abs() / requested * 100.0 <= self.tolerance_percent
+    }
+}
+
+impl Default for WeightToleranceConfig {
+    fn default() -> Self {
+        Self {
+            tolerance_percent: Self::default_tolerance_percent(),
+        }
+    }
+}
+
+/// A shopper's proposed replacement for an unavailable order line,
+/// awaiting the customer's approval.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct SubstitutionProposal {
+    pub order_hash: ActionHash,
+    /// The line being substituted.
+    pub group_hash: ActionHash,
+    pub product_index: u32,
+    /// The proposed replacement.
+    pub proposed_group_hash: ActionHash,
+    pub proposed_product_index: u32,
+    pub note: Option<String>,
+    pub proposed_at: u64,
+    /// After this the customer's stored substitution preference applies
+    /// instead of an explicit answer.
+    pub expires_at: u64,
+}
+
+/// The customer's answer to a substitution proposal.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct SubstitutionResponse {
+    pub proposal_hash: ActionHash,
+    pub approved: bool,
+    pub responded_at: u64,
+    /// Set when the response window closed and the line's stored
+    /// substitution preference answered instead of the customer.
+    #[serde(default)]
+    pub auto: bool,
+}
+
+/// One order line (or part of one) a customer wants refunded.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct RefundItem {
+    pub group_hash: ActionHash,
+    pub product_index: u32,
+    pub quantity: f64,
+}
+
+/// Lifecycle of a refund request.
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[hdk_entry_helper]
+#[serde(rename_all = "snake_case")]
+pub enum RefundStatus {
+    Requested,
+    Approved,
+    Denied,
+    Refunded,
+}
+
+impl RefundStatus {
+    pub fn can_transition_to(&self, next: RefundStatus) -> bool {
+        use RefundStatus::*;
+        matches!((self, next), (Requested, Approved) | (Requested, Denied) | (Approved, Refunded))
+    }
+}
+
+/// Structured post-delivery recourse: which items of an order the
+/// customer wants refunded and why, resolved by admin or fulfiller
+/// agents.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct RefundRequest {
+    pub order_hash: ActionHash,
+    pub items: Vec<RefundItem>,
+    pub reason: String,
+    /// Uploaded evidence photos, if any.
+    pub photo_hashes: Vec<ActionHash>,
+    pub status: RefundStatus,
+    pub requested_at: u64,
+}
+
+pub fn validate_refund_request(
+    refund: RefundRequest,
+    author: &AgentPubKey,
+) -> ExternResult<ValidateCallbackResult> {
+    if refund.items.is_empty() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Refund request must name at least one item".to_string(),
+        ));
+    }
+    if refund.status != RefundStatus::Requested {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Refund request must be created in the Requested state".to_string(),
+        ));
+    }
+    let order_record = must_get_valid_record(refund.order_hash)?;
+    if order_record.action().author() != author {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only the customer who placed an order may request a refund on it".to_string(),
+        ));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+pub fn validate_refund_update(
+    original_action_hash: ActionHash,
+    new_refund: &RefundRequest,
+    author: &AgentPubKey,
+) -> ExternResult<ValidateCallbackResult> {
+    let original_record = must_get_valid_record(original_action_hash)?;
+    let original: RefundRequest = original_record
+        .entry()
+        .to_app_option()
+        .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        .ok_or(wasm_error!(WasmErrorInner::Guest(
+            "Updated record is not a RefundRequest".to_string()
+        )))?;
+
+    if new_refund.items != original.items
+        || new_refund.order_hash != original.order_hash
+        || new_refund.reason != original.reason
+    {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only the status of a refund request may change".to_string(),
+        ));
+    }
+    if !original.status.can_transition_to(new_refund.status) {
+        return Ok(ValidateCallbackResult::Invalid(format!(
+            "Illegal refund status transition: {:?} -> {:?}",
+            original.status, new_refund.status
+        )));
+    }
+    let properties = DnaProperties::try_from(dna_info()?.modifiers.properties).unwrap_or_default();
+    if !properties.admins.is_empty() && !properties.admins.contains(author) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only admin agents may resolve refund requests".to_string(),
+        ));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// Who cancelled an order, when, and why.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct CancellationRecord {
+    pub reason: String,
+    pub cancelled_by: AgentPubKey,
+    pub cancelled_at: u64,
+}
+
+/// A published order. Public so fulfillment can see it.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct CheckedOutCart {
+    pub id: String,
+    pub products: Vec<CartProduct>,
+    /// One snapshot per entry in `products`, same order.
+    #[serde(default)]
+    pub product_snapshots: Vec<ProductSnapshot>,
+    /// Line total per entry in `products`, same order, in dollars
+    /// rounded to cents.
+    #[serde(default)]
+    pub line_totals: Vec<f64>,
+    #[serde(default)]
+    pub subtotal: f64,
+    #[serde(default)]
+    pub tax_lines: Vec<TaxLine>,
+    #[serde(default)]
+    pub tax: f64,
+    #[serde(default)]
+    pub delivery_fee: f64,
+    /// The promo code entry redeemed on this order, if any.
+    #[serde(default)]
+    pub promo_code_hash: Option<ActionHash>,
+    #[serde(default)]
+    pub discount: f64,
+    /// Portion of the total paid from a claimed gift card.
+    #[serde(default)]
+    pub gift_card_payment: f64,
+    /// Computed from catalog prices at checkout; consistency with the
+    /// line totals is enforced in validation.
+    pub total: f64,
+    pub created_at: u64,
+    pub status: OrderStatus,
+    /// Every status the order has been through, oldest first.
+    #[serde(default)]
+    pub status_history: Vec<StatusChange>,
+    /// Present exactly when the order is Cancelled.
+    #[serde(default)]
+    pub cancellation: Option<CancellationRecord>,
+    /// Per-line fulfillment outcome, same order as `products`; `None`
+    /// until the shopper has handled the line. Empty before shopping
+    /// starts.
+    #[serde(default)]
+    pub item_fulfillments: Vec<Option<ItemFulfillment>>,
+    pub address_hash: Option<ActionHash>,
+    pub delivery_instructions: Option<String>,
+    pub delivery_time: Option<DeliveryTimeSlot>,
+    /// Prior delivery details, oldest first, appended whenever the
+    /// customer edits them after checkout.
+    #[serde(default)]
+    pub delivery_details_history: Vec<DeliveryDetailsChange>,
+    /// Absent on orders from before pickup existed; those are delivery
+    /// orders using `address_hash`.
+    #[serde(default)]
+    pub fulfillment_method: Option<FulfillmentMethod>,
+    /// Recorded by the shopper at handoff when the order carries
+    /// age-restricted items.
+    #[serde(default)]
+    pub id_check: Option<IdCheck>,
+    /// Set when a pickup order's handoff token was scanned and
+    /// verified at the counter.
+    #[serde(default)]
+    pub handoff_verification: Option<HandoffVerification>,
+    /// Arrival window computed at checkout from zone config, slot load
+    /// and prep times.
+    #[serde(default)]
+    pub delivery_estimate: Option<DeliveryEstimate>,
+    /// How the fulfiller should hand the order over. Absent means the
+    /// free-text instructions are all there is.
+    #[serde(default)]
+    pub delivery_handoff: Option<DeliveryHandoff>,
+    /// Name of the service zone the delivery address falls in, stamped
+    /// at checkout so dispatch can bucket orders without seeing the
+    /// (private) address.
+    #[serde(default)]
+    pub delivery_zone: Option<String>,
+    /// Placed from a one-time identity with no public address
+    /// reference; the delivery details exist only as a private entry on
+    /// the ordering chain and travel through the order-access
+    /// capability.
+    #[serde(default)]
+    pub pseudonymous: bool,
+}
+
+/// Delivery details for a pseudonymous order. A one-time identity has
+/// no saved address book, so the address is captured inline at checkout
+/// and held privately; only the assigned shopper ever sees it, via
+/// `remote_get_order`.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct PrivateDeliveryAddress {
+    pub order_hash: ActionHash,
+    pub street: String,
+    pub unit: Option<String>,
+    pub city: String,
+    pub state: String,
+    pub zip: String,
+    pub label: Option<String>,
+}
+
+/// One agent the chain owner refuses to work with: a customer keeping a
+/// shopper off their orders, or a shopper declining a customer. Private
+/// to the chain that wrote it; dispatch consults it locally and admins
+/// can still assign across it after review.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct BlockedAgent {
+    pub agent: AgentPubKey,
+    pub note: Option<String>,
+    pub blocked_at: u64,
+}
+
+/// Bookkeeping for one run of a scheduled job, so the scheduler can
+/// tell when each job last ran without separate storage. Private: job
+/// history is nobody else's business.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct JobRun {
+    pub job: String,
+    pub ran_at: u64,
+}
+
+/// One line of a receipt: what was actually delivered and charged,
+/// after substitutions and weight adjustments.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct ReceiptLine {
+    pub name: String,
+    /// Actual quantity delivered; weight items often differ from what
+    /// was ordered.
+    pub quantity: f64,
+    pub unit_price: f64,
+    pub line_total: f64,
+    pub fulfillment: Option<ItemFulfillment>,
+}
+
+/// The final charged amounts for a fulfilled order, written by the
+/// fulfiller once delivery completes.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct Receipt {
+    pub order_hash: ActionHash,
+    /// The Completed revision the receipt was built from, so validation
+    /// can reach the order's final state and fulfillment history.
+    pub final_revision_hash: ActionHash,
+    pub lines: Vec<ReceiptLine>,
+    pub subtotal: f64,
+    pub discount: f64,
+    pub tax: f64,
+    pub delivery_fee: f64,
+    pub tip: f64,
+    pub total: f64,
+    pub created_at: u64,
+}
+
+pub fn validate_receipt(
+    receipt: Receipt,
+    author: &AgentPubKey,
+) -> ExternResult<ValidateCallbackResult> {
+    if receipt.lines.is_empty() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Receipt must contain at least one line".to_string(),
+        ));
+    }
+    let line_sum: f64 = receipt.lines.iter().map(|line| line.line_total).sum();
+    if (receipt.subtotal - line_sum).abs() > MONEY_EPSILON {
+        return Ok(ValidateCallbackResult::Invalid(format!(
+            "Receipt subtotal {} does not match sum of lines {}",
+            receipt.subtotal, line_sum
+        )));
+    }
+    let expected_total =
+        receipt.subtotal - receipt.discount + receipt.tax + receipt.delivery_fee + receipt.tip;
+    if (receipt.total - expected_total).abs() > MONEY_EPSILON {
+        return Ok(ValidateCallbackResult::Invalid(format!(
+            "Receipt total {} does not match its components {}",
+            receipt.total, expected_total
+        )));
+    }
+    // Receipts are the fulfiller's document: the referenced revision
+    // must belong to the order, show it Completed, and name the author
+    // as the shopper who worked it — same rule as item fulfillments.
+    let final_record = must_get_valid_record(receipt.final_revision_hash.clone())?;
+    let Some(final_cart) = final_record
+        .entry()
+        .to_app_option::<CheckedOutCart>()
+        .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+    else {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Receipt's final revision is not a CheckedOutCart".to_string(),
+        ));
+    };
+    if order_chain_root(receipt.final_revision_hash)? != receipt.order_hash {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Receipt's final revision does not belong to its order".to_string(),
+        ));
+    }
+    if final_cart.status != OrderStatus::Completed {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Receipts may only be written for completed orders".to_string(),
+        ));
+    }
+    let fulfiller = final_cart
+        .status_history
+        .iter()
+        .rev()
+        .find(|change| {
+            matches!(
+                change.status,
+                OrderStatus::Shopping | OrderStatus::Delivering
+            )
+        })
+        .map(|change| &change.actor);
+    if fulfiller != Some(author) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only the shopper who fulfilled the order may write its receipt".to_string(),
+        ));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// A remembered note for a product ("no green bananas"), applied to the
+/// matching cart line when the product is added again.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct ProductPreference {
+    pub group_hash: ActionHash,
+    pub product_index: u32,
+    pub note: String,
+    pub timestamp: u64,
+}
+
+/// A reusable shopping list ("weekly staples") the agent can merge into
+/// their cart on demand.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct ShoppingListTemplate {
+    pub name: String,
+    pub items: Vec<CartProduct>,
+    pub created_at: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type")]
+#[hdk_entry_types]
+#[unit_enum(UnitEntryTypes)]
+// Orders dwarf the other entries; boxing them isn't worth fighting the
+// entry-types macro over.
+#[allow(clippy::large_enum_variant)]
+pub enum EntryTypes {
+    #[entry_type(visibility = "private")]
+    PrivateCart(PrivateCart),
+    CheckedOutCart(CheckedOutCart),
+    PromoCode(PromoCode),
+    GiftCard(GiftCard),
+    GiftCardClaim(GiftCardClaim),
+    GiftCardSpend(GiftCardSpend),
+    SubstitutionProposal(SubstitutionProposal),
+    SubstitutionResponse(SubstitutionResponse),
+    RefundRequest(RefundRequest),
+    Receipt(Receipt),
+    #[entry_type(visibility = "private")]
+    ProductPreference(ProductPreference),
+    #[entry_type(visibility = "private")]
+    ShoppingListTemplate(ShoppingListTemplate),
+    #[entry_type(visibility = "private")]
+    CartDelta(CartDelta),
+    #[entry_type(visibility = "private")]
+    SavedCart(SavedCart),
+    OrderBundle(OrderBundle),
+    PickupSlot(PickupSlot),
+    SlotReservation(SlotReservation),
+    DeliveryProof(DeliveryProof),
+    DeliveryProofChunk(DeliveryProofChunk),
+    ShopperProfile(ShopperProfile),
+    OrderClaim(OrderClaim),
+    ChatMessage(ChatMessage),
+    ShopperRating(ShopperRating),
+    #[entry_type(visibility = "private")]
+    CustomerFlag(CustomerFlag),
+    ShoppingBatch(ShoppingBatch),
+    Dispute(Dispute),
+    ShelfPhoto(ShelfPhoto),
+    #[entry_type(visibility = "private")]
+    PrivateDeliveryAddress(PrivateDeliveryAddress),
+    #[entry_type(visibility = "private")]
+    BlockedAgent(BlockedAgent),
+    #[entry_type(visibility = "private")]
+    JobRun(JobRun),
+    InviteClaim(InviteClaim),
+}
+
+#[derive(Serialize, Deserialize)]
+#[hdk_link_types]
+pub enum LinkTypes {
+    /// Agent key -> current PrivateCart revision.
+    PrivateCart,
+    /// Agent key -> CheckedOutCart, tagged "customer".
+    CheckedOutCart,
+    /// Agent key -> ProductPreference.
+    ProductPreference,
+    /// Agent key -> ShoppingListTemplate.
+    ShoppingListTemplate,
+    /// Agent key -> CartDelta entries newer than the latest snapshot.
+    CartDelta,
+    /// "promo_codes" anchor -> PromoCode.
+    PromoCode,
+    /// PromoCode -> CheckedOutCart that redeemed it.
+    PromoCodeUse,
+    /// "gift_cards" anchor -> GiftCard.
+    GiftCard,
+    /// GiftCard -> GiftCardClaim; a card should have at most one.
+    GiftCardClaim,
+    /// GiftCardClaim -> newest GiftCardSpend (the chain tip).
+    GiftCardSpend,
+    /// CheckedOutCart -> SubstitutionProposal.
+    SubstitutionProposal,
+    /// SubstitutionProposal -> SubstitutionResponse.
+    SubstitutionResponse,
+    /// "refund_requests" anchor and CheckedOutCart -> RefundRequest.
+    RefundRequest,
+    /// CheckedOutCart -> Receipt.
+    Receipt,
+    /// Agent key -> OrderBundle from a split checkout.
+    OrderBundle,
+    /// Per-store "pickup_slots.{store_id}" anchor -> PickupSlot.
+    PickupSlot,
+    /// PickupSlot -> CheckedOutCart booked into it (pre-reservation
+    /// orders).
+    PickupSlotOrder,
+    /// PickupSlot and CheckedOutCart -> SlotReservation holding one
+    /// unit of the slot's capacity.
+    SlotReservation,
+    /// "slot_load.{date}.{window}" anchor -> CheckedOutCart, counted
+    /// when estimating delivery times.
+    SlotLoad,
+    /// CheckedOutCart -> DeliveryProof.
+    DeliveryProof,
+    /// DeliveryProof -> DeliveryProofChunk, tag carries the 4-byte
+    /// chunk index.
+    DeliveryProofChunk,
+    /// "shoppers" anchor -> ShopperProfile, and agent key ->
+    /// ShopperProfile for the owner's own lookups.
+    ShopperProfile,
+    /// "available_orders" anchor -> CheckedOutCart awaiting a claim.
+    AvailableOrder,
+    /// CheckedOutCart -> OrderClaim by the assigned shopper.
+    OrderClaim,
+    /// CheckedOutCart -> ChatMessage, oldest first by timestamp.
+    ChatMessage,
+    /// CheckedOutCart -> ShopperRating, and ShopperProfile ->
+    /// ShopperRating for per-shopper aggregates.
+    ShopperRating,
+    /// Shopper agent key -> ShoppingBatch for their own trips.
+    ShoppingBatch,
+    /// CheckedOutCart -> Dispute, and raiser agent key -> Dispute.
+    Dispute,
+    /// CheckedOutCart -> ShelfPhoto.
+    ShelfPhoto,
+    /// ShelfPhoto -> DeliveryProofChunk, tag carries the 4-byte chunk
+    /// index.
+    ShelfPhotoChunk,
+    /// "order_log" anchor -> CheckedOutCart, one per published order,
+    /// for network-wide fulfillment metrics.
+    OrderLog,
+    /// "invite_claims" anchor -> InviteClaim, one per joined invitee.
+    InviteClaim,
+}
+
+#[hdk_extern]
+pub fn genesis_self_check(_data: GenesisSelfCheckData) -> ExternResult<ValidateCallbackResult> {
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// Tolerance for comparing money amounts that were rounded to cents
+/// independently.
+const MONEY_EPSILON: f64 = 0.005;
+
+/// Status transitions are validated against the state machine, and
+/// customer-only transitions against the order's original author.
+/// The create-action hash at the root of an order's update chain,
+/// resolved by walking the chain down. Deterministic: every hop is a
+/// `must_get`, so all validators agree.
+fn order_chain_root(mut hash: ActionHash) -> ExternResult<ActionHash> {
+    loop {
+        let record = must_get_valid_record(hash.clone())?;
+        match record.action() {
+            Action::Update(update) => hash = update.original_action_address.clone(),
+            _ => return Ok(hash),
+        }
+    }
+}
+
+/// The author of an order's create action — the customer.
+fn order_create_author(hash: ActionHash) -> ExternResult<AgentPubKey> {
+    let record = must_get_valid_record(order_chain_root(hash)?)?;
+    Ok(record.action().author().clone())
+}
+
+/// Creates must enter the state machine at its start: Processing, no
+/// cancellation or fulfillments, and at most the seed history entry
+/// recording the checkout itself. Anything further along has to arrive
+/// as an update, so the transition rules below can't be skipped by
+/// publishing a finished order outright.
+pub fn validate_order_create(cart: &CheckedOutCart) -> ExternResult<ValidateCallbackResult> {
+    if cart.status != OrderStatus::Processing {
+        return Ok(ValidateCallbackResult::Invalid(
+            "A new order must start in Processing".to_string(),
+        ));
+    }
+    // The seed entry's actor is not pinned to the action author because
+    // countersigned checkouts commit the same entry from both signers.
+    let seed_only = match cart.status_history.as_slice() {
+        [] => true,
+        [seed] => seed.status == OrderStatus::Processing,
+        _ => false,
+    };
+    if !seed_only {
+        return Ok(ValidateCallbackResult::Invalid(
+            "A new order's history may only record its own checkout".to_string(),
+        ));
+    }
+    if cart.cancellation.is_some() || !cart.item_fulfillments.is_empty() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "A new order cannot carry a cancellation or fulfillment outcomes".to_string(),
+        ));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+pub fn validate_order_update(
+    original_action_hash: ActionHash,
+    new_cart: &CheckedOutCart,
+    author: &AgentPubKey,
+) -> ExternResult<ValidateCallbackResult> {
+    let original_record = must_get_valid_record(original_action_hash.clone())?;
+    let original: CheckedOutCart = original_record
+        .entry()
+        .to_app_option()
+        .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        .ok_or(wasm_error!(WasmErrorInner::Guest(
+            "Updated record is not a CheckedOutCart".to_string()
+        )))?;
+
+    if original.status != new_cart.status {
+        if !original.status.can_transition_to(new_cart.status) {
+            return Ok(ValidateCallbackResult::Invalid(format!(
+                "Illegal order status transition: {:?} -> {:?}",
+                original.status, new_cart.status
+            )));
+        }
+        if OrderStatus::customer_only(new_cart.status)
+            && *author != order_create_author(original_action_hash.clone())?
+        {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Only the customer may return or cancel their order".to_string(),
+            ));
+        }
+        if new_cart.status == OrderStatus::Completed
+            && new_cart
+                .product_snapshots
+                .iter()
+                .any(|snapshot| snapshot.age_restricted)
+            && new_cart.id_check.is_none()
+        {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Orders with age-restricted items need an ID check before completion".to_string(),
+            ));
+        }
+        // A status change must append exactly one matching entry to the
+        // history; the existing trail is immutable.
+        if new_cart.status_history.len() != original.status_history.len() + 1
+            || new_cart.status_history[..original.status_history.len()]
+                != original.status_history[..]
+        {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Status change must append one entry to the status history".to_string(),
+            ));
+        }
+        let appended = new_cart
+            .status_history
+            .last()
+            .expect("history was just checked to be non-empty");
+        if appended.status != new_cart.status || appended.actor != *author {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Appended status history entry must match the new status and author".to_string(),
+            ));
+        }
+    } else if new_cart.status_history != original.status_history {
+        // A same-status append is a handoff marker naming the incoming
+        // shopper; anything else is tampering.
+        let appended_handoff = new_cart.status_history.len()
+            == original.status_history.len() + 1
+            && new_cart.status_history[..original.status_history.len()]
+                == original.status_history[..]
+            && new_cart
+                .status_history
+                .last()
+                .is_some_and(|appended| appended.status == new_cart.status);
+        if !appended_handoff {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Status history may only change with the status".to_string(),
+            ));
+        }
+    }
+
+    // The order's contents and money are the customer's: amendments may
+    // only come from the create author, so nobody else can rewrite what
+    // was ordered or what it cost.
+    let contents_changed = new_cart.products != original.products
+        || new_cart.product_snapshots != original.product_snapshots
+        || new_cart.line_totals != original.line_totals
+        || new_cart.subtotal != original.subtotal
+        || new_cart.tax_lines != original.tax_lines
+        || new_cart.tax != original.tax
+        || new_cart.delivery_fee != original.delivery_fee
+        || new_cart.promo_code_hash != original.promo_code_hash
+        || new_cart.discount != original.discount
+        || new_cart.gift_card_payment != original.gift_card_payment
+        || new_cart.total != original.total;
+    if contents_changed && *author != order_create_author(original_action_hash.clone())? {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only the customer may change an order's contents or totals".to_string(),
+        ));
+    }
+
+    // Likewise the delivery details (and their redaction): where an
+    // order goes and what the courier is told is the customer's call.
+    let delivery_changed = new_cart.delivery_instructions != original.delivery_instructions
+        || new_cart.delivery_time != original.delivery_time
+        || new_cart.delivery_details_history != original.delivery_details_history
+        || new_cart.address_hash != original.address_hash
+        || new_cart.fulfillment_method != original.fulfillment_method;
+    if delivery_changed && *author != order_create_author(original_action_hash.clone())? {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only the customer may change an order's delivery details".to_string(),
+        ));
+    }
+
+    // Fulfillment outcomes are the shopper's testimony about what was
+    // bagged. The claim link isn't deterministic, but the actor of the
+    // latest Shopping transition (refreshed on handoff) is the shopper
+    // working the order.
+    if new_cart.item_fulfillments != original.item_fulfillments {
+        let shopper = new_cart
+            .status_history
+            .iter()
+            .rev()
+            .find(|change| change.status == OrderStatus::Shopping)
+            .map(|change| &change.actor);
+        if shopper != Some(author) {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Only the shopper working the order may record item fulfillments".to_string(),
+            ));
+        }
+    }
+
+    match (new_cart.status, &new_cart.cancellation) {
+        (OrderStatus::Cancelled, Some(cancellation)) => {
+            if original.status == OrderStatus::Cancelled {
+                if new_cart.cancellation != original.cancellation {
+                    return Ok(ValidateCallbackResult::Invalid(
+                        "Cancellation record is immutable".to_string(),
+                    ));
+                }
+            } else if cancellation.cancelled_by != *author {
+                return Ok(ValidateCallbackResult::Invalid(
+                    "Cancellation record must name the agent cancelling".to_string(),
+                ));
+            }
+        }
+        (OrderStatus::Cancelled, None) => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Cancelled order must carry a cancellation record".to_string(),
+            ));
+        }
+        (_, Some(_)) => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Only a cancelled order may carry a cancellation record".to_string(),
+            ));
+        }
+        (_, None) => {}
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+pub fn validate_checked_out_cart(cart: CheckedOutCart) -> ExternResult<ValidateCallbackResult> {
+    if cart.products.is_empty() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Checked out cart must contain at least one product".to_string(),
+        ));
+    }
+    for item in &cart.products {
+        if let Err(reason) = validate_quantity(item.quantity, item.sold_by) {
+            return Ok(ValidateCallbackResult::Invalid(reason));
+        }
+    }
+    if cart.line_totals.len() != cart.products.len() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Order must carry one line total per product".to_string(),
+        ));
+    }
+    if !cart.item_fulfillments.is_empty()
+        && cart.item_fulfillments.len() != cart.products.len()
+    {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Item fulfillments must cover every product or be empty".to_string(),
+        ));
+    }
+    let line_sum: f64 = cart.line_totals.iter().sum();
+    if (cart.subtotal - line_sum).abs() > MONEY_EPSILON {
+        return Ok(ValidateCallbackResult::Invalid(format!(
+            "Order subtotal {} does not match sum of line totals {}",
+            cart.subtotal, line_sum
+        )));
+    }
+    let tax_sum: f64 = cart.tax_lines.iter().map(|line| line.amount).sum();
+    if (cart.tax - tax_sum).abs() > MONEY_EPSILON {
+        return Ok(ValidateCallbackResult::Invalid(format!(
+            "Order tax {} does not match sum of tax lines {}",
+            cart.tax, tax_sum
+        )));
+    }
+    match &cart.promo_code_hash {
+        Some(promo_hash) => {
+            let promo_record = must_get_valid_record(promo_hash.clone())?;
+            let promo: PromoCode = promo_record
+                .entry()
+                .to_app_option()
+                .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+                .ok_or(wasm_error!(WasmErrorInner::Guest(
+                    "Order references a non-PromoCode entry".to_string()
+                )))?;
+            if cart.created_at < promo.valid_from || cart.created_at > promo.valid_until {
+                return Ok(ValidateCallbackResult::Invalid(
+                    "Promo code was not valid when the order was created".to_string(),
+                ));
+            }
+            let expected = promo.discount.amount_for_subtotal(cart.subtotal);
+            if (cart.discount - expected).abs() > MONEY_EPSILON {
+                return Ok(ValidateCallbackResult::Invalid(format!(
+                    "Order discount {} does not match promo code discount {}",
+                    cart.discount, expected
+                )));
+            }
+        }
+        None => {
+            if cart.discount.abs() > MONEY_EPSILON {
+                return Ok(ValidateCallbackResult::Invalid(
+                    "Order carries a discount without a promo code".to_string(),
+                ));
+            }
+        }
+    }
+    match &cart.fulfillment_method {
+        Some(FulfillmentMethod::Delivery { address_hash })
+            if cart.address_hash.as_ref() != Some(address_hash) =>
+        {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Delivery order's address field must match its fulfillment method".to_string(),
+            ));
+        }
+        Some(FulfillmentMethod::Pickup { store_id, slot }) => {
+            if store_id.trim().is_empty() {
+                return Ok(ValidateCallbackResult::Invalid(
+                    "Pickup order must name a store".to_string(),
+                ));
+            }
+            if slot.time_slot.trim().is_empty() {
+                return Ok(ValidateCallbackResult::Invalid(
+                    "Pickup order must carry a pickup slot".to_string(),
+                ));
+            }
+            if cart.delivery_handoff.is_some() {
+                return Ok(ValidateCallbackResult::Invalid(
+                    "Pickup orders cannot carry a delivery handoff".to_string(),
+                ));
+            }
+        }
+        _ => {}
+    }
+    let properties = DnaProperties::try_from(dna_info()?.modifiers.properties).unwrap_or_default();
+    if let Some(slot) = &cart.delivery_time {
+        if let Err(reason) = validate_delivery_slot(slot, cart.created_at, &properties.hours) {
+            return Ok(ValidateCallbackResult::Invalid(reason));
+        }
+    }
+    if let Some(FulfillmentMethod::Pickup { slot, .. }) = &cart.fulfillment_method {
+        if let Err(reason) = validate_delivery_slot(slot, cart.created_at, &properties.hours) {
+            return Ok(ValidateCallbackResult::Invalid(reason));
+        }
+    }
+    // Pickup orders are never charged a delivery fee.
+    let expected_fee = match &cart.fulfillment_method {
+        Some(FulfillmentMethod::Pickup { .. }) => 0.0,
+        _ => properties.delivery.fee_for_subtotal(cart.subtotal),
+    };
+    if (cart.delivery_fee - expected_fee).abs() > MONEY_EPSILON {
+        return Ok(ValidateCallbackResult::Invalid(format!(
+            "Order delivery fee {} does not match configured fee {}",
+            cart.delivery_fee, expected_fee
+        )));
+    }
+    if let Err(reason) = properties.limits.check(&cart) {
+        return Ok(ValidateCallbackResult::Invalid(reason));
+    }
+    if cart.gift_card_payment < -MONEY_EPSILON
+        || cart.gift_card_payment > cart.total + MONEY_EPSILON
+    {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Gift card payment must be between zero and the order total".to_string(),
+        ));
+    }
+    let expected_total = cart.subtotal - cart.discount + cart.tax + cart.delivery_fee;
+    if (cart.total - expected_total).abs() > MONEY_EPSILON {
+        return Ok(ValidateCallbackResult::Invalid(format!(
+            "Order total {} does not match subtotal {} minus discount {} plus tax {} and delivery fee {}",
+            cart.total, cart.subtotal, cart.discount, cart.tax, cart.delivery_fee
+        )));
+    }
+    for product in &cart.products {
+        if let Some(SubstitutionPreference::SpecificItem {
+            group_hash,
+            product_index,
+        }) = &product.substitution_preference
+        {
+            if *group_hash == product.group_hash && *product_index == product.product_index {
+                return Ok(ValidateCallbackResult::Invalid(
+                    "Substitution preference cannot reference the item itself".to_string(),
+                ));
+            }
+        }
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+#[hdk_extern]
+pub fn validate(op: Op) -> ExternResult<ValidateCallbackResult> {
+    if let Op::StoreRecord(StoreRecord { record }) = &op {
+        if let Action::AgentValidationPkg(pkg) = record.action() {
+            return validate_agent_joining(
+                pkg.author.clone(),
+                &pkg.membrane_proof,
+                record.action().timestamp(),
+            );
+        }
+    }
+    match op.flattened::<EntryTypes, LinkTypes>()? {
+        FlatOp::StoreEntry(OpEntry::CreateEntry { app_entry, action }) => match app_entry {
+            EntryTypes::CheckedOutCart(cart) => {
+                match validate_checked_out_cart(cart.clone())? {
+                    ValidateCallbackResult::Valid => validate_order_create(&cart),
+                    invalid => Ok(invalid),
+                }
+            }
+            EntryTypes::PromoCode(promo) => validate_promo_code(promo, &action.author),
+            EntryTypes::GiftCard(card) => validate_gift_card(card, &action.author),
+            EntryTypes::GiftCardSpend(spend) => validate_gift_card_spend(spend, &action.author),
+            EntryTypes::RefundRequest(refund) => validate_refund_request(refund, &action.author),
+            EntryTypes::Receipt(receipt) => validate_receipt(receipt, &action.author),
+            EntryTypes::PickupSlot(slot) => validate_pickup_slot(slot, &action.author),
+            EntryTypes::SlotReservation(reservation) => {
+                validate_slot_reservation(reservation, &action.author)
+            }
+            EntryTypes::DeliveryProof(proof) => validate_delivery_proof(proof),
+            EntryTypes::ShopperProfile(profile) => validate_shopper_profile(profile),
+            EntryTypes::OrderClaim(claim) => validate_order_claim(claim, &action.author),
+            EntryTypes::ChatMessage(message) => validate_chat_message(message),
+            EntryTypes::ShopperRating(rating) => validate_shopper_rating(rating, &action.author),
+            EntryTypes::ShoppingBatch(batch) => validate_shopping_batch(batch, &action.author),
+            EntryTypes::Dispute(dispute) => validate_dispute(dispute),
+            EntryTypes::ShelfPhoto(photo) => validate_shelf_photo(photo),
+            EntryTypes::InviteClaim(claim) => validate_invite_claim(claim, &action.author),
+            _ => Ok(ValidateCallbackResult::Valid),
+        },
+        FlatOp::StoreEntry(OpEntry::UpdateEntry {
+            original_action_hash,
+            app_entry,
+            action,
+            ..
+        }) => match app_entry {
+            EntryTypes::CheckedOutCart(cart) => {
+                match validate_checked_out_cart(cart.clone())? {
+                    ValidateCallbackResult::Valid => {
+                        validate_order_update(original_action_hash, &cart, &action.author)
+                    }
+                    invalid => Ok(invalid),
+                }
+            }
+            EntryTypes::PromoCode(promo) => validate_promo_code(promo, &action.author),
+            EntryTypes::GiftCard(card) => validate_gift_card(card, &action.author),
+            EntryTypes::GiftCardSpend(spend) => validate_gift_card_spend(spend, &action.author),
+            EntryTypes::RefundRequest(refund) => {
+                validate_refund_update(original_action_hash, &refund, &action.author)
+            }
+            EntryTypes::PickupSlot(slot) => validate_pickup_slot(slot, &action.author),
+            EntryTypes::ShopperProfile(profile) => validate_shopper_profile(profile),
+            EntryTypes::Dispute(dispute) => {
+                validate_dispute_update(original_action_hash, &dispute, &action.author)
+            }
+            _ => Ok(ValidateCallbackResult::Valid),
+        },
+        _ => Ok(ValidateCallbackResult::Valid),
+    }
+}
@@ -0,0 +1,77 @@
+//! Preferences coordinator zome. One preference per UPC, stored
+//! privately in the agent's personal clone.
+
+use hdk::prelude::*;
+use preferences_integrity::*;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SavePreferenceInput {
+    pub upc: String,
+    pub note: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PreferenceKey {
+    pub upc: String,
+}
+
+fn find_preference(upc: &str) -> ExternResult<Option<(Link, Preference)>> {
+    let agent = agent_info()?.agent_initial_pubkey;
+    let links = get_links(
+        GetLinksInputBuilder::try_new(agent, LinkTypes::AgentToPreference)?.build(),
+    )?;
+    for link in links {
+        if link.tag.as_ref().as_slice() != upc.as_bytes() {
+            continue;
+        }
+        let Some(hash) = link.target.clone().into_action_hash() else {
+            continue;
+        };
+        let Some(record) = get(hash, GetOptions::default())? else {
+            continue;
+        };
+        if let Some(preference) = record
+            .entry()
+            .to_app_option::<Preference>()
+            .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        {
+            return Ok(Some((link, preference)));
+        }
+    }
+    Ok(None)
+}
+
+#[hdk_extern]
+pub fn save_preference(input: SavePreferenceInput) -> ExternResult<ActionHash> {
+    let agent = agent_info()?.agent_initial_pubkey;
+    if let Some((link, _)) = find_preference(&input.upc)? {
+        delete_link(link.create_link_hash)?;
+    }
+
+    let preference = Preference {
+        upc: input.upc.clone(),
+        note: input.note,
+        updated_at: sys_time()?.as_millis() as u64,
+    };
+    let hash = create_entry(&EntryTypes::Preference(preference))?;
+    create_link(
+        agent,
+        hash.clone(),
+        LinkTypes::AgentToPreference,
+        LinkTag::new(input.upc.into_bytes()),
+    )?;
+    Ok(hash)
+}
+
+#[hdk_extern]
+pub fn get_preference(key: PreferenceKey) -> ExternResult<Option<Preference>> {
+    Ok(find_preference(&key.upc)?.map(|(_, preference)| preference))
+}
+
+#[hdk_extern]
+pub fn delete_preference(key: PreferenceKey) -> ExternResult<()> {
+    if let Some((link, _)) = find_preference(&key.upc)? {
+        delete_link(link.create_link_hash)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,37 @@
+use hdi::prelude::*;
+
+/// A note the agent keeps about a product, keyed by UPC. Each agent runs
+/// in their own preferences clone, so these never leave their network.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct Preference {
+    pub upc: String,
+    pub note: String,
+    pub updated_at: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type")]
+#[hdk_entry_types]
+#[unit_enum(UnitEntryTypes)]
+pub enum EntryTypes {
+    #[entry_type(visibility = "private")]
+    Preference(Preference),
+}
+
+#[derive(Serialize, Deserialize)]
+#[hdk_link_types]
+pub enum LinkTypes {
+    /// Agent key -> Preference, tag carries the UPC.
+    AgentToPreference,
+}
+
+#[hdk_extern]
+pub fn genesis_self_check(_data: GenesisSelfCheckData) -> ExternResult<ValidateCallbackResult> {
+    Ok(ValidateCallbackResult::Valid)
+}
+
+#[hdk_extern]
+pub fn validate(_op: Op) -> ExternResult<ValidateCallbackResult> {
+    Ok(ValidateCallbackResult::Valid)
+}
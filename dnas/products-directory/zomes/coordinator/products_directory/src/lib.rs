@@ -0,0 +1,56 @@
+//! Directory zome: a never-cloned registry telling every agent which
+//! products clone (by network seed) is currently active.
+
+use hdk::prelude::*;
+use products_directory_integrity::*;
+
+fn directory_path() -> ExternResult<TypedPath> {
+    Path::from("active_catalog").typed(LinkTypes::ActiveCatalog)
+}
+
+/// Returns the network seed of the active catalog clone, if one has
+/// been registered.
+#[hdk_extern]
+pub fn get_active_catalog(_: ()) -> ExternResult<Option<String>> {
+    let path = directory_path()?;
+    let links = get_links(
+        GetLinksInputBuilder::try_new(path.path_entry_hash()?, LinkTypes::ActiveCatalog)?
+            .build(),
+    )?;
+
+    let Some(link) = links.into_iter().max_by_key(|link| link.timestamp) else {
+        return Ok(None);
+    };
+    let Some(hash) = link.target.into_action_hash() else {
+        return Ok(None);
+    };
+    let Some(record) = get(hash, GetOptions::default())? else {
+        return Ok(None);
+    };
+    let catalog = record
+        .entry()
+        .to_app_option::<ActiveCatalog>()
+        .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?;
+
+    Ok(catalog.map(|catalog| catalog.seed))
+}
+
+/// Registers a new active catalog seed after an upload completes.
+#[hdk_extern]
+pub fn update_active_catalog(seed: String) -> ExternResult<ActionHash> {
+    let path = directory_path()?;
+    path.ensure()?;
+
+    let catalog = ActiveCatalog {
+        seed,
+        updated_at: sys_time()?.as_millis() as u64,
+    };
+    let hash = create_entry(&EntryTypes::ActiveCatalog(catalog))?;
+    create_link(
+        path.path_entry_hash()?,
+        hash.clone(),
+        LinkTypes::ActiveCatalog,
+        (),
+    )?;
+    Ok(hash)
+}
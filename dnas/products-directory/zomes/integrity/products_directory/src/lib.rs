@@ -0,0 +1,35 @@
+use hdi::prelude::*;
+
+/// Registry record for the catalog clone currently in service. The seed
+/// is the network seed of the active products clone.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct ActiveCatalog {
+    pub seed: String,
+    pub updated_at: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type")]
+#[hdk_entry_types]
+#[unit_enum(UnitEntryTypes)]
+pub enum EntryTypes {
+    ActiveCatalog(ActiveCatalog),
+}
+
+#[derive(Serialize, Deserialize)]
+#[hdk_link_types]
+pub enum LinkTypes {
+    /// "active_catalog" anchor -> newest ActiveCatalog entry.
+    ActiveCatalog,
+}
+
+#[hdk_extern]
+pub fn genesis_self_check(_data: GenesisSelfCheckData) -> ExternResult<ValidateCallbackResult> {
+    Ok(ValidateCallbackResult::Valid)
+}
+
+#[hdk_extern]
+pub fn validate(_op: Op) -> ExternResult<ValidateCallbackResult> {
+    Ok(ValidateCallbackResult::Valid)
+}
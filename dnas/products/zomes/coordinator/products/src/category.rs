@@ -0,0 +1,110 @@
+use hdk::prelude::*;
+use products_integrity::*;
+
+pub(crate) use summon_types::{count_from_tag, link_count_tag};
+use summon_types::{Page, PageRequest};
+
+/// Anchor path for a category / subcategory / product-type triple.
+pub(crate) fn category_path(
+    category: &str,
+    subcategory: Option<&str>,
+    product_type: Option<&str>,
+) -> ExternResult<TypedPath> {
+    Path::from(summon_types::category_path_string(
+        category,
+        subcategory,
+        product_type,
+    ))
+    .typed(LinkTypes::CategoryToGroup)
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct CategoryQuery {
+    pub category: String,
+    pub subcategory: Option<String>,
+    #[serde(alias = "productType")]
+    pub product_type: Option<String>,
+    /// The cursor is an offset into the path's group links.
+    #[serde(flatten)]
+    pub page: PageRequest,
+}
+
+/// Legacy response shape of `get_all_category_products`, kept because
+/// the UI's clone-readiness probe reads `product_groups` off it.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct CategorizedProducts {
+    pub product_groups: Vec<Record>,
+    pub total_products: usize,
+    pub has_more: bool,
+}
+
+fn group_links_for_path(query: &CategoryQuery) -> ExternResult<Vec<Link>> {
+    let path = category_path(
+        &query.category,
+        query.subcategory.as_deref(),
+        query.product_type.as_deref(),
+    )?;
+    get_links(
+        GetLinksInputBuilder::try_new(path.path_entry_hash()?, LinkTypes::CategoryToGroup)?
+            .build(),
+    )
+}
+
+/// Page through the ProductGroups linked at a category path. The
+/// cursor and limit are in groups, not products; `total` is the group
+/// count at the path.
+#[hdk_extern]
+pub fn get_products_by_category(query: CategoryQuery) -> ExternResult<Page<Record>> {
+    let links = group_links_for_path(&query)?;
+    let total = links.len();
+
+    let offset = query.page.offset();
+    let limit = query.page.limit_or(5);
+
+    let mut product_groups = Vec::new();
+    for link in links.into_iter().skip(offset).take(limit) {
+        let Some(hash) = link.target.into_action_hash() else {
+            continue;
+        };
+        if let Some(record) = crate::product::latest_group_record(hash)? {
+            product_groups.push(record);
+        }
+    }
+
+    Ok(Page::from_offset(product_groups, offset, total))
+}
+
+/// Fetch every group linked at the category root in one call. Used by
+/// the UI's home rows and the clone-readiness probe, which expect the
+/// legacy `product_groups` shape rather than the shared page envelope.
+#[hdk_extern]
+pub fn get_all_category_products(category: String) -> ExternResult<CategorizedProducts> {
+    let query = CategoryQuery {
+        category,
+        subcategory: None,
+        product_type: None,
+        page: PageRequest {
+            cursor: None,
+            limit: Some(usize::MAX),
+        },
+    };
+    let links = group_links_for_path(&query)?;
+    let total_products: usize = links.iter().map(|link| count_from_tag(&link.tag)).sum();
+    let page = get_products_by_category(query)?;
+    Ok(CategorizedProducts {
+        product_groups: page.items,
+        total_products,
+        has_more: page.has_more,
+    })
+}
+
+/// Per-group product counts for a path, in link order, so the frontend
+/// can compute group boundaries for virtual scrolling without fetching
+/// the groups themselves.
+#[hdk_extern]
+pub fn get_all_group_counts_for_path(query: CategoryQuery) -> ExternResult<Vec<usize>> {
+    let links = group_links_for_path(&query)?;
+    Ok(links.iter().map(|link| count_from_tag(&link.tag)).collect())
+}
@@ -0,0 +1,92 @@
+use hdk::prelude::*;
+use products_integrity::*;
+use summon_types::SummonError;
+
+/// Properties this catalog network was installed with.
+pub(crate) fn dna_properties() -> ExternResult<DnaProperties> {
+    Ok(DnaProperties::try_from(dna_info()?.modifiers.properties).unwrap_or_default())
+}
+
+/// Readable coordinator-side gate for import-level operations; the same
+/// check is enforced again in validation.
+pub(crate) fn require_admin() -> ExternResult<()> {
+    if !dna_properties()?.is_admin(&agent_info()?.agent_initial_pubkey) {
+        return Err(SummonError::not_authorized(
+            "Only admin agents may import or remove product groups",
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// The complete curated state of one product: everything a curator is
+/// allowed to touch, sent whole so clearing a field (dropping a promo
+/// price, say) is just sending `None`.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct ProductEdit {
+    pub name: String,
+    pub size: String,
+    #[serde(default)]
+    pub brand: Option<String>,
+    #[serde(alias = "imageUrl")]
+    #[serde(default)]
+    pub image_url: Option<String>,
+    #[serde(alias = "isOrganic")]
+    pub is_organic: bool,
+    #[serde(alias = "promoPrice")]
+    #[serde(default)]
+    pub promo_price: Option<f64>,
+    #[serde(alias = "stocksStatus")]
+    #[serde(default)]
+    pub stocks_status: Option<String>,
+    #[serde(default)]
+    pub aisle: Option<String>,
+    #[serde(default)]
+    pub shelf: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct UpdateProductInput {
+    #[serde(alias = "groupHash")]
+    pub group_hash: ActionHash,
+    pub index: u32,
+    pub edit: ProductEdit,
+}
+
+/// Edit one product in place: metadata, shelf location, stock status
+/// and promo price. Open to admins and curators; the price, ids and
+/// categorization stay pinned to the import feed, enforced again in
+/// validation.
+#[hdk_extern]
+pub fn update_product(input: UpdateProductInput) -> ExternResult<ActionHash> {
+    let agent = agent_info()?.agent_initial_pubkey;
+    if !dna_properties()?.may_curate(&agent) {
+        return Err(
+            SummonError::not_authorized("Only admin and curator agents may edit products").into(),
+        );
+    }
+
+    let (newest_hash, mut group) =
+        crate::product::latest_group_revision(input.group_hash)?
+            .ok_or(SummonError::not_found("ProductGroup"))?;
+    let product = group.products.get_mut(input.index as usize).ok_or(
+        SummonError::invalid_input(
+            "index",
+            format!("group has no product at index {}", input.index),
+        ),
+    )?;
+
+    product.name = input.edit.name;
+    product.size = input.edit.size;
+    product.brand = input.edit.brand;
+    product.image_url = input.edit.image_url;
+    product.is_organic = input.edit.is_organic;
+    product.promo_price = input.edit.promo_price;
+    product.stocks_status = input.edit.stocks_status;
+    product.aisle = input.edit.aisle;
+    product.shelf = input.edit.shelf;
+
+    update_entry(newest_hash, &EntryTypes::ProductGroup(group))
+}
@@ -0,0 +1,369 @@
+//! Dev-gated demo catalog seeding, so new contributors and scenario
+//! tests can exercise category browsing without a private import feed.
+//! Only available when the DNA's properties set `enable_dev_seeding`;
+//! production networks never expose it.
+
+use hdk::prelude::*;
+use products_integrity::*;
+use summon_types::{DemoRng, FeatureFlags, SummonError};
+
+use crate::product::{create_product_batch, ProductInput};
+
+/// One demo subcategory: its name and the product nouns it draws from.
+type DemoSubcategory = (&'static str, &'static [&'static str]);
+
+/// Demo categories with their subcategories and the product nouns each
+/// one draws from.
+const DEMO_CATALOG: &[(&str, &[DemoSubcategory])] = &[
+    (
+        "Produce",
+        &[
+            ("Fresh Fruits", &["Apples", "Bananas", "Strawberries", "Grapes"]),
+            ("Fresh Vegetables", &["Carrots", "Spinach", "Broccoli", "Peppers"]),
+        ],
+    ),
+    (
+        "Dairy & Eggs",
+        &[
+            ("Milk", &["Whole Milk", "2% Milk", "Oat Milk"]),
+            ("Cheese", &["Cheddar", "Mozzarella", "Feta"]),
+        ],
+    ),
+    (
+        "Beverages",
+        &[
+            ("Juice", &["Orange Juice", "Apple Juice", "Lemonade"]),
+            ("Sparkling Water", &["Lime Seltzer", "Berry Seltzer"]),
+        ],
+    ),
+    (
+        "Snacks",
+        &[
+            ("Chips", &["Tortilla Chips", "Potato Chips", "Pita Chips"]),
+            ("Crackers", &["Wheat Crackers", "Rice Crackers"]),
+        ],
+    ),
+    (
+        "Bakery",
+        &[
+            ("Bread", &["Sourdough", "Whole Wheat Loaf", "Baguette"]),
+            ("Pastries", &["Croissants", "Cinnamon Rolls"]),
+        ],
+    ),
+];
+
+const DEMO_BRANDS: &[&str] = &[
+    "Orchard Lane",
+    "Green Meadow",
+    "Sunrise Farms",
+    "Pantry Co",
+    "Harvest Mill",
+];
+
+const DEMO_SIZES: &[&str] = &["12 oz", "16 oz", "1 lb", "2 lb", "6 pack"];
+
+/// How many groups each subcategory gets and how many products go in a
+/// group, unless the spec says otherwise.
+const DEFAULT_GROUPS_PER_SUBCATEGORY: usize = 2;
+const DEFAULT_PRODUCTS_PER_GROUP: usize = 8;
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct DemoSeedSpec {
+    /// How many of the demo categories to seed; defaults to all of
+    /// them.
+    #[serde(default)]
+    pub categories: Option<usize>,
+    #[serde(default)]
+    pub groups_per_subcategory: Option<usize>,
+    #[serde(default)]
+    pub products_per_group: Option<usize>,
+    /// Seed for the pseudo-random prices and flags, so two runs with
+    /// the same spec produce the same catalog.
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct DemoSeedReport {
+    pub categories: usize,
+    pub product_groups: usize,
+    pub products: usize,
+    pub group_hashes: Vec<ActionHash>,
+}
+
+fn require_dev_seeding() -> ExternResult<()> {
+    let flags =
+        FeatureFlags::try_from(dna_info()?.modifiers.properties).unwrap_or_default();
+    if flags.enable_dev_seeding {
+        Ok(())
+    } else {
+        Err(SummonError::feature_disabled("dev_seeding").into())
+    }
+}
+
+fn demo_product(
+    rng: &mut DemoRng,
+    category: &str,
+    subcategory: &str,
+    noun: &str,
+    position: usize,
+) -> Product {
+    let brand = DEMO_BRANDS[rng.below(DEMO_BRANDS.len())];
+    // Whole cents between $0.99 and $15.99, skewed low like a real
+    // shelf.
+    let price = 0.99 + rng.below(16) as f64 + rng.below(100) as f64 / 100.0;
+    let price = (price.min(15.99) * 100.0).round() / 100.0;
+    let on_promo = rng.below(5) == 0;
+    let is_organic = category == "Produce" && rng.below(3) == 0;
+
+    Product {
+        name: format!("{} {}", brand, noun),
+        price,
+        promo_price: on_promo.then(|| (price * 0.8 * 100.0).round() / 100.0),
+        size: DEMO_SIZES[rng.below(DEMO_SIZES.len())].to_string(),
+        stocks_status: Some("HIGH".to_string()),
+        category: category.to_string(),
+        subcategory: Some(subcategory.to_string()),
+        product_type: None,
+        image_url: None,
+        sold_by: Some("UNIT".to_string()),
+        product_id: format!(
+            "demo-{}-{}-{}",
+            category.to_lowercase().replace(' ', "-"),
+            subcategory.to_lowercase().replace(' ', "-"),
+            position
+        ),
+        upc: None,
+        embedding: None,
+        brand: Some(brand.to_string()),
+        is_organic,
+        store_id: None,
+        aisle: None,
+        shelf: None,
+        age_restricted: false,
+    }
+}
+
+/// One product of a canned fixture: name, brand, price, size. Every
+/// other field is derived deterministically.
+type FixtureProduct = (&'static str, &'static str, f64, &'static str);
+
+/// One group of a canned fixture: category, subcategory, products.
+type FixtureGroup = (&'static str, &'static str, &'static [FixtureProduct]);
+
+/// Canned catalog subsets for integration tests. Content is fully
+/// literal — no randomness, no clock reads — so the group *entry*
+/// hashes are identical on every run and every agent. (Action hashes
+/// still differ per author and commit time; tests should assert on the
+/// entry hashes.)
+const FIXTURES: &[(&str, &[FixtureGroup])] = &[
+    (
+        "produce-basics",
+        &[
+            (
+                "Produce",
+                "Fresh Fruits",
+                &[
+                    ("Fuji Apples", "Orchard Lane", 1.49, "1 lb"),
+                    ("Bananas", "Sunrise Farms", 0.69, "1 lb"),
+                    ("Strawberries", "Green Meadow", 3.99, "16 oz"),
+                ],
+            ),
+            (
+                "Produce",
+                "Fresh Vegetables",
+                &[
+                    ("Carrots", "Harvest Mill", 1.29, "2 lb"),
+                    ("Baby Spinach", "Green Meadow", 2.99, "12 oz"),
+                ],
+            ),
+        ],
+    ),
+    (
+        "pantry-mini",
+        &[
+            (
+                "Snacks",
+                "Chips",
+                &[
+                    ("Tortilla Chips", "Pantry Co", 2.79, "12 oz"),
+                    ("Potato Chips", "Pantry Co", 2.49, "12 oz"),
+                ],
+            ),
+            (
+                "Beverages",
+                "Juice",
+                &[("Orange Juice", "Sunrise Farms", 4.29, "6 pack")],
+            ),
+        ],
+    ),
+];
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct ImportedFixtureGroup {
+    pub category: String,
+    pub subcategory: String,
+    /// Stable across runs and agents; what tests assert on.
+    pub entry_hash: EntryHash,
+    /// What the category links point at; differs per run.
+    pub action_hash: ActionHash,
+    pub products: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct FixtureReport {
+    pub fixture_id: String,
+    pub groups: Vec<ImportedFixtureGroup>,
+}
+
+fn fixture_product(
+    fixture_id: &str,
+    position: usize,
+    category: &str,
+    subcategory: &str,
+    (name, brand, price, size): FixtureProduct,
+) -> Product {
+    Product {
+        name: format!("{} {}", brand, name),
+        price,
+        promo_price: None,
+        size: size.to_string(),
+        stocks_status: Some("HIGH".to_string()),
+        category: category.to_string(),
+        subcategory: Some(subcategory.to_string()),
+        product_type: None,
+        image_url: None,
+        sold_by: Some("UNIT".to_string()),
+        product_id: format!("fixture-{}-{}", fixture_id, position),
+        upc: None,
+        embedding: None,
+        brand: Some(brand.to_string()),
+        is_organic: false,
+        store_id: None,
+        aisle: None,
+        shelf: None,
+        age_restricted: false,
+    }
+}
+
+/// Import one of the canned test fixtures. Same dev gate as
+/// `seed_demo_data`; unknown fixture ids fail rather than silently
+/// seeding nothing.
+#[hdk_extern]
+pub fn import_fixture(fixture_id: String) -> ExternResult<FixtureReport> {
+    require_dev_seeding()?;
+    let (_, fixture_groups) = FIXTURES
+        .iter()
+        .find(|(id, _)| *id == fixture_id)
+        .ok_or(SummonError::not_found(format!(
+            "fixture \"{}\"",
+            fixture_id
+        )))?;
+
+    let mut groups = Vec::new();
+    let mut position = 0;
+    for (category, subcategory, products) in fixture_groups.iter() {
+        let inputs: Vec<ProductInput> = products
+            .iter()
+            .map(|product| {
+                let input = ProductInput {
+                    product: fixture_product(
+                        &fixture_id,
+                        position,
+                        category,
+                        subcategory,
+                        *product,
+                    ),
+                    main_category: category.to_string(),
+                    subcategory: Some(subcategory.to_string()),
+                    product_type: None,
+                    additional_categorizations: Vec::new(),
+                };
+                position += 1;
+                input
+            })
+            .collect();
+        for record in create_product_batch(inputs)? {
+            let entry_hash = record
+                .action()
+                .entry_hash()
+                .ok_or(SummonError::not_found(
+                    "entry hash of the imported ProductGroup",
+                ))?
+                .clone();
+            groups.push(ImportedFixtureGroup {
+                category: category.to_string(),
+                subcategory: subcategory.to_string(),
+                entry_hash,
+                action_hash: record.action_address().clone(),
+                products: products.len(),
+            });
+        }
+    }
+
+    Ok(FixtureReport {
+        fixture_id,
+        groups,
+    })
+}
+
+/// Seed a demo catalog with realistic category and price
+/// distributions. Deterministic for a given spec, so scenario tests can
+/// rerun it and see the same products.
+#[hdk_extern]
+pub fn seed_demo_data(spec: DemoSeedSpec) -> ExternResult<DemoSeedReport> {
+    require_dev_seeding()?;
+
+    let category_count = spec.categories.unwrap_or(DEMO_CATALOG.len()).clamp(1, DEMO_CATALOG.len());
+    let groups_per_subcategory = spec
+        .groups_per_subcategory
+        .unwrap_or(DEFAULT_GROUPS_PER_SUBCATEGORY)
+        .max(1);
+    let products_per_group = spec
+        .products_per_group
+        .unwrap_or(DEFAULT_PRODUCTS_PER_GROUP)
+        .clamp(1, MAX_GROUP_SIZE);
+    let mut rng = DemoRng::new(spec.seed.unwrap_or(0));
+
+    let mut group_hashes = Vec::new();
+    let mut products = 0;
+    for (category, subcategories) in DEMO_CATALOG.iter().take(category_count) {
+        for (subcategory, nouns) in subcategories.iter() {
+            for group_index in 0..groups_per_subcategory {
+                let inputs: Vec<ProductInput> = (0..products_per_group)
+                    .map(|position| {
+                        let noun = nouns[rng.below(nouns.len())];
+                        ProductInput {
+                            product: demo_product(
+                                &mut rng,
+                                category,
+                                subcategory,
+                                noun,
+                                group_index * products_per_group + position,
+                            ),
+                            main_category: category.to_string(),
+                            subcategory: Some(subcategory.to_string()),
+                            product_type: None,
+                            additional_categorizations: Vec::new(),
+                        }
+                    })
+                    .collect();
+                products += inputs.len();
+                for record in create_product_batch(inputs)? {
+                    group_hashes.push(record.action_address().clone());
+                }
+            }
+        }
+    }
+
+    Ok(DemoSeedReport {
+        categories: category_count,
+        product_groups: group_hashes.len(),
+        products,
+        group_hashes,
+    })
+}
@@ -0,0 +1,87 @@
+use hdk::prelude::*;
+use products_integrity::*;
+use summon_types::{ChunkTag, LinkTagCodec};
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct UploadImageInput {
+    #[serde(alias = "productId")]
+    pub product_id: String,
+    #[serde(alias = "mimeType")]
+    pub mime_type: String,
+    pub chunks: Vec<SerializedBytes>,
+}
+
+/// Store a product image as a metadata entry plus one `ImageChunk` per
+/// chunk, with the chunk index in the link tag so chunks can be
+/// reassembled in order.
+#[hdk_extern]
+pub fn upload_product_image(input: UploadImageInput) -> ExternResult<ActionHash> {
+    let image = ProductImage {
+        product_id: input.product_id,
+        mime_type: input.mime_type,
+        chunk_count: input.chunks.len() as u32,
+    };
+    let image_hash = create_entry(&EntryTypes::ProductImage(image))?;
+
+    for (index, bytes) in input.chunks.into_iter().enumerate() {
+        let chunk_hash = create_entry(&EntryTypes::ImageChunk(ImageChunk { bytes }))?;
+        create_link(
+            image_hash.clone(),
+            chunk_hash,
+            LinkTypes::ImageToChunk,
+            ChunkTag(index as u32).encode(),
+        )?;
+    }
+
+    Ok(image_hash)
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct ProductImageData {
+    pub image: ProductImage,
+    pub chunks: Vec<SerializedBytes>,
+}
+
+#[hdk_extern]
+pub fn get_product_image(image_hash: ActionHash) -> ExternResult<Option<ProductImageData>> {
+    let Some(record) = get(image_hash.clone(), GetOptions::default())? else {
+        return Ok(None);
+    };
+    let Some(image) = record
+        .entry()
+        .to_app_option::<ProductImage>()
+        .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+    else {
+        return Ok(None);
+    };
+
+    let links = get_links(
+        GetLinksInputBuilder::try_new(image_hash, LinkTypes::ImageToChunk)?.build(),
+    )?;
+
+    let mut indexed: Vec<(u32, SerializedBytes)> = Vec::new();
+    for link in links {
+        let index = ChunkTag::decode(&link.tag).map_or(0, |ChunkTag(index)| index);
+        let Some(hash) = link.target.into_action_hash() else {
+            continue;
+        };
+        let Some(chunk_record) = get(hash, GetOptions::default())? else {
+            continue;
+        };
+        if let Some(chunk) = chunk_record
+            .entry()
+            .to_app_option::<ImageChunk>()
+            .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        {
+            indexed.push((index, chunk.bytes));
+        }
+    }
+    indexed.sort_by_key(|(index, _)| *index);
+
+    Ok(Some(ProductImageData {
+        image,
+        chunks: indexed.into_iter().map(|(_, bytes)| bytes).collect(),
+    }))
+}
@@ -0,0 +1,137 @@
+use hdk::prelude::*;
+use products_integrity::*;
+
+use crate::category::{count_from_tag, link_count_tag};
+
+/// Anchor path for one brand's groups. Brands are indexed lowercase so
+/// feed casing differences don't split an anchor.
+pub(crate) fn brand_path(brand: &str) -> ExternResult<TypedPath> {
+    Path::from(format!("brands.{}", brand.to_lowercase())).typed(LinkTypes::BrandToGroup)
+}
+
+/// Derived-index maintenance, run by the conductor after the committing
+/// extern has already returned. Imports don't pay for these link writes,
+/// and an index failure can't fail the import itself — the category and
+/// search-anchor links the catalog depends on are still written inline
+/// by `create_product_batch`.
+#[hdk_extern(infallible)]
+pub fn post_commit(committed_actions: Vec<SignedActionHashed>) {
+    for signed in committed_actions {
+        // Infallible callback: a group whose indexing fails is simply
+        // absent from the brand index, never a chain error.
+        let _ = index_committed_action(&signed);
+    }
+}
+
+fn index_committed_action(signed: &SignedActionHashed) -> ExternResult<()> {
+    if !matches!(signed.action(), Action::Create(_) | Action::Update(_)) {
+        return Ok(());
+    }
+    let group_type: EntryType = UnitEntryTypes::ProductGroup.try_into()?;
+    if signed.action().entry_type() != Some(&group_type) {
+        return Ok(());
+    }
+    let Some(record) = get(signed.action_address().clone(), GetOptions::default())? else {
+        return Ok(());
+    };
+    let Some(group) = record
+        .entry()
+        .to_app_option::<ProductGroup>()
+        .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+    else {
+        return Ok(());
+    };
+    let group_hash = group_create_hash(record.action(), signed.action_address())?;
+    index_group_brands(&group, &group_hash)
+}
+
+/// The create hash a commit's group is addressed by — the hash all
+/// category links target — walking curator-edit update chains back to
+/// the original create.
+fn group_create_hash(action: &Action, action_hash: &ActionHash) -> ExternResult<ActionHash> {
+    let mut hash = action_hash.clone();
+    let mut current = action.clone();
+    while let Action::Update(update) = current {
+        hash = update.original_action_address.clone();
+        let Some(record) = get(hash.clone(), GetOptions::default())? else {
+            break;
+        };
+        current = record.action().clone();
+    }
+    Ok(hash)
+}
+
+/// Link a group from every brand anchor its products name, with the
+/// per-brand product count in the tag. Idempotent per anchor, so
+/// curator edits that re-commit a group don't duplicate links.
+pub(crate) fn index_group_brands(
+    group: &ProductGroup,
+    group_hash: &ActionHash,
+) -> ExternResult<()> {
+    let mut counts: std::collections::BTreeMap<String, usize> = std::collections::BTreeMap::new();
+    for product in &group.products {
+        let Some(brand) = product.brand.as_deref() else {
+            continue;
+        };
+        let brand = brand.trim();
+        if brand.is_empty() {
+            continue;
+        }
+        *counts.entry(brand.to_lowercase()).or_insert(0) += 1;
+    }
+
+    for (brand, count) in counts {
+        let path = brand_path(&brand)?;
+        path.ensure()?;
+        let existing = get_links(
+            GetLinksInputBuilder::try_new(path.path_entry_hash()?, LinkTypes::BrandToGroup)?
+                .build(),
+        )?;
+        if existing
+            .iter()
+            .any(|link| link.target.clone().into_action_hash().as_ref() == Some(group_hash))
+        {
+            continue;
+        }
+        create_link(
+            path.path_entry_hash()?,
+            group_hash.clone(),
+            LinkTypes::BrandToGroup,
+            link_count_tag(count),
+        )?;
+    }
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct BrandProducts {
+    pub product_groups: Vec<Record>,
+    pub total_products: usize,
+}
+
+/// Every group linked from a brand anchor, with the summed per-brand
+/// product count from the link tags. Case-insensitive.
+#[hdk_extern]
+pub fn get_products_by_brand(brand: String) -> ExternResult<BrandProducts> {
+    let path = brand_path(&brand)?;
+    let links = get_links(
+        GetLinksInputBuilder::try_new(path.path_entry_hash()?, LinkTypes::BrandToGroup)?.build(),
+    )?;
+
+    let total_products: usize = links.iter().map(|link| count_from_tag(&link.tag)).sum();
+    let mut product_groups = Vec::new();
+    for link in links {
+        let Some(hash) = link.target.into_action_hash() else {
+            continue;
+        };
+        if let Some(record) = crate::product::latest_group_record(hash)? {
+            product_groups.push(record);
+        }
+    }
+
+    Ok(BrandProducts {
+        product_groups,
+        total_products,
+    })
+}
@@ -0,0 +1,39 @@
+//! Product catalog coordinator zome (`product_catalog` in the DNA
+//! manifest).
+//!
+//! Products are uploaded in batches, grouped into `ProductGroup` entries
+//! per category path, and linked from category anchors so the UI can
+//! page through groups without downloading the whole catalog.
+
+mod category;
+mod curation;
+mod dev;
+mod image;
+mod index;
+mod metrics;
+mod product;
+mod scheduler;
+
+pub use category::*;
+pub use curation::*;
+pub use dev::*;
+pub use image::*;
+pub use index::*;
+pub use metrics::*;
+pub use product::*;
+pub use scheduler::*;
+
+use hdk::prelude::*;
+use products_integrity::*;
+
+/// Ensure the global search anchor exists up front, so the first import
+/// and the first search-index fetch don't race to create it. This zome
+/// exposes no remote-callable functions, so no cap grants are needed.
+#[hdk_extern]
+pub fn init(_: ()) -> ExternResult<InitCallbackResult> {
+    Path::from("all_products")
+        .typed(LinkTypes::AllProductsToGroup)?
+        .ensure()?;
+    schedule("run_due_jobs")?;
+    Ok(InitCallbackResult::Pass)
+}
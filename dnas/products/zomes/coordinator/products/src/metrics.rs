@@ -0,0 +1,26 @@
+//! Source-chain metrics for the calling agent, mostly to watch how
+//! much chain weight the importer accumulates across catalog uploads.
+
+use hdk::prelude::*;
+use products_integrity::*;
+
+pub use summon_types::AgentMetrics;
+
+fn entry_type_names() -> ExternResult<Vec<(EntryType, &'static str)>> {
+    Ok(vec![
+        (UnitEntryTypes::ProductGroup.try_into()?, "ProductGroup"),
+        (UnitEntryTypes::ProductImage.try_into()?, "ProductImage"),
+        (UnitEntryTypes::ImageChunk.try_into()?, "ImageChunk"),
+    ])
+}
+
+/// The caller's chain length, app-entry counts by type and approximate
+/// entry bytes written in this cell.
+#[hdk_extern]
+pub fn get_agent_metrics(_: ()) -> ExternResult<AgentMetrics> {
+    let records = query(ChainQueryFilter::new().include_entries(true))?;
+    Ok(summon_types::agent_metrics_from_records(
+        &records,
+        &entry_type_names()?,
+    ))
+}
@@ -0,0 +1,396 @@
+use hdk::prelude::*;
+use products_integrity::*;
+use summon_types::SummonError;
+
+use crate::category::{category_path, link_count_tag};
+
+/// One product from the import feed with its primary categorization and
+/// any additional category placements.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct ProductInput {
+    pub product: Product,
+    #[serde(alias = "mainCategory")]
+    pub main_category: String,
+    pub subcategory: Option<String>,
+    #[serde(alias = "productType")]
+    pub product_type: Option<String>,
+    #[serde(alias = "additionalCategorizations")]
+    pub additional_categorizations: Vec<AdditionalCategorization>,
+}
+
+pub use summon_types::ProductReference;
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct ProductsResponse {
+    pub products: Vec<Record>,
+}
+
+/// Create `ProductGroup` entries for a batch of products sharing a
+/// category path, linking each group from its primary path, any
+/// additional paths, and the global search anchor.
+#[hdk_extern]
+pub fn create_product_batch(inputs: Vec<ProductInput>) -> ExternResult<Vec<Record>> {
+    crate::curation::require_admin()?;
+    let mut records = Vec::new();
+
+    for chunk in inputs.chunks(MAX_GROUP_SIZE) {
+        let Some(first) = chunk.first() else {
+            continue;
+        };
+        let group = ProductGroup {
+            category: first.main_category.clone(),
+            subcategory: first.subcategory.clone(),
+            product_type: first.product_type.clone(),
+            additional_categorizations: first.additional_categorizations.clone(),
+            products: chunk.iter().map(|input| input.product.clone()).collect(),
+        };
+        let count = group.products.len();
+
+        let group_hash = create_entry(&EntryTypes::ProductGroup(group.clone()))?;
+        let record = get(group_hash.clone(), GetOptions::default())?
+            .ok_or(SummonError::not_found("the newly created ProductGroup"))?;
+
+        // Primary path plus category root, so whole-category reads don't
+        // have to walk subtrees.
+        for path in group_paths(&group)? {
+            path.ensure()?;
+            create_link(
+                path.path_entry_hash()?,
+                group_hash.clone(),
+                LinkTypes::CategoryToGroup,
+                link_count_tag(count),
+            )?;
+        }
+
+        let all_products = Path::from("all_products").typed(LinkTypes::AllProductsToGroup)?;
+        all_products.ensure()?;
+        create_link(
+            all_products.path_entry_hash()?,
+            group_hash,
+            LinkTypes::AllProductsToGroup,
+            link_count_tag(count),
+        )?;
+
+        records.push(record);
+    }
+
+    Ok(records)
+}
+
+/// All category paths a group should be linked from: the full primary
+/// path, the category root, and every additional categorization.
+pub(crate) fn group_paths(group: &ProductGroup) -> ExternResult<Vec<TypedPath>> {
+    let mut paths = vec![
+        category_path(&group.category, None, None)?,
+        category_path(
+            &group.category,
+            group.subcategory.as_deref(),
+            group.product_type.as_deref(),
+        )?,
+    ];
+    for additional in &group.additional_categorizations {
+        paths.push(category_path(
+            &additional.main_category,
+            additional.subcategory.as_deref(),
+            additional.product_type.as_deref(),
+        )?);
+    }
+    paths.dedup_by(|a, b| a.path == b.path);
+    Ok(paths)
+}
+
+/// The newest revision of a group, following the update chain from the
+/// create hash the category links point at. Curator edits land as
+/// updates, so group reads resolve through here.
+pub(crate) fn latest_group_record(group_hash: ActionHash) -> ExternResult<Option<Record>> {
+    let Some(details) = get_details(group_hash, GetOptions::default())? else {
+        return Ok(None);
+    };
+    let Details::Record(details) = details else {
+        return Ok(None);
+    };
+    let mut newest = details.record;
+    let mut updates = details.updates;
+    while let Some(update) = updates
+        .iter()
+        .max_by_key(|update| update.action().timestamp())
+        .cloned()
+    {
+        let Some(update_details) =
+            get_details(update.action_address().clone(), GetOptions::default())?
+        else {
+            break;
+        };
+        let Details::Record(update_details) = update_details else {
+            break;
+        };
+        newest = update_details.record;
+        updates = update_details.updates;
+    }
+    Ok(Some(newest))
+}
+
+/// [`latest_group_record`] decoded, with the revision's action hash for
+/// updates to chain from.
+pub(crate) fn latest_group_revision(
+    group_hash: ActionHash,
+) -> ExternResult<Option<(ActionHash, ProductGroup)>> {
+    let Some(record) = latest_group_record(group_hash)? else {
+        return Ok(None);
+    };
+    let group = record
+        .entry()
+        .to_app_option::<ProductGroup>()
+        .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?;
+    Ok(group.map(|group| (record.action_address().clone(), group)))
+}
+
+#[hdk_extern]
+pub fn get_product(hash: ActionHash) -> ExternResult<Option<Record>> {
+    get(hash, GetOptions::default())
+}
+
+#[hdk_extern]
+pub fn get_product_group(hash: ActionHash) -> ExternResult<Option<Record>> {
+    latest_group_record(hash)
+}
+
+#[hdk_extern]
+pub fn get_products_by_hashes(hashes: Vec<ActionHash>) -> ExternResult<ProductsResponse> {
+    let mut products = Vec::new();
+    for hash in hashes {
+        if let Some(record) = get(hash, GetOptions::default())? {
+            products.push(record);
+        }
+    }
+    Ok(ProductsResponse { products })
+}
+
+/// Fetch the groups containing the referenced products. The caller
+/// indexes into each group with `ProductReference::index`.
+#[hdk_extern]
+pub fn get_products_by_references(
+    references: Vec<ProductReference>,
+) -> ExternResult<ProductsResponse> {
+    let mut products = Vec::new();
+    for reference in references {
+        if let Some(record) = latest_group_record(reference.group_hash)? {
+            products.push(record);
+        }
+    }
+    Ok(ProductsResponse { products })
+}
+
+/// The catalog details an order needs to remember about a product.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct ResolvedProduct {
+    pub name: String,
+    /// Stable import-feed id, used to re-resolve references after a
+    /// catalog re-import replaces the groups.
+    pub product_id: String,
+    pub category: String,
+    pub price: f64,
+    pub promo_price: Option<f64>,
+    pub size: String,
+    pub image_url: Option<String>,
+    pub store_id: Option<String>,
+    pub aisle: Option<String>,
+    pub shelf: Option<String>,
+    pub age_restricted: bool,
+}
+
+/// Resolve each reference to the product's display details, or `None`
+/// when the group is missing or the index is out of range. Called over
+/// the bridge by the cart DNA to snapshot products into orders.
+#[hdk_extern]
+pub fn resolve_product_references(
+    references: Vec<ProductReference>,
+) -> ExternResult<Vec<Option<ResolvedProduct>>> {
+    let mut groups: std::collections::HashMap<ActionHash, Option<ProductGroup>> =
+        std::collections::HashMap::new();
+    let mut results = Vec::with_capacity(references.len());
+    for reference in references {
+        if !groups.contains_key(&reference.group_hash) {
+            let group = latest_group_revision(reference.group_hash.clone())?
+                .map(|(_, group)| group);
+            groups.insert(reference.group_hash.clone(), group);
+        }
+        let resolved = groups
+            .get(&reference.group_hash)
+            .and_then(|group| group.as_ref())
+            .and_then(|group| group.products.get(reference.index as usize))
+            .map(|product| ResolvedProduct {
+                name: product.name.clone(),
+                product_id: product.product_id.clone(),
+                category: product.category.clone(),
+                price: product.price,
+                promo_price: product.promo_price,
+                size: product.size.clone(),
+                image_url: product.image_url.clone(),
+                store_id: product.store_id.clone(),
+                aisle: product.aisle.clone(),
+                shelf: product.shelf.clone(),
+                age_restricted: product.age_restricted,
+            });
+        results.push(resolved);
+    }
+    Ok(results)
+}
+
+/// Resolve stable product ids back to current catalog references by
+/// scanning the all-products anchor, for remapping stale references
+/// after a re-import. Result entries align with the input ids.
+#[hdk_extern]
+pub fn find_products_by_ids(
+    product_ids: Vec<String>,
+) -> ExternResult<Vec<Option<ProductReference>>> {
+    let all_products = Path::from("all_products").typed(LinkTypes::AllProductsToGroup)?;
+    let links = get_links(
+        GetLinksInputBuilder::try_new(
+            all_products.path_entry_hash()?,
+            LinkTypes::AllProductsToGroup,
+        )?
+        .build(),
+    )?;
+
+    let mut found: std::collections::HashMap<String, ProductReference> =
+        std::collections::HashMap::new();
+    for link in links {
+        let Some(group_hash) = link.target.into_action_hash() else {
+            continue;
+        };
+        let Some((_, group)) = latest_group_revision(group_hash.clone())? else {
+            continue;
+        };
+        for (index, product) in group.products.iter().enumerate() {
+            if product_ids.contains(&product.product_id)
+                && !found.contains_key(&product.product_id)
+            {
+                found.insert(
+                    product.product_id.clone(),
+                    ProductReference {
+                        group_hash: group_hash.clone(),
+                        index: index as u32,
+                    },
+                );
+            }
+        }
+        if found.len() == product_ids.len() {
+            break;
+        }
+    }
+
+    Ok(product_ids
+        .iter()
+        .map(|product_id| found.get(product_id).cloned())
+        .collect())
+}
+
+/// Per-reference validity check: `true` when the group exists and the
+/// index is in range. Called over the bridge by the cart DNA before it
+/// publishes an order.
+#[hdk_extern]
+pub fn check_product_references(references: Vec<ProductReference>) -> ExternResult<Vec<bool>> {
+    let mut group_sizes: std::collections::HashMap<ActionHash, usize> =
+        std::collections::HashMap::new();
+    let mut results = Vec::with_capacity(references.len());
+    for reference in references {
+        let size = match group_sizes.get(&reference.group_hash) {
+            Some(size) => *size,
+            None => {
+                let size = latest_group_revision(reference.group_hash.clone())?
+                    .map(|(_, group)| group.products.len())
+                    .unwrap_or(0);
+                group_sizes.insert(reference.group_hash.clone(), size);
+                size
+            }
+        };
+        results.push((reference.index as usize) < size);
+    }
+    Ok(results)
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct SearchIndexResponse {
+    pub products: Vec<Record>,
+    pub total: usize,
+}
+
+/// Bulk fetch of every ProductGroup for building the client-side search
+/// index.
+#[hdk_extern]
+pub fn get_all_products_for_search_index(_: ()) -> ExternResult<SearchIndexResponse> {
+    let all_products = Path::from("all_products").typed(LinkTypes::AllProductsToGroup)?;
+    let links = get_links(
+        GetLinksInputBuilder::try_new(
+            all_products.path_entry_hash()?,
+            LinkTypes::AllProductsToGroup,
+        )?
+        .build(),
+    )?;
+
+    let mut total = 0;
+    let mut products = Vec::new();
+    for link in links {
+        total += crate::category::count_from_tag(&link.tag);
+        let Some(hash) = link.target.into_action_hash() else {
+            continue;
+        };
+        if let Some(record) = latest_group_record(hash)? {
+            products.push(record);
+        }
+    }
+
+    Ok(SearchIndexResponse { products, total })
+}
+
+/// Remove every category link pointing at a group, prior to re-importing
+/// the path it lives under.
+#[hdk_extern]
+pub fn delete_links_to_product_group(group_hash: ActionHash) -> ExternResult<u32> {
+    crate::curation::require_admin()?;
+    let record = get(group_hash.clone(), GetOptions::default())?
+        .ok_or(SummonError::not_found("ProductGroup"))?;
+    let group: ProductGroup = record
+        .entry()
+        .to_app_option()
+        .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        .ok_or(wasm_error!(WasmErrorInner::Guest(
+            "Record is not a ProductGroup".to_string()
+        )))?;
+
+    let mut deleted = 0;
+    for path in group_paths(&group)? {
+        let links = get_links(
+            GetLinksInputBuilder::try_new(path.path_entry_hash()?, LinkTypes::CategoryToGroup)?
+                .build(),
+        )?;
+        for link in links {
+            if link.target.clone().into_action_hash() == Some(group_hash.clone()) {
+                delete_link(link.create_link_hash)?;
+                deleted += 1;
+            }
+        }
+    }
+
+    let all_products = Path::from("all_products").typed(LinkTypes::AllProductsToGroup)?;
+    let links = get_links(
+        GetLinksInputBuilder::try_new(
+            all_products.path_entry_hash()?,
+            LinkTypes::AllProductsToGroup,
+        )?
+        .build(),
+    )?;
+    for link in links {
+        if link.target.clone().into_action_hash() == Some(group_hash.clone()) {
+            delete_link(link.create_link_hash)?;
+            deleted += 1;
+        }
+    }
+
+    Ok(deleted)
+}
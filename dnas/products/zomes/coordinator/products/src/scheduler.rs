@@ -0,0 +1,110 @@
+//! Recurring-job scheduler, the catalog-side twin of the cart zome's.
+//! One five-minute tick (`run_due_jobs`, registered from `init`) runs
+//! whatever the job table says is due, with a private [`JobRun`] entry
+//! per run carrying each job's own period.
+
+use std::collections::BTreeMap;
+
+use hdk::prelude::*;
+use products_integrity::*;
+use summon_types::ScheduledJob;
+
+const DAY_MS: u64 = 24 * 60 * 60_000;
+
+/// A job body: runs the work, reporting but not propagating errors
+/// upward (the scheduler records the attempt either way).
+type JobBody = fn() -> ExternResult<()>;
+
+/// The zome's recurring jobs. Job bodies swallow their own errors — a
+/// failing job is retried next period, never allowed to starve the
+/// jobs after it.
+const JOBS: &[(ScheduledJob, JobBody)] = &[(
+    ScheduledJob {
+        name: "reconcile_brand_index",
+        period_ms: DAY_MS,
+    },
+    reconcile_brand_index,
+)];
+
+/// Repair the derived brand index: `post_commit` indexing is
+/// best-effort, so a group whose brand links failed to write would
+/// otherwise stay missing from brand browsing forever. Idempotent —
+/// already-indexed groups are skipped by `index_group_brands`.
+fn reconcile_brand_index() -> ExternResult<()> {
+    let all_products = Path::from("all_products").typed(LinkTypes::AllProductsToGroup)?;
+    let links = get_links(
+        GetLinksInputBuilder::try_new(
+            all_products.path_entry_hash()?,
+            LinkTypes::AllProductsToGroup,
+        )?
+        .build(),
+    )?;
+    for link in links {
+        let Some(group_hash) = link.target.into_action_hash() else {
+            continue;
+        };
+        let Some(record) = get(group_hash.clone(), GetOptions::default())? else {
+            continue;
+        };
+        let Some(group) = record
+            .entry()
+            .to_app_option::<ProductGroup>()
+            .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        else {
+            continue;
+        };
+        crate::index::index_group_brands(&group, &group_hash)?;
+    }
+    Ok(())
+}
+
+/// When each job last ran, from the chain's [`JobRun`] entries.
+fn last_runs() -> ExternResult<BTreeMap<String, u64>> {
+    let filter = ChainQueryFilter::new()
+        .entry_type(UnitEntryTypes::JobRun.try_into()?)
+        .include_entries(true);
+    let mut last_runs = BTreeMap::new();
+    for record in query(filter)? {
+        if let Some(run) = record
+            .entry()
+            .to_app_option::<JobRun>()
+            .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        {
+            let newest = last_runs.entry(run.job).or_insert(0);
+            *newest = (*newest).max(run.ran_at);
+        }
+    }
+    Ok(last_runs)
+}
+
+pub(crate) fn run_due_jobs_inner() -> ExternResult<Vec<String>> {
+    let now = sys_time()?.as_millis() as u64;
+    let last_runs = last_runs()?;
+
+    let mut ran = Vec::new();
+    for (job, run) in JOBS {
+        if !job.due(now, last_runs.get(job.name).copied()) {
+            continue;
+        }
+        // The run is recorded whether or not the body succeeded, so a
+        // persistently failing job retries once per period instead of
+        // on every tick.
+        let _ = run();
+        create_entry(&EntryTypes::JobRun(JobRun {
+            job: job.name.to_string(),
+            ran_at: now,
+        }))?;
+        ran.push(job.name.to_string());
+    }
+    Ok(ran)
+}
+
+/// The scheduler tick. Registered once from `init`; also callable
+/// directly (payload `null`) so tests can force a pass without waiting
+/// out the cron.
+#[hdk_extern(infallible)]
+pub fn run_due_jobs(_: Option<Schedule>) -> Option<Schedule> {
+    let _ = run_due_jobs_inner();
+    // Every five minutes; per-job periods come from the table.
+    Some(Schedule::Persisted("0 */5 * * * *".to_string()))
+}
@@ -0,0 +1,315 @@
+use hdi::prelude::*;
+
+/// A secondary category placement for a product, e.g. a soda that also
+/// appears under "Beverages > Soft Drinks".
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct AdditionalCategorization {
+    pub main_category: String,
+    pub subcategory: Option<String>,
+    pub product_type: Option<String>,
+}
+
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct Product {
+    pub name: String,
+    pub price: f64,
+    pub promo_price: Option<f64>,
+    pub size: String,
+    pub stocks_status: Option<String>,
+    pub category: String,
+    pub subcategory: Option<String>,
+    pub product_type: Option<String>,
+    pub image_url: Option<String>,
+    pub sold_by: Option<String>,
+    /// Kroger product id. Field name matches the import feed.
+    #[serde(rename = "productId")]
+    pub product_id: String,
+    pub upc: Option<String>,
+    /// Embedding vector used by the client-side semantic search index.
+    pub embedding: Option<Vec<f32>>,
+    pub brand: Option<String>,
+    pub is_organic: bool,
+    /// Store/vendor this listing belongs to in multi-store catalogs.
+    /// Absent for single-store imports.
+    #[serde(default)]
+    pub store_id: Option<String>,
+    /// Store-layout location, e.g. aisle "12", shelf "B3". Absent when
+    /// the import feed carries no layout data.
+    #[serde(default)]
+    pub aisle: Option<String>,
+    #[serde(default)]
+    pub shelf: Option<String>,
+    /// Requires an ID check at handoff (alcohol, tobacco). Absent in
+    /// older feeds, which never carried restricted items.
+    #[serde(default)]
+    pub age_restricted: bool,
+}
+
+/// Products are stored in groups of up to [`MAX_GROUP_SIZE`] sharing the
+/// same category path, so category browsing fetches a handful of entries
+/// instead of thousands.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct ProductGroup {
+    pub category: String,
+    pub subcategory: Option<String>,
+    pub product_type: Option<String>,
+    pub additional_categorizations: Vec<AdditionalCategorization>,
+    pub products: Vec<Product>,
+}
+
+pub const MAX_GROUP_SIZE: usize = 1000;
+
+/// Metadata entry for a chunked product image upload.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct ProductImage {
+    pub product_id: String,
+    pub mime_type: String,
+    pub chunk_count: u32,
+}
+
+/// One chunk of image bytes, linked from its `ProductImage` with the
+/// chunk index encoded in the link tag.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct ImageChunk {
+    pub bytes: SerializedBytes,
+}
+
+/// Bookkeeping for one run of a scheduled job, so the scheduler can
+/// tell when each job last ran without separate storage. Private: job
+/// history is nobody else's business.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct JobRun {
+    pub job: String,
+    pub ran_at: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type")]
+#[hdk_entry_types]
+#[unit_enum(UnitEntryTypes)]
+pub enum EntryTypes {
+    ProductGroup(ProductGroup),
+    ProductImage(ProductImage),
+    ImageChunk(ImageChunk),
+    #[entry_type(visibility = "private")]
+    JobRun(JobRun),
+}
+
+#[derive(Serialize, Deserialize)]
+#[hdk_link_types]
+pub enum LinkTypes {
+    /// Category path -> ProductGroup. The product count is encoded in
+    /// the link tag as little-endian bytes.
+    CategoryToGroup,
+    /// "all_products" anchor -> ProductGroup, for the search index bulk
+    /// fetch.
+    AllProductsToGroup,
+    /// ProductImage -> ImageChunk, tag carries the 4-byte chunk index.
+    ImageToChunk,
+    /// "brands.<brand>" anchor -> ProductGroup, maintained by
+    /// `post_commit` after imports; the tag carries the group's
+    /// per-brand product count.
+    BrandToGroup,
+}
+
+/// Properties this DNA is installed with. Missing fields fall back to
+/// an unrestricted development network.
+#[derive(Clone, PartialEq, Default)]
+#[hdk_entry_helper]
+pub struct DnaProperties {
+    /// Agents allowed to run full catalog imports and remove groups.
+    /// Empty means unrestricted (development networks).
+    #[serde(default)]
+    pub admins: Vec<AgentPubKey>,
+    /// Store-staff curators: may edit product details in place but may
+    /// not import catalogs or delete groups.
+    #[serde(default)]
+    pub curators: Vec<AgentPubKey>,
+}
+
+impl DnaProperties {
+    /// Importer-level access. An empty admin list leaves the network
+    /// unrestricted, matching the other config defaults.
+    pub fn is_admin(&self, agent: &AgentPubKey) -> bool {
+        self.admins.is_empty() || self.admins.contains(agent)
+    }
+
+    /// Curation-level access: admins plus the listed curators.
+    pub fn may_curate(&self, agent: &AgentPubKey) -> bool {
+        self.is_admin(agent) || self.curators.contains(agent)
+    }
+}
+
+#[hdk_extern]
+pub fn genesis_self_check(_data: GenesisSelfCheckData) -> ExternResult<ValidateCallbackResult> {
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// Membrane check: the shared invite rules applied with this DNA's
+/// admin list, so the invite code that admits a customer to the cart
+/// network admits them to the catalog as well. Open networks (no
+/// admins) admit anyone, proof or not.
+pub fn validate_agent_joining(
+    agent: AgentPubKey,
+    membrane_proof: &Option<MembraneProof>,
+    joined_at: Timestamp,
+) -> ExternResult<ValidateCallbackResult> {
+    summon_types::validate_invite_membrane(
+        agent,
+        membrane_proof,
+        &properties()?.admins,
+        joined_at.as_millis() as u64,
+    )
+}
+
+fn properties() -> ExternResult<DnaProperties> {
+    Ok(DnaProperties::try_from(dna_info()?.modifiers.properties).unwrap_or_default())
+}
+
+pub fn validate_product_group(group: ProductGroup) -> ExternResult<ValidateCallbackResult> {
+    if group.products.is_empty() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "ProductGroup must contain at least one product".to_string(),
+        ));
+    }
+    if group.products.len() > MAX_GROUP_SIZE {
+        return Ok(ValidateCallbackResult::Invalid(format!(
+            "ProductGroup exceeds maximum size of {} products",
+            MAX_GROUP_SIZE
+        )));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// New groups are an import: admin-only once an admin list is
+/// configured.
+pub fn validate_product_group_create(
+    author: &AgentPubKey,
+) -> ExternResult<ValidateCallbackResult> {
+    if !properties()?.is_admin(author) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only admin agents may import product groups".to_string(),
+        ));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// Fields a curator may change on a product. Everything else — the
+/// price, ids, category placement and the age restriction — stays
+/// pinned to the import feed.
+fn curator_edit_allowed(original: &Product, edited: &Product) -> bool {
+    original.price == edited.price
+        && original.product_id == edited.product_id
+        && original.upc == edited.upc
+        && original.embedding == edited.embedding
+        && original.sold_by == edited.sold_by
+        && original.store_id == edited.store_id
+        && original.category == edited.category
+        && original.subcategory == edited.subcategory
+        && original.product_type == edited.product_type
+        && original.age_restricted == edited.age_restricted
+}
+
+/// Group updates are curation: admins may rewrite anything, curators
+/// may touch product metadata, shelf locations, stock status and
+/// promotions but not reshape the group or reprice it.
+pub fn validate_product_group_update(
+    original_action_hash: ActionHash,
+    new_group: &ProductGroup,
+    author: &AgentPubKey,
+) -> ExternResult<ValidateCallbackResult> {
+    let properties = properties()?;
+    if properties.is_admin(author) {
+        return Ok(ValidateCallbackResult::Valid);
+    }
+    if !properties.may_curate(author) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only admin and curator agents may edit product groups".to_string(),
+        ));
+    }
+
+    let original_record = must_get_valid_record(original_action_hash)?;
+    let original: ProductGroup = original_record
+        .entry()
+        .to_app_option()
+        .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        .ok_or(wasm_error!(WasmErrorInner::Guest(
+            "Updated record is not a ProductGroup".to_string()
+        )))?;
+
+    if new_group.category != original.category
+        || new_group.subcategory != original.subcategory
+        || new_group.product_type != original.product_type
+        || new_group.additional_categorizations != original.additional_categorizations
+        || new_group.products.len() != original.products.len()
+    {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Curators may edit products in place but not reshape or recategorize a group"
+                .to_string(),
+        ));
+    }
+    for (original, edited) in original.products.iter().zip(new_group.products.iter()) {
+        if !curator_edit_allowed(original, edited) {
+            return Ok(ValidateCallbackResult::Invalid(format!(
+                "Curators may not change the price, ids, categorization or age restriction \
+                 of {}",
+                original.product_id
+            )));
+        }
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+#[hdk_extern]
+pub fn validate(op: Op) -> ExternResult<ValidateCallbackResult> {
+    if let Op::StoreRecord(StoreRecord { record }) = &op {
+        if let Action::AgentValidationPkg(pkg) = record.action() {
+            return validate_agent_joining(
+                pkg.author.clone(),
+                &pkg.membrane_proof,
+                record.action().timestamp(),
+            );
+        }
+    }
+    match op.flattened::<EntryTypes, LinkTypes>()? {
+        FlatOp::StoreEntry(OpEntry::CreateEntry {
+            app_entry: EntryTypes::ProductGroup(group),
+            action,
+        }) => match validate_product_group(group)? {
+            ValidateCallbackResult::Valid => validate_product_group_create(&action.author),
+            invalid => Ok(invalid),
+        },
+        FlatOp::StoreEntry(OpEntry::UpdateEntry {
+            original_action_hash,
+            app_entry: EntryTypes::ProductGroup(group),
+            action,
+            ..
+        }) => match validate_product_group(group.clone())? {
+            ValidateCallbackResult::Valid => {
+                validate_product_group_update(original_action_hash, &group, &action.author)
+            }
+            invalid => Ok(invalid),
+        },
+        FlatOp::RegisterDeleteLink {
+            link_type: LinkTypes::CategoryToGroup | LinkTypes::AllProductsToGroup,
+            action,
+            ..
+        } => {
+            // Unlinking a group removes it from the catalog: import
+            // territory, not curation.
+            if !properties()?.is_admin(&action.author) {
+                return Ok(ValidateCallbackResult::Invalid(
+                    "Only admin agents may remove product groups from the catalog".to_string(),
+                ));
+            }
+            Ok(ValidateCallbackResult::Valid)
+        }
+        _ => Ok(ValidateCallbackResult::Valid),
+    }
+}
@@ -0,0 +1,451 @@
+//! Address book coordinator zome. Addresses live in the profiles DNA so
+//! they survive catalog clone rotation.
+
+use address_integrity::*;
+use hdk::prelude::*;
+use summon_types::{Page, PageRequest};
+
+/// Street-suffix and unit abbreviations folded to one spelling before
+/// comparing addresses, so "123 Main Street" and "123 Main St." count
+/// as the same place.
+const ABBREVIATIONS: &[(&str, &str)] = &[
+    ("street", "st"),
+    ("avenue", "ave"),
+    ("boulevard", "blvd"),
+    ("drive", "dr"),
+    ("road", "rd"),
+    ("lane", "ln"),
+    ("court", "ct"),
+    ("place", "pl"),
+    ("apartment", "apt"),
+    ("suite", "ste"),
+    ("north", "n"),
+    ("south", "s"),
+    ("east", "e"),
+    ("west", "w"),
+];
+
+fn normalize_part(part: &str) -> String {
+    part.split_whitespace()
+        .map(|word| {
+            let word = word
+                .to_lowercase()
+                .trim_matches(|c: char| c == '.' || c == ',')
+                .to_string();
+            ABBREVIATIONS
+                .iter()
+                .find(|(long, _)| *long == word)
+                .map(|(_, short)| short.to_string())
+                .unwrap_or(word)
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The comparison key two addresses must share to count as duplicates:
+/// normalized street, unit, city, state and postal code. Coordinates
+/// and labels are ignored — the same place geocoded twice is still the
+/// same place.
+fn dedup_key(address: &Address) -> String {
+    format!(
+        "{}|{}|{}|{}|{}",
+        normalize_part(&address.street),
+        normalize_part(address.unit.as_deref().unwrap_or("")),
+        normalize_part(&address.city),
+        normalize_part(&address.state),
+        address.zip.trim().to_lowercase().replace(' ', "")
+    )
+}
+
+/// Addresses are private entries, so their bytes only exist on the
+/// caller's own chain; resolve hashes through a chain query instead of
+/// the DHT.
+fn own_address_entries() -> ExternResult<Vec<(ActionHash, Address)>> {
+    let records = query(
+        ChainQueryFilter::new()
+            .entry_type(UnitEntryTypes::Address.try_into()?)
+            .include_entries(true),
+    )?;
+    let mut entries = Vec::new();
+    for record in records {
+        if let Some(address) = record
+            .entry()
+            .to_app_option::<Address>()
+            .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?
+        {
+            entries.push((record.action_address().clone(), address));
+        }
+    }
+    Ok(entries)
+}
+
+fn own_address(address_hash: &ActionHash) -> ExternResult<Option<Address>> {
+    Ok(own_address_entries()?
+        .into_iter()
+        .find(|(hash, _)| hash == address_hash)
+        .map(|(_, address)| address))
+}
+
+/// Save an address, unless the caller already has it: re-saving the
+/// same place (different case, whitespace or abbreviations included)
+/// returns the existing entry's hash instead of storing a copy.
+#[hdk_extern]
+pub fn create_address(address: Address) -> ExternResult<ActionHash> {
+    let key = dedup_key(&address);
+    for (existing_hash, existing) in get_addresses(())? {
+        if dedup_key(&existing) == key {
+            return Ok(existing_hash);
+        }
+    }
+
+    let agent = agent_info()?.agent_initial_pubkey;
+    let hash = create_entry(&EntryTypes::Address(address))?;
+    create_link(agent, hash.clone(), LinkTypes::AgentToAddress, ())?;
+    Ok(hash)
+}
+
+#[hdk_extern]
+pub fn get_addresses(_: ()) -> ExternResult<Vec<(ActionHash, Address)>> {
+    let agent = agent_info()?.agent_initial_pubkey;
+    let links = get_links(
+        GetLinksInputBuilder::try_new(agent, LinkTypes::AgentToAddress)?.build(),
+    )?;
+
+    // The agent links track which revision is current; the entries
+    // themselves come off the caller's own chain.
+    let entries = own_address_entries()?;
+    let mut addresses = Vec::new();
+    for link in links {
+        let Some(hash) = link.target.into_action_hash() else {
+            continue;
+        };
+        if let Some((_, address)) = entries.iter().find(|(entry_hash, _)| entry_hash == &hash) {
+            addresses.push((hash, address.clone()));
+        }
+    }
+    Ok(addresses)
+}
+
+/// Paged variant of `get_addresses`, in the page envelope the other
+/// listing externs share. The cursor is an offset.
+#[hdk_extern]
+pub fn get_addresses_page(
+    request: PageRequest,
+) -> ExternResult<Page<(ActionHash, Address)>> {
+    let addresses = get_addresses(())?;
+    let offset = request.offset();
+    let limit = request.limit_or(20);
+    let total = addresses.len();
+    let items: Vec<_> = addresses.into_iter().skip(offset).take(limit).collect();
+    Ok(Page::from_offset(items, offset, total))
+}
+
+#[hdk_extern]
+pub fn update_address(input: (ActionHash, Address)) -> ExternResult<ActionHash> {
+    let (previous_hash, address) = input;
+    let agent = agent_info()?.agent_initial_pubkey;
+
+    let new_hash = update_entry(previous_hash.clone(), &EntryTypes::Address(address))?;
+
+    // Repoint the agent link at the newest revision.
+    let links = get_links(
+        GetLinksInputBuilder::try_new(agent.clone(), LinkTypes::AgentToAddress)?.build(),
+    )?;
+    for link in links {
+        if link.target.clone().into_action_hash() == Some(previous_hash.clone()) {
+            delete_link(link.create_link_hash)?;
+        }
+    }
+    create_link(agent, new_hash.clone(), LinkTypes::AgentToAddress, ())?;
+    Ok(new_hash)
+}
+
+#[hdk_extern]
+pub fn delete_address(address_hash: ActionHash) -> ExternResult<ActionHash> {
+    let agent = agent_info()?.agent_initial_pubkey;
+    let links = get_links(
+        GetLinksInputBuilder::try_new(agent, LinkTypes::AgentToAddress)?.build(),
+    )?;
+    for link in links {
+        if link.target.clone().into_action_hash() == Some(address_hash.clone()) {
+            delete_link(link.create_link_hash)?;
+        }
+    }
+    delete_entry(address_hash)
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct AddressValidationResult {
+    pub valid: bool,
+    pub problems: Vec<String>,
+}
+
+/// Dry-run the address rules without committing anything, so the UI
+/// can surface every problem at entry time instead of the first one at
+/// checkout — or worse, at the shopper's door.
+#[hdk_extern]
+pub fn validate_address(address: Address) -> ExternResult<AddressValidationResult> {
+    let problems = address_problems(&address)?;
+    Ok(AddressValidationResult {
+        valid: problems.is_empty(),
+        problems,
+    })
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct AddressServiceCheck {
+    pub served: bool,
+    /// Why the address is not served, naming the served areas.
+    pub message: Option<String>,
+    /// Name of the matching service zone, for dispatch bucketing.
+    #[serde(default)]
+    pub zone: Option<String>,
+}
+
+/// Whether a saved address falls inside a configured service zone.
+/// Called over the bridge by the cart DNA at checkout, since addresses
+/// saved before a zone change may no longer be deliverable.
+#[hdk_extern]
+pub fn check_address_served(address_hash: ActionHash) -> ExternResult<AddressServiceCheck> {
+    let address = own_address(&address_hash)?.ok_or(wasm_error!(WasmErrorInner::Guest(
+        "Address not found".to_string()
+    )))?;
+
+    let message = service_zone_problem(&address.zip)?;
+    let zone = zone_for_zip(&address.zip)?;
+    Ok(AddressServiceCheck {
+        served: message.is_none(),
+        message,
+        zone,
+    })
+}
+
+/// The caller's complete address book, shaped for a file download so
+/// it can be re-imported on another device or network.
+#[hdk_extern]
+pub fn export_addresses(_: ()) -> ExternResult<Vec<Address>> {
+    Ok(get_addresses(())?
+        .into_iter()
+        .map(|(_, address)| address)
+        .collect())
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct ImportAddressesReport {
+    pub imported: u32,
+    /// Entries skipped because the caller already had the same place
+    /// saved, or the import listed it twice.
+    pub skipped: u32,
+}
+
+/// Restore an exported address book. Every entry runs through the same
+/// dedup as [`create_address`], so importing on top of an existing book
+/// (or importing the same file twice) never creates copies.
+#[hdk_extern]
+pub fn import_addresses(addresses: Vec<Address>) -> ExternResult<ImportAddressesReport> {
+    let mut seen: Vec<String> = get_addresses(())?
+        .iter()
+        .map(|(_, existing)| dedup_key(existing))
+        .collect();
+
+    let mut report = ImportAddressesReport {
+        imported: 0,
+        skipped: 0,
+    };
+    let agent = agent_info()?.agent_initial_pubkey;
+    for address in addresses {
+        let key = dedup_key(&address);
+        if seen.contains(&key) {
+            report.skipped += 1;
+            continue;
+        }
+        let hash = create_entry(&EntryTypes::Address(address))?;
+        create_link(agent.clone(), hash, LinkTypes::AgentToAddress, ())?;
+        seen.push(key);
+        report.imported += 1;
+    }
+    Ok(report)
+}
+
+/// Tag carried by the cap grant and claim for one shared address, with
+/// the address hash embedded so a grant never exposes more than the
+/// address it was made for.
+const SHARED_ADDRESS_TAG_PREFIX: &str = "shared-address:";
+
+fn share_tag(address_hash: &ActionHash) -> String {
+    format!(
+        "{}{}",
+        SHARED_ADDRESS_TAG_PREFIX,
+        ActionHashB64::from(address_hash.clone())
+    )
+}
+
+fn new_cap_secret() -> ExternResult<CapSecret> {
+    let bytes: [u8; CAP_SECRET_BYTES] = random_bytes(CAP_SECRET_BYTES as u32)?
+        .into_vec()
+        .try_into()
+        .map_err(|_| {
+            wasm_error!(WasmErrorInner::Guest(
+                "Failed to generate capability secret".to_string()
+            ))
+        })?;
+    Ok(bytes.into())
+}
+
+/// Pushed to the household member an address was shared with; their
+/// conductor stores the claim so [`get_shared_addresses`] can use it.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct AddressShareSignal {
+    pub address_hash: ActionHash,
+    pub secret: CapSecret,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct ShareAddressInput {
+    #[serde(alias = "addressHash")]
+    pub address_hash: ActionHash,
+    #[serde(alias = "withAgent")]
+    pub with_agent: AgentPubKey,
+}
+
+/// Grant another agent read access to one saved address, so shared-cart
+/// partners don't each have to retype the same home address. The grant
+/// is assigned to that agent alone and scoped to that address alone.
+#[hdk_extern]
+pub fn share_address(input: ShareAddressInput) -> ExternResult<()> {
+    if !get_addresses(())?
+        .iter()
+        .any(|(hash, _)| hash == &input.address_hash)
+    {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Can only share your own saved addresses".to_string()
+        )));
+    }
+
+    let secret = new_cap_secret()?;
+    let mut assignees = BTreeSet::new();
+    assignees.insert(input.with_agent.clone());
+    let mut functions = BTreeSet::new();
+    functions.insert((zome_info()?.name, FunctionName::from("get_shared_address")));
+    create_cap_grant(CapGrantEntry {
+        tag: share_tag(&input.address_hash),
+        access: CapAccess::Assigned { secret, assignees },
+        functions: GrantedFunctions::Listed(functions),
+    })?;
+
+    // Fire-and-forget, but the host call itself can fail transiently;
+    // a couple of retries keeps the share from silently never arriving.
+    let signal = AddressShareSignal {
+        address_hash: input.address_hash,
+        secret,
+    };
+    summon_types::with_retries(3, || {
+        send_remote_signal(&signal, vec![input.with_agent.clone()])
+    })
+}
+
+/// Stores the claim for an address shared with us and surfaces it to
+/// the UI. The grantor is whoever delivered the signal.
+#[hdk_extern]
+pub fn recv_remote_signal(signal: AddressShareSignal) -> ExternResult<()> {
+    let grantor = call_info()?.provenance;
+    create_cap_claim(CapClaim::new(
+        share_tag(&signal.address_hash),
+        grantor.clone(),
+        signal.secret,
+    ))?;
+    emit_signal((grantor, signal.address_hash))
+}
+
+/// Serves a shared address to the agent it was shared with. Callable
+/// remotely only through a matching [`share_address`] grant, which the
+/// tag check pins to this exact address.
+#[hdk_extern]
+pub fn get_shared_address(address_hash: ActionHash) -> ExternResult<Address> {
+    match call_info()?.cap_grant {
+        CapGrant::ChainAuthor(_) => {}
+        CapGrant::RemoteAgent(grant) if grant.tag == share_tag(&address_hash) => {}
+        _ => {
+            return Err(wasm_error!(WasmErrorInner::Guest(
+                "Address has not been shared with the caller".to_string()
+            )))
+        }
+    }
+
+    own_address(&address_hash)?.ok_or(wasm_error!(WasmErrorInner::Guest(
+        "Address not found".to_string()
+    )))
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct SharedAddress {
+    pub owner: AgentPubKey,
+    pub address_hash: ActionHash,
+    pub address: Address,
+}
+
+/// Every address other agents have shared with the caller, fetched
+/// live from their cells. Owners who are offline or have revoked the
+/// share are skipped.
+#[hdk_extern]
+pub fn get_shared_addresses(_: ()) -> ExternResult<Vec<SharedAddress>> {
+    let claims = query(
+        ChainQueryFilter::new()
+            .entry_type(EntryType::CapClaim)
+            .include_entries(true),
+    )?;
+
+    let mut shared = Vec::new();
+    for record in claims {
+        let Some(Entry::CapClaim(claim)) = record.entry().as_option() else {
+            continue;
+        };
+        let Some(encoded) = claim.tag.strip_prefix(SHARED_ADDRESS_TAG_PREFIX) else {
+            continue;
+        };
+        let Ok(address_hash) = ActionHashB64::from_b64_str(encoded) else {
+            continue;
+        };
+        let address_hash = ActionHash::from(address_hash);
+
+        let response = call_remote(
+            claim.grantor.clone(),
+            zome_info()?.name,
+            FunctionName::from("get_shared_address"),
+            Some(claim.secret),
+            address_hash.clone(),
+        );
+        let Ok(ZomeCallResponse::Ok(io)) = response else {
+            continue;
+        };
+        let Ok(address) = io.decode::<Address>() else {
+            continue;
+        };
+        shared.push(SharedAddress {
+            owner: claim.grantor.clone(),
+            address_hash,
+            address,
+        });
+    }
+    Ok(shared)
+}
+
+/// Grant every agent the capability to deliver remote signals to us;
+/// without this, share notifications are silently dropped.
+#[hdk_extern]
+pub fn init(_: ()) -> ExternResult<InitCallbackResult> {
+    let mut functions = BTreeSet::new();
+    functions.insert((zome_info()?.name, FunctionName::from("recv_remote_signal")));
+    create_cap_grant(CapGrantEntry {
+        tag: "remote-signals".to_string(),
+        access: CapAccess::Unrestricted,
+        functions: GrantedFunctions::Listed(functions),
+    })?;
+    Ok(InitCallbackResult::Pass)
+}
@@ -0,0 +1,139 @@
+//! Profiles coordinator zome: nickname and avatar per agent, so order
+//! lists, chat and shopper assignments can show who someone is instead
+//! of a raw key. One profile per agent, updated in place.
+
+use hdk::prelude::*;
+use profiles_integrity::*;
+use summon_types::{Page, PageRequest};
+
+fn all_profiles_anchor() -> ExternResult<TypedPath> {
+    Path::from("all_profiles").typed(LinkTypes::AllProfiles)
+}
+
+/// The caller's current profile link, if they have set one.
+fn own_profile_link() -> ExternResult<Option<Link>> {
+    let agent = agent_info()?.agent_initial_pubkey;
+    let links = get_links(
+        GetLinksInputBuilder::try_new(agent, LinkTypes::AgentToProfile)?.build(),
+    )?;
+    Ok(links
+        .into_iter()
+        .max_by_key(|link| link.timestamp))
+}
+
+/// Create the caller's profile, or update it in place when one exists.
+/// The agent link is repointed at the newest revision, so readers never
+/// have to walk update chains.
+#[hdk_extern]
+pub fn set_profile(profile: Profile) -> ExternResult<ActionHash> {
+    let agent = agent_info()?.agent_initial_pubkey;
+
+    if let Some(previous) = own_profile_link()? {
+        let previous_hash = previous
+            .target
+            .clone()
+            .into_action_hash()
+            .ok_or(wasm_error!(WasmErrorInner::Guest(
+                "Profile link does not point at an action".to_string()
+            )))?;
+        let new_hash = update_entry(previous_hash, &EntryTypes::Profile(profile))?;
+        delete_link(previous.create_link_hash)?;
+        create_link(agent, new_hash.clone(), LinkTypes::AgentToProfile, ())?;
+        return Ok(new_hash);
+    }
+
+    let hash = create_entry(&EntryTypes::Profile(profile))?;
+    create_link(agent.clone(), hash.clone(), LinkTypes::AgentToProfile, ())?;
+    let anchor = all_profiles_anchor()?;
+    anchor.ensure()?;
+    create_link(anchor.path_entry_hash()?, agent, LinkTypes::AllProfiles, ())?;
+    Ok(hash)
+}
+
+/// An agent's profile record, or `None` when they haven't set one.
+#[hdk_extern]
+pub fn get_agent_profile(agent: AgentPubKey) -> ExternResult<Option<Record>> {
+    let links = get_links(
+        GetLinksInputBuilder::try_new(agent, LinkTypes::AgentToProfile)?.build(),
+    )?;
+    let Some(link) = links.into_iter().max_by_key(|link| link.timestamp) else {
+        return Ok(None);
+    };
+    let Some(hash) = link.target.into_action_hash() else {
+        return Ok(None);
+    };
+    get(hash, GetOptions::default())
+}
+
+#[hdk_extern]
+pub fn get_my_profile(_: ()) -> ExternResult<Option<Record>> {
+    get_agent_profile(agent_info()?.agent_initial_pubkey)
+}
+
+/// Batch lookup for rendering lists: one result per input agent, in
+/// input order, `None` for agents without a profile.
+#[hdk_extern]
+pub fn get_agent_profiles(agents: Vec<AgentPubKey>) -> ExternResult<Vec<Option<Record>>> {
+    let mut profiles = Vec::with_capacity(agents.len());
+    for agent in agents {
+        profiles.push(get_agent_profile(agent)?);
+    }
+    Ok(profiles)
+}
+
+/// Every profile on the network, resolved to its newest revision via
+/// each agent's own link.
+#[hdk_extern]
+pub fn get_all_profiles(_: ()) -> ExternResult<Vec<Record>> {
+    let anchor = all_profiles_anchor()?;
+    let links = get_links(
+        GetLinksInputBuilder::try_new(anchor.path_entry_hash()?, LinkTypes::AllProfiles)?.build(),
+    )?;
+    let mut profiles = Vec::new();
+    for link in links {
+        let Some(agent) = link.target.into_agent_pub_key() else {
+            continue;
+        };
+        if let Some(record) = get_agent_profile(agent)? {
+            profiles.push(record);
+        }
+    }
+    Ok(profiles)
+}
+
+/// Paged variant of `get_all_profiles`, in the page envelope the other
+/// listing externs share. The cursor is an offset into the anchor's
+/// agent links.
+#[hdk_extern]
+pub fn get_all_profiles_page(request: PageRequest) -> ExternResult<Page<Record>> {
+    let anchor = all_profiles_anchor()?;
+    let links = get_links(
+        GetLinksInputBuilder::try_new(anchor.path_entry_hash()?, LinkTypes::AllProfiles)?.build(),
+    )?;
+    let offset = request.offset();
+    let limit = request.limit_or(20);
+    let total = links.len();
+
+    let mut profiles = Vec::new();
+    for link in links.into_iter().skip(offset).take(limit) {
+        let Some(agent) = link.target.into_agent_pub_key() else {
+            continue;
+        };
+        if let Some(record) = get_agent_profile(agent)? {
+            profiles.push(record);
+        }
+    }
+    Ok(Page::from_offset(profiles, offset, total))
+}
+
+/// Dry-run the profile rules so the UI can surface problems at entry
+/// time instead of at commit.
+#[hdk_extern]
+pub fn validate_profile_input(profile: Profile) -> ExternResult<Vec<String>> {
+    Ok(profile_problems(&profile))
+}
+
+#[hdk_extern]
+pub fn whoami(_: ()) -> ExternResult<AgentPubKey> {
+    Ok(agent_info()?.agent_initial_pubkey)
+}
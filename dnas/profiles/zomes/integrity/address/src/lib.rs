@@ -0,0 +1,203 @@
+use hdi::prelude::*;
+
+/// A saved delivery address.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct Address {
+    pub street: String,
+    pub unit: Option<String>,
+    pub city: String,
+    pub state: String,
+    pub zip: String,
+    /// ISO country code, e.g. "US". Absent on addresses saved before
+    /// the field existed; postal-code rules then don't apply.
+    #[serde(default)]
+    pub country: Option<String>,
+    pub lat: f64,
+    pub lng: f64,
+    pub is_default: bool,
+    pub label: Option<String>,
+}
+
+/// Accepted postal-code shapes for one country, read from DNA
+/// properties. In a format, `#` matches a digit, `A` a letter, and any
+/// other character must appear literally — e.g. "#####" and
+/// "#####-####" for the US, "A#A #A#" for Canada.
+#[derive(Clone, PartialEq, Default)]
+#[hdk_entry_helper]
+pub struct PostalCodeRule {
+    pub country: String,
+    pub formats: Vec<String>,
+}
+
+/// One served delivery area, defined by postal-code prefixes: an
+/// address is inside the zone when its postal code starts with any of
+/// them.
+#[derive(Clone, PartialEq, Default)]
+#[hdk_entry_helper]
+pub struct ServiceZone {
+    /// Display name, e.g. "Portland metro".
+    pub name: String,
+    pub postal_prefixes: Vec<String>,
+}
+
+/// Properties this DNA is installed with. Missing fields fall back to
+/// defaults (no postal-code rules, every area served).
+#[derive(Clone, PartialEq, Default)]
+#[hdk_entry_helper]
+pub struct DnaProperties {
+    #[serde(default)]
+    pub postal_code_rules: Vec<PostalCodeRule>,
+    /// Configured service areas; empty means no zone restriction.
+    #[serde(default)]
+    pub service_zones: Vec<ServiceZone>,
+}
+
+fn matches_format(format: &str, value: &str) -> bool {
+    let format: Vec<char> = format.chars().collect();
+    let value: Vec<char> = value.chars().collect();
+    if format.len() != value.len() {
+        return false;
+    }
+    format.iter().zip(&value).all(|(mask, got)| match mask {
+        '#' => got.is_ascii_digit(),
+        'A' => got.is_ascii_alphabetic(),
+        literal => literal == got,
+    })
+}
+
+/// The configured service zone a postal code falls in, by prefix
+/// match. `None` when no zones are configured or none match.
+pub fn zone_for_zip(zip: &str) -> ExternResult<Option<String>> {
+    let properties = DnaProperties::try_from(dna_info()?.modifiers.properties).unwrap_or_default();
+    let zip = zip.trim().to_lowercase().replace(' ', "");
+    Ok(properties
+        .service_zones
+        .iter()
+        .find(|zone| {
+            zone.postal_prefixes
+                .iter()
+                .any(|prefix| zip.starts_with(&prefix.trim().to_lowercase().replace(' ', "")))
+        })
+        .map(|zone| zone.name.clone()))
+}
+
+/// Whether a postal code falls inside a configured service zone.
+/// `None` when it is served (or no zones are configured); otherwise a
+/// message listing the served areas.
+pub fn service_zone_problem(zip: &str) -> ExternResult<Option<String>> {
+    let properties = DnaProperties::try_from(dna_info()?.modifiers.properties).unwrap_or_default();
+    if properties.service_zones.is_empty() {
+        return Ok(None);
+    }
+
+    let zip = zip.trim().to_lowercase().replace(' ', "");
+    let served = properties.service_zones.iter().any(|zone| {
+        zone.postal_prefixes
+            .iter()
+            .any(|prefix| zip.starts_with(&prefix.trim().to_lowercase().replace(' ', "")))
+    });
+    if served {
+        return Ok(None);
+    }
+
+    let areas: Vec<String> = properties
+        .service_zones
+        .iter()
+        .map(|zone| format!("{} ({})", zone.name, zone.postal_prefixes.join(", ")))
+        .collect();
+    Ok(Some(format!(
+        "Postal code {} is outside the served areas: {}",
+        zip,
+        areas.join("; ")
+    )))
+}
+
+/// Everything wrong with an address, empty when it is well-formed.
+/// Shared by commit validation and the dry-run extern so the two can
+/// never disagree.
+pub fn address_problems(address: &Address) -> ExternResult<Vec<String>> {
+    let mut problems = Vec::new();
+    if address.street.trim().is_empty() {
+        problems.push("Street must not be empty".to_string());
+    }
+    if address.city.trim().is_empty() {
+        problems.push("City must not be empty".to_string());
+    }
+    if address.zip.trim().is_empty() {
+        problems.push("Postal code must not be empty".to_string());
+    }
+    if !(-90.0..=90.0).contains(&address.lat) {
+        problems.push(format!("Latitude {} is out of range", address.lat));
+    }
+    if !(-180.0..=180.0).contains(&address.lng) {
+        problems.push(format!("Longitude {} is out of range", address.lng));
+    }
+
+    if let Some(country) = &address.country {
+        let properties =
+            DnaProperties::try_from(dna_info()?.modifiers.properties).unwrap_or_default();
+        let rule = properties
+            .postal_code_rules
+            .iter()
+            .find(|rule| rule.country.eq_ignore_ascii_case(country));
+        if let Some(rule) = rule {
+            if !rule
+                .formats
+                .iter()
+                .any(|format| matches_format(format, address.zip.trim()))
+            {
+                problems.push(format!(
+                    "Postal code \"{}\" does not match any accepted {} format",
+                    address.zip.trim(),
+                    rule.country
+                ));
+            }
+        }
+    }
+    if let Some(problem) = service_zone_problem(&address.zip)? {
+        problems.push(problem);
+    }
+    Ok(problems)
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type")]
+#[hdk_entry_types]
+#[unit_enum(UnitEntryTypes)]
+pub enum EntryTypes {
+    /// Private since addresses stopped being published to the DHT;
+    /// fulfillers read them through capability grants instead.
+    #[entry_type(visibility = "private")]
+    Address(Address),
+}
+
+#[derive(Serialize, Deserialize)]
+#[hdk_link_types]
+pub enum LinkTypes {
+    /// Agent key -> Address.
+    AgentToAddress,
+}
+
+#[hdk_extern]
+pub fn genesis_self_check(_data: GenesisSelfCheckData) -> ExternResult<ValidateCallbackResult> {
+    Ok(ValidateCallbackResult::Valid)
+}
+
+pub fn validate_address(address: Address) -> ExternResult<ValidateCallbackResult> {
+    match address_problems(&address)?.into_iter().next() {
+        Some(problem) => Ok(ValidateCallbackResult::Invalid(problem)),
+        None => Ok(ValidateCallbackResult::Valid),
+    }
+}
+
+#[hdk_extern]
+pub fn validate(op: Op) -> ExternResult<ValidateCallbackResult> {
+    match op.flattened::<EntryTypes, LinkTypes>()? {
+        FlatOp::StoreEntry(OpEntry::CreateEntry { app_entry, .. })
+        | FlatOp::StoreEntry(OpEntry::UpdateEntry { app_entry, .. }) => match app_entry {
+            EntryTypes::Address(address) => validate_address(address),
+        },
+        _ => Ok(ValidateCallbackResult::Valid),
+    }
+}
@@ -0,0 +1,102 @@
+use hdi::prelude::*;
+
+/// Longest accepted nickname, in characters.
+pub const MAX_NICKNAME_LENGTH: usize = 32;
+
+/// Largest accepted avatar, in bytes. Avatars are stored inline as a
+/// data URL, so the cap keeps profile entries small enough to gossip
+/// cheaply.
+pub const MAX_AVATAR_BYTES: usize = 64 * 1024;
+
+/// Public display identity for an agent, so orders, chat messages and
+/// shopper assignments can show a name and face instead of a raw key.
+#[derive(Clone, PartialEq)]
+#[hdk_entry_helper]
+pub struct Profile {
+    pub nickname: String,
+    /// Small image as a data URL, e.g. "data:image/png;base64,...".
+    pub avatar: Option<String>,
+}
+
+/// Everything wrong with a profile, empty when it is well-formed.
+/// Shared by commit validation and the dry-run extern so the two can
+/// never disagree.
+pub fn profile_problems(profile: &Profile) -> Vec<String> {
+    let mut problems = Vec::new();
+    let nickname = profile.nickname.trim();
+    if nickname.is_empty() {
+        problems.push("Nickname must not be empty".to_string());
+    }
+    if nickname.chars().count() > MAX_NICKNAME_LENGTH {
+        problems.push(format!(
+            "Nickname must be at most {} characters",
+            MAX_NICKNAME_LENGTH
+        ));
+    }
+    if let Some(avatar) = &profile.avatar {
+        if avatar.len() > MAX_AVATAR_BYTES {
+            problems.push(format!(
+                "Avatar must be at most {} bytes",
+                MAX_AVATAR_BYTES
+            ));
+        }
+    }
+    problems
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type")]
+#[hdk_entry_types]
+#[unit_enum(UnitEntryTypes)]
+pub enum EntryTypes {
+    Profile(Profile),
+}
+
+#[derive(Serialize, Deserialize)]
+#[hdk_link_types]
+pub enum LinkTypes {
+    /// Agent key -> Profile, repointed at the newest revision.
+    AgentToProfile,
+    /// "all_profiles" anchor -> agent key, for member listings. Links
+    /// the key rather than the entry so listings always resolve to the
+    /// newest revision through `AgentToProfile`.
+    AllProfiles,
+}
+
+#[hdk_extern]
+pub fn genesis_self_check(_data: GenesisSelfCheckData) -> ExternResult<ValidateCallbackResult> {
+    Ok(ValidateCallbackResult::Valid)
+}
+
+pub fn validate_profile(profile: Profile) -> ExternResult<ValidateCallbackResult> {
+    match profile_problems(&profile).into_iter().next() {
+        Some(problem) => Ok(ValidateCallbackResult::Invalid(problem)),
+        None => Ok(ValidateCallbackResult::Valid),
+    }
+}
+
+#[hdk_extern]
+pub fn validate(op: Op) -> ExternResult<ValidateCallbackResult> {
+    match op.flattened::<EntryTypes, LinkTypes>()? {
+        FlatOp::StoreEntry(OpEntry::CreateEntry { app_entry, .. })
+        | FlatOp::StoreEntry(OpEntry::UpdateEntry { app_entry, .. }) => match app_entry {
+            EntryTypes::Profile(profile) => validate_profile(profile),
+        },
+        // A profile speaks for the agent it hangs off; only that agent
+        // may hang it there.
+        FlatOp::RegisterCreateLink {
+            link_type: LinkTypes::AgentToProfile,
+            base_address,
+            action,
+            ..
+        } => {
+            if base_address != action.author.clone().into() {
+                return Ok(ValidateCallbackResult::Invalid(
+                    "Agents may only link a profile from their own key".to_string(),
+                ));
+            }
+            Ok(ValidateCallbackResult::Valid)
+        }
+        _ => Ok(ValidateCallbackResult::Valid),
+    }
+}